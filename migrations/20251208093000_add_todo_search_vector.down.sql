@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_todo_search_vector;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- 全文检索向量，由服务端使用 jieba 分词后写入（标题权重 A，描述权重 B，摘要权重 C）
+ALTER TABLE todo_list ADD COLUMN search_vector TSVECTOR;
+
+-- 用于全文检索的GIN索引
+CREATE INDEX idx_todo_search_vector ON todo_list USING GIN(search_vector);
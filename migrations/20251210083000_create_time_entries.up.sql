@@ -0,0 +1,31 @@
+-- Add up migration script here
+-- 创建计时记录表，ended_at 为空表示正在计时
+CREATE TABLE time_entries (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    todo_id INTEGER NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    ended_at TIMESTAMP WITH TIME ZONE,
+    source VARCHAR(10) NOT NULL DEFAULT 'timer' CHECK (source IN ('timer', 'manual')), -- 计时器记录或手动补录
+    paused BOOLEAN NOT NULL DEFAULT false, -- 暂停时结束的记录，可以继续计时
+    note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_time_entry_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_time_entry_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT chk_time_entry_range CHECK (ended_at IS NULL OR ended_at >= started_at)
+);
+
+-- 创建更新时间触发器
+CREATE TRIGGER update_time_entries_updated_at
+    BEFORE UPDATE ON time_entries
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 创建索引
+CREATE INDEX idx_time_entries_todo_id ON time_entries(todo_id);
+CREATE INDEX idx_time_entries_user_started ON time_entries(user_id, started_at);
+-- 每个用户同一时间只能有一个正在计时的记录
+CREATE UNIQUE INDEX uq_time_entries_running ON time_entries(user_id) WHERE ended_at IS NULL;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_todo_recurrence_id;
+ALTER TABLE todo_list DROP CONSTRAINT IF EXISTS fk_todo_recurrence;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS recurrence_id;
+DROP TABLE IF EXISTS todo_recurrences;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- 创建重复规则表，一条规则对应一个重复系列，同一时间只有一个未完成的实例
+CREATE TABLE todo_recurrences (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    rrule VARCHAR(255) NOT NULL, -- RFC 5545 RRULE，例如 FREQ=WEEKLY;BYDAY=MO,WE
+    dtstart TIMESTAMP WITH TIME ZONE NOT NULL, -- 第一次重复的截止时间，规则从这里开始展开
+    current_todo_id INTEGER, -- 当前正在进行的实例
+    ended_at TIMESTAMP WITH TIME ZONE, -- 系列结束时间，不为空时不再生成新的实例
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_recurrence_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_recurrence_current_todo FOREIGN KEY (current_todo_id) REFERENCES todo_list(id) ON DELETE SET NULL
+);
+
+-- 创建更新时间触发器
+CREATE TRIGGER update_todo_recurrences_updated_at
+    BEFORE UPDATE ON todo_recurrences
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 创建索引
+CREATE INDEX idx_recurrences_user_id ON todo_recurrences(user_id);
+CREATE INDEX idx_recurrences_current_todo_id ON todo_recurrences(current_todo_id);
+
+-- 待办事项所属的重复系列
+ALTER TABLE todo_list ADD COLUMN recurrence_id INTEGER;
+ALTER TABLE todo_list ADD CONSTRAINT fk_todo_recurrence
+    FOREIGN KEY (recurrence_id) REFERENCES todo_recurrences(id) ON DELETE SET NULL;
+CREATE INDEX idx_todo_recurrence_id ON todo_list(recurrence_id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS reminder_deliveries;
+DROP TABLE IF EXISTS todo_reminders;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- 创建提醒表，每条记录表示在截止时间之前多少分钟提醒一次
+CREATE TABLE todo_reminders (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    todo_id INTEGER NOT NULL,
+    offset_minutes INTEGER NOT NULL CHECK (offset_minutes >= 0), -- 提前提醒的分钟数，0 表示到期时提醒
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_reminder_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_reminder_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT uq_reminder_todo_offset UNIQUE (todo_id, offset_minutes)
+);
+
+-- 创建提醒发送记录表，同一个提醒针对同一个截止时间只发送一次，修改截止时间后会重新提醒
+CREATE TABLE reminder_deliveries (
+    id SERIAL PRIMARY KEY,
+    reminder_id INTEGER NOT NULL,
+    due_date TIMESTAMP WITH TIME ZONE NOT NULL, -- 发送时任务的截止时间
+    status VARCHAR(10) NOT NULL DEFAULT 'sending' CHECK (status IN ('sending', 'sent', 'failed')),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+
+    -- 外键约束
+    CONSTRAINT fk_delivery_reminder FOREIGN KEY (reminder_id) REFERENCES todo_reminders(id) ON DELETE CASCADE,
+    CONSTRAINT uq_delivery_reminder_due UNIQUE (reminder_id, due_date)
+);
+
+-- 创建索引
+CREATE INDEX idx_reminders_todo_id ON todo_reminders(todo_id);
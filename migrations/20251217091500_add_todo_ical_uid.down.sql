@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS uq_todo_user_ical_uid;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS ical_uid;
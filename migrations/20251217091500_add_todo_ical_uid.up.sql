@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- iCalendar UID，导入导出时用于识别同一个待办事项
+ALTER TABLE todo_list ADD COLUMN ical_uid VARCHAR(255);
+UPDATE todo_list SET ical_uid = gen_random_uuid()::TEXT WHERE ical_uid IS NULL;
+ALTER TABLE todo_list ALTER COLUMN ical_uid SET DEFAULT gen_random_uuid()::TEXT;
+ALTER TABLE todo_list ALTER COLUMN ical_uid SET NOT NULL;
+
+-- 同一个用户下 UID 唯一
+CREATE UNIQUE INDEX uq_todo_user_ical_uid ON todo_list(user_id, ical_uid);
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- 创建日历订阅表，客户端凭令牌访问订阅地址，数据库中只保存令牌的 SHA-256 摘要
+CREATE TABLE calendar_feeds (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL, -- 订阅名称，方便用户区分不同的客户端
+    token_hash CHAR(64) NOT NULL, -- 令牌的 SHA-256 摘要（十六进制）
+    token_prefix VARCHAR(8) NOT NULL, -- 令牌的前几位，用于展示
+    content_digest CHAR(32), -- 上次访问时订阅内容的摘要，用于判断内容是否变化
+    content_modified_at TIMESTAMP WITH TIME ZONE, -- 订阅内容最后一次变化的时间（包括删除待办事项）
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_calendar_feed_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT uq_calendar_feed_token_hash UNIQUE (token_hash)
+);
+
+-- 创建索引
+CREATE INDEX idx_calendar_feeds_user_id ON calendar_feeds(user_id);
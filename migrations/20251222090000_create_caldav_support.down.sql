@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS uq_todo_user_caldav_name;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS caldav_name;
+DROP TABLE IF EXISTS app_passwords;
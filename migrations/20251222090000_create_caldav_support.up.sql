@@ -0,0 +1,22 @@
+-- Add up migration script here
+-- 创建应用专用密码表，CalDAV 等客户端使用 HTTP Basic 认证时可以代替登陆密码
+-- 密码由服务端随机生成，数据库中只保存 SHA-256 摘要
+CREATE TABLE app_passwords (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL, -- 名称，方便用户区分不同的客户端
+    password_hash CHAR(64) NOT NULL, -- 密码的 SHA-256 摘要（十六进制）
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_app_password_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT uq_app_password_hash UNIQUE (password_hash)
+);
+
+-- 创建索引
+CREATE INDEX idx_app_passwords_user_id ON app_passwords(user_id);
+
+-- CalDAV 资源名，客户端创建时自己决定，为空时使用 `{ical_uid}.ics`
+ALTER TABLE todo_list ADD COLUMN caldav_name VARCHAR(255);
+CREATE UNIQUE INDEX uq_todo_user_caldav_name ON todo_list(user_id, caldav_name) WHERE caldav_name IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+-- 回滚前彻底删除回收站中的数据，否则无法恢复全表唯一索引
+DELETE FROM todo_list WHERE deleted_at IS NOT NULL;
+DROP INDEX IF EXISTS uq_todo_user_caldav_name;
+CREATE UNIQUE INDEX uq_todo_user_caldav_name ON todo_list(user_id, caldav_name) WHERE caldav_name IS NOT NULL;
+DROP INDEX IF EXISTS uq_todo_user_ical_uid;
+CREATE UNIQUE INDEX uq_todo_user_ical_uid ON todo_list(user_id, ical_uid);
+DROP INDEX IF EXISTS idx_todo_deleted_at;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS deleted_at;
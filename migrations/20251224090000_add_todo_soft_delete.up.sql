@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- 软删除：删除待办事项时只记录删除时间，整棵子树一起进入回收站
+-- 回收站中超过保留期的待办事项由后台任务彻底删除
+ALTER TABLE todo_list ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+CREATE INDEX idx_todo_deleted_at ON todo_list(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- 回收站中的待办事项不再占用 UID 和 CalDAV 资源名，恢复时如有冲突会重新生成
+DROP INDEX IF EXISTS uq_todo_user_ical_uid;
+CREATE UNIQUE INDEX uq_todo_user_ical_uid ON todo_list(user_id, ical_uid) WHERE deleted_at IS NULL;
+DROP INDEX IF EXISTS uq_todo_user_caldav_name;
+CREATE UNIQUE INDEX uq_todo_user_caldav_name ON todo_list(user_id, caldav_name)
+    WHERE caldav_name IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS record_todo_history ON todo_list;
+DROP FUNCTION IF EXISTS record_todo_history();
+DROP TABLE IF EXISTS todo_history;
@@ -0,0 +1,72 @@
+-- Add up migration script here
+-- 待办事项的修改历史，由触发器在每次新增、修改（包括移入和移出回收站）后写入
+CREATE TABLE IF NOT EXISTS todo_history (
+    id BIGSERIAL PRIMARY KEY,
+    todo_id INTEGER NOT NULL, -- 待办事项
+    user_id INTEGER NOT NULL, -- 待办事项所属的用户
+    revision INTEGER NOT NULL, -- 版本号，同一个待办事项从 1 开始递增
+    action VARCHAR(20) NOT NULL, -- create / update / delete / restore
+    changes JSONB NOT NULL, -- 字段级差异：{"字段": {"old": 旧值, "new": 新值}}
+    snapshot JSONB NOT NULL, -- 修改后的完整数据，用于回滚
+    actor_id INTEGER, -- 操作人，后台任务等系统操作为空
+    request_id VARCHAR(64), -- 请求 id，对应响应头 x-request-id
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT fk_todo_history_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_history_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT uq_todo_history_revision UNIQUE (todo_id, revision)
+);
+
+-- 记录一次修改
+-- 操作人和请求 id 由应用在事务开始时通过 set_config('todo.actor_id' / 'todo.request_id', ..., true) 传入；
+-- 排序值、汇总用时、检索向量和更新时间由系统维护，它们的变化不计入历史。
+CREATE OR REPLACE FUNCTION record_todo_history()
+RETURNS TRIGGER AS $$
+DECLARE
+    ignored TEXT[] := ARRAY['id', 'search_vector', 'updated_at', 'sort_order', 'actual_time'];
+    new_row JSONB := to_jsonb(NEW) - ignored;
+    old_row JSONB;
+    diff JSONB;
+    history_action VARCHAR(20);
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        history_action := 'create';
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', NULL, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        WHERE n.value <> 'null'::JSONB;
+    ELSE
+        old_row := to_jsonb(OLD) - ignored;
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', o.value, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        JOIN jsonb_each(old_row) o ON o.key = n.key
+        WHERE n.value IS DISTINCT FROM o.value;
+        IF diff = '{}'::JSONB THEN
+            RETURN NULL;
+        END IF;
+        history_action := CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END;
+    END IF;
+    INSERT INTO todo_history (todo_id, user_id, revision, action, changes, snapshot, actor_id, request_id)
+    VALUES (
+        NEW.id,
+        NEW.user_id,
+        (SELECT COALESCE(MAX(revision), 0) + 1 FROM todo_history WHERE todo_id = NEW.id),
+        history_action,
+        diff,
+        to_jsonb(NEW) - 'search_vector',
+        NULLIF(current_setting('todo.actor_id', true), '')::INTEGER,
+        NULLIF(current_setting('todo.request_id', true), '')
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_todo_history
+    AFTER INSERT OR UPDATE ON todo_list
+    FOR EACH ROW
+    EXECUTE FUNCTION record_todo_history();
@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_todo_history_request_id;
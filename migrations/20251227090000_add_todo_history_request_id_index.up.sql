@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- 撤销时按请求 id 查询该请求产生的全部修改
+CREATE INDEX IF NOT EXISTS idx_todo_history_request_id ON todo_history(request_id) WHERE request_id IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS idx_todo_project_id;
+ALTER TABLE todo_list DROP CONSTRAINT IF EXISTS fk_todo_project;
+ALTER TABLE todo_list DROP COLUMN IF EXISTS project_id;
+DROP TABLE IF EXISTS projects;
@@ -0,0 +1,29 @@
+-- Add up migration script here
+-- 项目：对待办事项分组，子任务跟随顶层任务所在的项目
+CREATE TABLE IF NOT EXISTS projects (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    color CHAR(7), -- 颜色，#RRGGBB
+    icon VARCHAR(50), -- 图标名称或 emoji，由客户端解释
+    archived BOOLEAN NOT NULL DEFAULT false, -- 归档后项目中的待办事项不再出现在默认视图中
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_project_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT uq_project_user_name UNIQUE (user_id, name)
+);
+
+-- 创建更新时间触发器
+CREATE TRIGGER update_projects_updated_at
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 删除项目时其中的待办事项回到收件箱（不属于任何项目）
+ALTER TABLE todo_list ADD COLUMN project_id INTEGER;
+ALTER TABLE todo_list ADD CONSTRAINT fk_todo_project
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL;
+CREATE INDEX idx_todo_project_id ON todo_list(project_id) WHERE project_id IS NOT NULL;
@@ -0,0 +1,35 @@
+-- Add up migration script here
+-- 协作：把一个待办事项（连同子任务）或整个项目共享给其他用户
+-- 邀请创建后处于待接受状态（accepted_at 为空），被邀请人接受后才生效
+CREATE TABLE IF NOT EXISTS todo_shares (
+    id SERIAL PRIMARY KEY,
+    owner_id INTEGER NOT NULL, -- 被共享数据的创建者
+    member_id INTEGER NOT NULL, -- 被邀请的用户
+    todo_id INTEGER, -- 共享的待办事项，与 project_id 二选一
+    project_id INTEGER, -- 共享的项目
+    role VARCHAR(10) NOT NULL CHECK (role IN ('viewer', 'editor', 'owner')),
+    invited_by INTEGER NOT NULL, -- 发出邀请的用户，可以是创建者或其他所有者
+    accepted_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_todo_share_owner FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_share_member FOREIGN KEY (member_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_share_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_share_project FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_share_inviter FOREIGN KEY (invited_by) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT ck_todo_share_target CHECK ((todo_id IS NULL) <> (project_id IS NULL)),
+    CONSTRAINT ck_todo_share_member CHECK (member_id <> owner_id)
+);
+
+-- 创建更新时间触发器
+CREATE TRIGGER update_todo_shares_updated_at
+    BEFORE UPDATE ON todo_shares
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- 同一个成员对同一个待办事项或项目只有一条共享记录
+CREATE UNIQUE INDEX uq_todo_share_todo_member ON todo_shares(todo_id, member_id) WHERE todo_id IS NOT NULL;
+CREATE UNIQUE INDEX uq_todo_share_project_member ON todo_shares(project_id, member_id) WHERE project_id IS NOT NULL;
+CREATE INDEX idx_todo_shares_member_id ON todo_shares(member_id);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS todo_comment_mentions;
+DROP TABLE IF EXISTS todo_comment_edits;
+DROP TABLE IF EXISTS todo_comments;
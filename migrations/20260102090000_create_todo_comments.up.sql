@@ -0,0 +1,52 @@
+-- Add up migration script here
+-- 待办事项的评论，正文为 Markdown，回复挂在顶层评论下形成讨论串
+CREATE TABLE IF NOT EXISTS todo_comments (
+    id SERIAL PRIMARY KEY,
+    todo_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL, -- 评论人
+    parent_id INTEGER, -- 回复的顶层评论，为空表示顶层评论
+    body TEXT NOT NULL, -- Markdown 正文，删除后清空
+    edited_at TIMESTAMP WITH TIME ZONE, -- 最近一次编辑的时间，为空表示没有编辑过
+    deleted_at TIMESTAMP WITH TIME ZONE, -- 有回复的评论删除后保留占位
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_todo_comment_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_comment_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_comment_parent FOREIGN KEY (parent_id) REFERENCES todo_comments(id) ON DELETE CASCADE
+);
+
+-- 创建更新时间触发器
+CREATE TRIGGER update_todo_comments_updated_at
+    BEFORE UPDATE ON todo_comments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE INDEX idx_todo_comments_todo_id ON todo_comments(todo_id, id);
+CREATE INDEX idx_todo_comments_parent_id ON todo_comments(parent_id);
+
+-- 评论的编辑历史，每次编辑保存编辑前的正文
+CREATE TABLE IF NOT EXISTS todo_comment_edits (
+    id SERIAL PRIMARY KEY,
+    comment_id INTEGER NOT NULL,
+    body TEXT NOT NULL, -- 编辑前的正文
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP, -- 编辑时间
+
+    CONSTRAINT fk_todo_comment_edit_comment FOREIGN KEY (comment_id) REFERENCES todo_comments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_todo_comment_edits_comment_id ON todo_comment_edits(comment_id, id);
+
+-- 评论中 @ 到的用户，只记录能看到该待办事项的用户
+CREATE TABLE IF NOT EXISTS todo_comment_mentions (
+    comment_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (comment_id, user_id),
+    CONSTRAINT fk_todo_comment_mention_comment FOREIGN KEY (comment_id) REFERENCES todo_comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_comment_mention_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_todo_comment_mentions_user_id ON todo_comment_mentions(user_id, comment_id);
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- 待办事项的附件元数据，文件内容按 SHA-256 存放在本地目录，相同内容只存一份
+CREATE TABLE IF NOT EXISTS todo_attachments (
+    id SERIAL PRIMARY KEY,
+    todo_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL, -- 上传人，附件大小计入上传人的存储配额
+    file_name VARCHAR(255) NOT NULL, -- 上传时的文件名，下载时原样返回
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL CHECK (size >= 0), -- 文件大小（字节）
+    sha256 CHAR(64) NOT NULL, -- 文件内容的 SHA-256（小写十六进制），同时是存储路径
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+
+    -- 外键约束
+    CONSTRAINT fk_todo_attachment_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE,
+    CONSTRAINT fk_todo_attachment_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_todo_attachments_todo_id ON todo_attachments(todo_id);
+CREATE INDEX idx_todo_attachments_user_id ON todo_attachments(user_id);
+CREATE INDEX idx_todo_attachments_sha256 ON todo_attachments(sha256);
@@ -0,0 +1,58 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS record_todo_history ON todo_list;
+
+-- 恢复外键前删除已经彻底删除的待办事项的历史
+DELETE FROM todo_history h WHERE NOT EXISTS (SELECT 1 FROM todo_list t WHERE t.id = h.todo_id);
+ALTER TABLE todo_history
+    ADD CONSTRAINT fk_todo_history_todo FOREIGN KEY (todo_id) REFERENCES todo_list(id) ON DELETE CASCADE;
+
+CREATE OR REPLACE FUNCTION record_todo_history()
+RETURNS TRIGGER AS $$
+DECLARE
+    ignored TEXT[] := ARRAY['id', 'search_vector', 'updated_at', 'sort_order', 'actual_time'];
+    new_row JSONB := to_jsonb(NEW) - ignored;
+    old_row JSONB;
+    diff JSONB;
+    history_action VARCHAR(20);
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        history_action := 'create';
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', NULL, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        WHERE n.value <> 'null'::JSONB;
+    ELSE
+        old_row := to_jsonb(OLD) - ignored;
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', o.value, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        JOIN jsonb_each(old_row) o ON o.key = n.key
+        WHERE n.value IS DISTINCT FROM o.value;
+        IF diff = '{}'::JSONB THEN
+            RETURN NULL;
+        END IF;
+        history_action := CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END;
+    END IF;
+    INSERT INTO todo_history (todo_id, user_id, revision, action, changes, snapshot, actor_id, request_id)
+    VALUES (
+        NEW.id,
+        NEW.user_id,
+        (SELECT COALESCE(MAX(revision), 0) + 1 FROM todo_history WHERE todo_id = NEW.id),
+        history_action,
+        diff,
+        to_jsonb(NEW) - 'search_vector',
+        NULLIF(current_setting('todo.actor_id', true), '')::INTEGER,
+        NULLIF(current_setting('todo.request_id', true), '')
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_todo_history
+    AFTER INSERT OR UPDATE ON todo_list
+    FOR EACH ROW
+    EXECUTE FUNCTION record_todo_history();
@@ -0,0 +1,70 @@
+-- Add up migration script here
+-- 彻底删除待办事项（回收站清理、清空回收站）时也记录一条 purge 历史；
+-- 历史不再随待办事项级联删除，彻底删除后仍然保留，只有删除用户时才一起删除。
+ALTER TABLE todo_history DROP CONSTRAINT IF EXISTS fk_todo_history_todo;
+
+CREATE OR REPLACE FUNCTION record_todo_history()
+RETURNS TRIGGER AS $$
+DECLARE
+    ignored TEXT[] := ARRAY['id', 'search_vector', 'updated_at', 'sort_order', 'actual_time'];
+    new_row JSONB;
+    old_row JSONB;
+    diff JSONB;
+    history_action VARCHAR(20);
+    target todo_list;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        -- 删除用户时级联删除的待办事项不记录，历史会随用户一起删除
+        IF NOT EXISTS (SELECT 1 FROM users WHERE id = OLD.user_id) THEN
+            RETURN NULL;
+        END IF;
+        target := OLD;
+        history_action := 'purge';
+        -- 彻底删除无法撤销，不记录字段差异，快照保存删除前的完整数据
+        diff := '{}'::JSONB;
+    ELSIF TG_OP = 'INSERT' THEN
+        target := NEW;
+        history_action := 'create';
+        new_row := to_jsonb(NEW) - ignored;
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', NULL, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        WHERE n.value <> 'null'::JSONB;
+    ELSE
+        target := NEW;
+        new_row := to_jsonb(NEW) - ignored;
+        old_row := to_jsonb(OLD) - ignored;
+        SELECT COALESCE(jsonb_object_agg(n.key, jsonb_build_object('old', o.value, 'new', n.value)), '{}')
+        INTO diff
+        FROM jsonb_each(new_row) n
+        JOIN jsonb_each(old_row) o ON o.key = n.key
+        WHERE n.value IS DISTINCT FROM o.value;
+        IF diff = '{}'::JSONB THEN
+            RETURN NULL;
+        END IF;
+        history_action := CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'delete'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restore'
+            ELSE 'update'
+        END;
+    END IF;
+    INSERT INTO todo_history (todo_id, user_id, revision, action, changes, snapshot, actor_id, request_id)
+    VALUES (
+        target.id,
+        target.user_id,
+        (SELECT COALESCE(MAX(revision), 0) + 1 FROM todo_history WHERE todo_id = target.id),
+        history_action,
+        diff,
+        to_jsonb(target) - 'search_vector',
+        NULLIF(current_setting('todo.actor_id', true), '')::INTEGER,
+        NULLIF(current_setting('todo.request_id', true), '')
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS record_todo_history ON todo_list;
+CREATE TRIGGER record_todo_history
+    AFTER INSERT OR UPDATE OR DELETE ON todo_list
+    FOR EACH ROW
+    EXECUTE FUNCTION record_todo_history();
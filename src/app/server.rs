@@ -1,11 +1,15 @@
+use crate::jobs::BackgroundJobs;
+use crate::middlewares::request_id::make_request_id::{MakeRequestXid, REQUEST_ID_HEADER};
 use crate::state::app_state::AppState;
 use crate::utils::latency::LatencyOnResponse;
 use crate::{conf, middlewares, router};
 use axum::extract::DefaultBodyLimit;
-use axum::http::{Request, StatusCode};
+use axum::http::{HeaderName, Request, StatusCode};
+use axum::response::Redirect;
 use bytesize::ByteSize;
 use std::net::SocketAddr;
 use tower_http::normalize_path::NormalizePathLayer;
+use tower_http::request_id::{PropagateRequestIdLayer, RequestId, SetRequestIdLayer};
 use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
 /// 服务端配置信息
@@ -23,6 +27,13 @@ impl Server {
     pub async fn start_server(&self) -> anyhow::Result<()> {
         // new app state 创建 app 数据状态对象
         let app_state = AppState::new().await;
+        // start background jobs 启动后台任务
+        let background_jobs = BackgroundJobs::spawn(
+            &app_state,
+            self.server_config.reminder(),
+            self.server_config.trash(),
+            self.server_config.attachment(),
+        );
         // create our application router 创建路由
         let app_router = self.build_router(app_state).await;
         // use axum to serve our application, listening on the specified address
@@ -44,6 +55,8 @@ impl Server {
         )
         .with_graceful_shutdown(shutdown_signal())
         .await?;
+        // stop background jobs after the server stopped 服务停止后再关闭后台任务
+        background_jobs.shutdown().await;
         // this point the application has stopped, so we can return
         tracing::info!("✅ server terminated gracefully");
         Ok(())
@@ -69,22 +82,40 @@ impl Server {
             .make_span_with(|request: &Request<axum::body::Body>| {
                 let method = request.method();
                 let path = request.uri().path();
-                let id = xid::new();
+                let id = request
+                    .extensions()
+                    .get::<RequestId>()
+                    .and_then(|id| id.header_value().to_str().ok())
+                    .unwrap_or_default();
                 tracing::info_span!("Api Request", id = %id, method = %method, path = %path)
             })
             .on_request(())
             .on_failure(())
             .on_response(LatencyOnResponse);
 
+        // request id 请求 id，写入响应头 x-request-id，并记录到日志和修改历史中
+        let request_id_header = HeaderName::from_static(REQUEST_ID_HEADER);
+        let set_request_id = SetRequestIdLayer::new(request_id_header.clone(), MakeRequestXid);
+        let propagate_request_id = PropagateRequestIdLayer::new(request_id_header);
+
         // trim trailing slash  /api/ ===> /api 去除后面的 “/”
         let normalize_path = NormalizePathLayer::trim_trailing_slash();
 
         // return the router 返回路由
         axum::Router::new()
-            .nest("/v1/api", router::merge_router())
+            .nest(router::API_PREFIX, router::merge_router())
+            // CalDAV 客户端通过 /.well-known/caldav 发现服务地址（RFC 6764）
+            .route(
+                "/.well-known/caldav",
+                axum::routing::any(|| async {
+                    Redirect::permanent(&format!("{}/caldav/", router::API_PREFIX))
+                }),
+            )
             .layer(timeout)
             .layer(body_size_limit)
             .layer(tracing)
+            .layer(propagate_request_id)
+            .layer(set_request_id)
             .layer(cors_layer)
             .layer(normalize_path)
             .with_state(state)
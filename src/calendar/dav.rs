@@ -0,0 +1,374 @@
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use roxmltree::{Document, Node};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::fmt::Write;
+
+/// WebDAV 命名空间（RFC 4918）
+pub const NS_DAV: &str = "DAV:";
+/// CalDAV 命名空间（RFC 4791）
+pub const NS_CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+/// Apple CalendarServer 扩展命名空间（getctag）
+pub const NS_CALSERVER: &str = "http://calendarserver.org/ns/";
+
+/// 带命名空间的 XML 元素名，用于表示属性、报告类型等
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DavName {
+    pub ns: String,
+    pub name: String,
+}
+
+impl DavName {
+    pub fn new(ns: &str, name: &str) -> Self {
+        Self {
+            ns: ns.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn is(&self, ns: &str, name: &str) -> bool {
+        self.ns == ns && self.name == name
+    }
+
+    fn of(node: Node) -> Self {
+        Self::new(
+            node.tag_name().namespace().unwrap_or(""),
+            node.tag_name().name(),
+        )
+    }
+
+    /// 输出时使用的前缀，未知命名空间返回空
+    fn prefix(&self) -> Option<&'static str> {
+        match self.ns.as_str() {
+            NS_DAV => Some("d"),
+            NS_CALDAV => Some("c"),
+            NS_CALSERVER => Some("cs"),
+            _ => None,
+        }
+    }
+
+    /// 输出元素，内容为已经转义好的 XML 片段
+    fn write_element(&self, out: &mut String, inner: &str) {
+        let (open, close) = match self.prefix() {
+            Some(prefix) => (
+                format!("{prefix}:{}", self.name),
+                format!("{prefix}:{}", self.name),
+            ),
+            None => (
+                format!("x:{} xmlns:x=\"{}\"", self.name, escape_xml(&self.ns)),
+                format!("x:{}", self.name),
+            ),
+        };
+        if inner.is_empty() {
+            let _ = write!(out, "<{open}/>");
+        } else {
+            let _ = write!(out, "<{open}>{inner}</{close}>");
+        }
+    }
+}
+
+/// PROPFIND 请求要查询的属性
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropRequest {
+    /// 全部常用属性（请求体为空时也是这个）
+    AllProp,
+    /// 只列出属性名
+    PropName,
+    /// 指定的属性
+    Prop(Vec<DavName>),
+}
+
+/// CalDAV 的文本匹配条件（只支持子串匹配，不区分大小写）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    pub value: String,
+    pub negate: bool,
+}
+
+/// calendar-query 的过滤条件，只解析待办事项能用到的部分
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalendarFilter {
+    /// 过滤条件是否指向 VTODO，指向其他组件时结果为空
+    pub vtodo: bool,
+    /// time-range 的开始时间
+    pub start: Option<DateTimeWithTimeZone>,
+    /// time-range 的结束时间
+    pub end: Option<DateTimeWithTimeZone>,
+    /// COMPLETED 属性是否存在，is-not-defined 时为 Some(false)
+    pub completed: Option<bool>,
+    /// STATUS 属性的文本匹配
+    pub status: Option<TextMatch>,
+}
+
+/// REPORT 请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportRequest {
+    CalendarQuery {
+        props: PropRequest,
+        filter: CalendarFilter,
+    },
+    CalendarMultiget {
+        props: PropRequest,
+        hrefs: Vec<String>,
+    },
+    /// 不支持的报告类型
+    Unsupported(DavName),
+}
+
+fn children<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|n| n.is_element())
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+    children(node).find(|n| DavName::of(*n).is(ns, name))
+}
+
+/// 解析 prop / allprop / propname 元素
+fn parse_prop_request(parent: Node) -> PropRequest {
+    if child(parent, NS_DAV, "propname").is_some() {
+        return PropRequest::PropName;
+    }
+    match child(parent, NS_DAV, "prop") {
+        Some(prop) => PropRequest::Prop(children(prop).map(DavName::of).collect()),
+        None => PropRequest::AllProp,
+    }
+}
+
+/// 解析 PROPFIND 请求体，请求体为空时按 allprop 处理
+pub fn parse_propfind(body: &str) -> Result<PropRequest, String> {
+    if body.trim().is_empty() {
+        return Ok(PropRequest::AllProp);
+    }
+    let doc = Document::parse(body).map_err(|e| format!("无效的 XML：{e}"))?;
+    let root = doc.root_element();
+    if !DavName::of(root).is(NS_DAV, "propfind") {
+        return Err(String::from("请求体必须是 DAV:propfind"));
+    }
+    Ok(parse_prop_request(root))
+}
+
+/// 解析 time-range 的时间，格式为 UTC 的 `20250101T000000Z`
+fn parse_utc(value: &str) -> Result<DateTimeWithTimeZone, String> {
+    NaiveDateTime::parse_from_str(value.trim().trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .map(|dt| Utc.from_utc_datetime(&dt).fixed_offset())
+        .map_err(|_| format!("无效的 time-range：{value}"))
+}
+
+/// 解析 VTODO 的 comp-filter
+fn parse_vtodo_filter(node: Node, filter: &mut CalendarFilter) -> Result<(), String> {
+    for item in children(node) {
+        let name = DavName::of(item);
+        if name.is(NS_CALDAV, "time-range") {
+            filter.start = item.attribute("start").map(parse_utc).transpose()?;
+            filter.end = item.attribute("end").map(parse_utc).transpose()?;
+        } else if name.is(NS_CALDAV, "prop-filter") {
+            let prop = item.attribute("name").unwrap_or("").to_ascii_uppercase();
+            let not_defined = child(item, NS_CALDAV, "is-not-defined").is_some();
+            match prop.as_str() {
+                "COMPLETED" => filter.completed = Some(!not_defined),
+                "STATUS" => {
+                    if let Some(text_match) = child(item, NS_CALDAV, "text-match") {
+                        filter.status = Some(TextMatch {
+                            value: text_match.text().unwrap_or("").trim().to_string(),
+                            negate: text_match.attribute("negate-condition") == Some("yes"),
+                        });
+                    }
+                }
+                // 其他属性的过滤条件不支持，返回的结果会多于请求的范围
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 解析 calendar-query 的 filter，只支持 VCALENDAR > VTODO 结构
+fn parse_filter(node: Option<Node>) -> Result<CalendarFilter, String> {
+    let mut filter = CalendarFilter::default();
+    let Some(calendar) = node.and_then(|n| child(n, NS_CALDAV, "comp-filter")) else {
+        // 没有过滤条件时返回全部待办事项
+        filter.vtodo = true;
+        return Ok(filter);
+    };
+    if !calendar
+        .attribute("name")
+        .is_some_and(|n| n.eq_ignore_ascii_case("VCALENDAR"))
+    {
+        return Ok(filter);
+    }
+    let components: Vec<Node> = children(calendar)
+        .filter(|n| DavName::of(*n).is(NS_CALDAV, "comp-filter"))
+        .collect();
+    if components.is_empty() {
+        filter.vtodo = true;
+        return Ok(filter);
+    }
+    if let Some(vtodo) = components.iter().find(|n| {
+        n.attribute("name")
+            .is_some_and(|name| name.eq_ignore_ascii_case("VTODO"))
+    }) {
+        filter.vtodo = true;
+        parse_vtodo_filter(*vtodo, &mut filter)?;
+    }
+    Ok(filter)
+}
+
+/// 解析 REPORT 请求体
+pub fn parse_report(body: &str) -> Result<ReportRequest, String> {
+    let doc = Document::parse(body).map_err(|e| format!("无效的 XML：{e}"))?;
+    let root = doc.root_element();
+    let name = DavName::of(root);
+    if name.is(NS_CALDAV, "calendar-query") {
+        Ok(ReportRequest::CalendarQuery {
+            props: parse_prop_request(root),
+            filter: parse_filter(child(root, NS_CALDAV, "filter"))?,
+        })
+    } else if name.is(NS_CALDAV, "calendar-multiget") {
+        Ok(ReportRequest::CalendarMultiget {
+            props: parse_prop_request(root),
+            hrefs: children(root)
+                .filter(|n| DavName::of(*n).is(NS_DAV, "href"))
+                .filter_map(|n| n.text())
+                .map(|href| href.trim().to_string())
+                .collect(),
+        })
+    } else {
+        Ok(ReportRequest::Unsupported(name))
+    }
+}
+
+/// 转义 XML 文本和属性值
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 只包含一个 href 的属性值，例如 current-user-principal
+pub fn href_value(href: &str) -> String {
+    format!("<d:href>{}</d:href>", escape_xml(href))
+}
+
+/// 生成 207 Multi-Status 响应体
+#[derive(Debug)]
+pub struct Multistatus {
+    out: String,
+}
+
+impl Default for Multistatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multistatus {
+    pub fn new() -> Self {
+        let mut out = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        let _ = write!(
+            out,
+            r#"<d:multistatus xmlns:d="{NS_DAV}" xmlns:c="{NS_CALDAV}" xmlns:cs="{NS_CALSERVER}">"#
+        );
+        Self { out }
+    }
+
+    fn propstat(&mut self, status: &str, props: impl Iterator<Item = (DavName, String)>) {
+        self.out.push_str("<d:propstat><d:prop>");
+        for (name, value) in props {
+            name.write_element(&mut self.out, &value);
+        }
+        let _ = write!(
+            self.out,
+            "</d:prop><d:status>HTTP/1.1 {status}</d:status></d:propstat>"
+        );
+    }
+
+    /// 输出一个资源的属性，找不到的属性以 404 返回
+    ///
+    /// # 参数
+    /// - href: 资源地址
+    /// - found: 属性名和已经转义好的属性值
+    /// - missing: 不支持或不存在的属性
+    pub fn response(&mut self, href: &str, found: Vec<(DavName, String)>, missing: Vec<DavName>) {
+        let _ = write!(
+            self.out,
+            "<d:response><d:href>{}</d:href>",
+            escape_xml(href)
+        );
+        if !found.is_empty() {
+            self.propstat("200 OK", found.into_iter());
+        }
+        if !missing.is_empty() {
+            self.propstat(
+                "404 Not Found",
+                missing.into_iter().map(|name| (name, String::new())),
+            );
+        }
+        self.out.push_str("</d:response>");
+    }
+
+    /// 输出只有状态的资源，例如 multiget 中不存在的资源
+    pub fn response_status(&mut self, href: &str, status: &str) {
+        let _ = write!(
+            self.out,
+            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 {status}</d:status></d:response>",
+            escape_xml(href)
+        );
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("</d:multistatus>");
+        self.out
+    }
+}
+
+/// 生成 WebDAV 错误响应体，例如 `<c:no-uid-conflict/>`
+pub fn error_body(condition: &DavName) -> String {
+    let mut out = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?><d:error xmlns:d="{NS_DAV}" xmlns:c="{NS_CALDAV}">"#
+    );
+    condition.write_element(&mut out, "");
+    out.push_str("</d:error>");
+    out
+}
+
+/// 按 RFC 3986 对路径中的一段做百分号编码
+pub fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+/// 百分号解码，编码无效时返回空
+pub fn decode_path_segment(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,296 @@
+use crate::utils::timezone::east8;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::fmt::Write;
+
+/// 单行内容最多 75 个字节（RFC 5545 3.1），超出部分折行
+const MAX_LINE_OCTETS: usize = 75;
+
+/// 一行 iCalendar 内容，例如 `DUE;VALUE=DATE:20250101`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentLine {
+    /// 属性名，统一为大写
+    pub name: String,
+    /// 参数，参数名统一为大写，参数值去掉引号
+    pub params: Vec<(String, String)>,
+    /// 原始值，TEXT 类型需要再调用 `unescape_text`
+    pub value: String,
+}
+
+impl ContentLine {
+    /// 查询参数值（参数名不区分大小写）
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 解析一行已经展开折行的内容
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut in_quotes = false;
+        let mut value_start = None;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ':' if !in_quotes => {
+                    value_start = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let value_start = value_start.ok_or_else(|| format!("无法解析的内容行：{line}"))?;
+        let (head, value) = (&line[..value_start], &line[value_start + 1..]);
+        let mut parts = split_unquoted(head, ';').into_iter();
+        let name = parts.next().unwrap_or_default().trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return Err(format!("缺少属性名：{line}"));
+        }
+        let params = parts
+            .filter_map(|p| {
+                p.split_once('=').map(|(k, v)| {
+                    (
+                        k.trim().to_ascii_uppercase(),
+                        v.trim().trim_matches('"').to_string(),
+                    )
+                })
+            })
+            .collect();
+        Ok(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// 按分隔符切分，忽略双引号中的分隔符
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == sep && !in_quotes {
+            parts.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// 组件，例如 VCALENDAR、VTODO，可以嵌套
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<ContentLine>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    /// 第一个同名属性
+    pub fn property(&self, name: &str) -> Option<&ContentLine> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    /// 全部同名属性
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a ContentLine> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    /// 解析 iCalendar 文本，返回顶层组件（通常只有一个 VCALENDAR）
+    pub fn parse_all(text: &str) -> Result<Vec<Component>, String> {
+        let mut stack: Vec<Component> = vec![];
+        let mut roots = vec![];
+        for line in unfold(text) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line = ContentLine::parse(&line)?;
+            match line.name.as_str() {
+                "BEGIN" => stack.push(Component {
+                    name: line.value.trim().to_ascii_uppercase(),
+                    ..Default::default()
+                }),
+                "END" => {
+                    let component = stack
+                        .pop()
+                        .filter(|c| c.name.eq_ignore_ascii_case(line.value.trim()))
+                        .ok_or_else(|| format!("END:{} 没有对应的 BEGIN", line.value))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(component),
+                        None => roots.push(component),
+                    }
+                }
+                _ => stack
+                    .last_mut()
+                    .ok_or_else(|| format!("属性 {} 不在任何组件中", line.name))?
+                    .properties
+                    .push(line),
+            }
+        }
+        if let Some(component) = stack.pop() {
+            return Err(format!("组件 {} 缺少 END", component.name));
+        }
+        Ok(roots)
+    }
+}
+
+/// 展开折行：以空格或制表符开头的行是上一行的延续
+pub fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// 转义 TEXT 类型的值
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 反转义 TEXT 类型的值
+pub fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// 拆分以逗号分隔的多值 TEXT（例如 CATEGORIES），并反转义每一项
+pub fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ',' => items.push(unescape_text(&std::mem::take(&mut current))),
+            c => current.push(c),
+        }
+    }
+    items.push(unescape_text(&current));
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 格式化为 UTC 时间，例如 `20250101T080000Z`
+pub fn format_utc(dt: &DateTimeWithTimeZone) -> String {
+    dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// 解析 DATE / DATE-TIME 类型的属性
+///
+/// 带 Z 后缀的按 UTC 解析；不带时区（浮动时间）或带 TZID 参数的按东八区解析；
+/// 只有日期（VALUE=DATE）时取东八区当天零点。
+pub fn parse_datetime(line: &ContentLine) -> Result<DateTimeWithTimeZone, String> {
+    let value = line.value.trim();
+    let east8 = east8().expect("valid offset");
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|dt| Utc.from_utc_datetime(&dt).with_timezone(&east8))
+            .map_err(|_| format!("{} 的时间格式无效：{value}", line.name));
+    }
+    let naive = if value.contains('T') {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+    } else {
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight"))
+    }
+    .map_err(|_| format!("{} 的时间格式无效：{value}", line.name))?;
+    east8
+        .from_local_datetime(&naive)
+        .single()
+        .ok_or_else(|| format!("{} 的时间无效：{value}", line.name))
+}
+
+/// 逐行生成 iCalendar 文本，负责折行和 CRLF 换行
+#[derive(Debug, Default)]
+pub struct IcsWriter {
+    out: String,
+}
+
+impl IcsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入一行，超过 75 字节时在字符边界处折行
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            let len = c.len_utf8();
+            if width + len > MAX_LINE_OCTETS {
+                self.out.push_str("\r\n ");
+                width = 1;
+            }
+            self.out.push(c);
+            width += len;
+        }
+        self.out.push_str("\r\n");
+    }
+
+    pub fn begin(&mut self, name: &str) {
+        self.line(&format!("BEGIN:{name}"));
+    }
+
+    pub fn end(&mut self, name: &str) {
+        self.line(&format!("END:{name}"));
+    }
+
+    /// 写入属性，值需要由调用方按类型处理（TEXT 类型先调用 `escape_text`）
+    pub fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{name}:{value}"));
+    }
+
+    /// 写入带参数的属性
+    pub fn property_with_params(&mut self, name: &str, params: &[(&str, &str)], value: &str) {
+        let mut line = String::from(name);
+        for (key, param) in params {
+            let _ = write!(line, ";{key}={param}");
+        }
+        let _ = write!(line, ":{value}");
+        self.line(&line);
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
@@ -0,0 +1,3 @@
+pub mod dav;
+pub mod ics;
+pub mod vtodo;
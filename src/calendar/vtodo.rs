@@ -0,0 +1,347 @@
+use crate::calendar::ics::{
+    Component, IcsWriter, escape_text, format_utc, parse_datetime, split_text_list, unescape_text,
+};
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::todo_list;
+use crate::handlers::todo::model::TodoPriority;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::collections::HashMap;
+
+/// 导出文件中的 PRODID
+pub const PRODID: &str = "-//Qiqianily//todo_list_v1//CN";
+/// UID 的最大长度，与 todo_list.ical_uid 保持一致
+const MAX_UID_CHARS: usize = 255;
+/// 标题的最大长度，与 todo_list.title 保持一致
+const MAX_TITLE_CHARS: usize = 200;
+/// 标签数量上限，与创建接口的校验保持一致
+const MAX_TAGS: usize = 20;
+
+/// 待办事项状态与 VTODO STATUS 的对应关系
+pub fn status_to_ical(status: TodoStatus) -> &'static str {
+    match status {
+        TodoStatus::Pending => "NEEDS-ACTION",
+        TodoStatus::InProgress => "IN-PROCESS",
+        TodoStatus::Completed => "COMPLETED",
+        TodoStatus::Cancelled => "CANCELLED",
+    }
+}
+
+fn status_from_ical(value: &str) -> Result<TodoStatus, String> {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "NEEDS-ACTION" => Ok(TodoStatus::Pending),
+        "IN-PROCESS" => Ok(TodoStatus::InProgress),
+        "COMPLETED" => Ok(TodoStatus::Completed),
+        "CANCELLED" => Ok(TodoStatus::Cancelled),
+        other => Err(format!("未知的 STATUS：{other}")),
+    }
+}
+
+/// 优先级与 VTODO PRIORITY（1 最高，9 最低）的对应关系
+pub fn priority_to_ical(priority: TodoPriority) -> u8 {
+    match priority {
+        TodoPriority::Urgent => 1,
+        TodoPriority::High => 3,
+        TodoPriority::Medium => 5,
+        TodoPriority::Low => 9,
+    }
+}
+
+/// PRIORITY 为 0 表示未定义，返回空
+fn priority_from_ical(value: &str) -> Result<Option<TodoPriority>, String> {
+    match value.trim().parse::<u8>() {
+        Ok(0) => Ok(None),
+        Ok(1..=2) => Ok(Some(TodoPriority::Urgent)),
+        Ok(3..=4) => Ok(Some(TodoPriority::High)),
+        Ok(5) => Ok(Some(TodoPriority::Medium)),
+        Ok(6..=9) => Ok(Some(TodoPriority::Low)),
+        _ => Err(format!("无效的 PRIORITY：{value}")),
+    }
+}
+
+/// 从 VTODO 中解析出的待办事项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VTodo {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub status: Option<TodoStatus>,
+    pub priority: Option<TodoPriority>,
+    pub due: Option<DateTimeWithTimeZone>,
+    pub completed: Option<DateTimeWithTimeZone>,
+    pub categories: Vec<String>,
+    /// RELATED-TO（RELTYPE=PARENT）指向的父任务 UID
+    pub parent_uid: Option<String>,
+}
+
+impl VTodo {
+    /// 从 VTODO 组件解析，缺少 UID 或 SUMMARY 时返回错误
+    pub fn from_component(component: &Component) -> Result<Self, String> {
+        let uid = component
+            .property("UID")
+            .map(|p| unescape_text(p.value.trim()))
+            .filter(|uid| !uid.is_empty())
+            .ok_or_else(|| String::from("缺少 UID"))?;
+        if uid.chars().count() > MAX_UID_CHARS {
+            return Err(format!("UID 不能超过 {MAX_UID_CHARS} 个字符"));
+        }
+        let summary = component
+            .property("SUMMARY")
+            .map(|p| unescape_text(&p.value).trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| String::from("缺少 SUMMARY"))?;
+        if summary.chars().count() > MAX_TITLE_CHARS {
+            return Err(format!("SUMMARY 不能超过 {MAX_TITLE_CHARS} 个字符"));
+        }
+        let mut categories: Vec<String> = vec![];
+        for tag in component
+            .properties_named("CATEGORIES")
+            .flat_map(|p| split_text_list(&p.value))
+        {
+            if !categories.contains(&tag) {
+                categories.push(tag);
+            }
+        }
+        if categories.len() > MAX_TAGS {
+            return Err(format!("CATEGORIES 不能超过 {MAX_TAGS} 个"));
+        }
+        let parent_uid = component
+            .properties_named("RELATED-TO")
+            .find(|p| {
+                p.param("RELTYPE")
+                    .is_none_or(|t| t.eq_ignore_ascii_case("PARENT"))
+            })
+            .map(|p| unescape_text(p.value.trim()))
+            .filter(|uid| !uid.is_empty());
+        Ok(Self {
+            uid,
+            summary,
+            description: component
+                .property("DESCRIPTION")
+                .map(|p| unescape_text(&p.value))
+                .filter(|d| !d.is_empty()),
+            status: component
+                .property("STATUS")
+                .map(|p| status_from_ical(&p.value))
+                .transpose()?,
+            priority: component
+                .property("PRIORITY")
+                .map(|p| priority_from_ical(&p.value))
+                .transpose()?
+                .flatten(),
+            due: component.property("DUE").map(parse_datetime).transpose()?,
+            completed: component
+                .property("COMPLETED")
+                .map(parse_datetime)
+                .transpose()?,
+            categories,
+            parent_uid,
+        })
+    }
+}
+
+/// 单个 VTODO 的解析结果，失败时带上 UID（如果有）和错误信息
+pub type ParsedVTodo = Result<VTodo, (Option<String>, String)>;
+
+/// 解析 iCalendar 文本中的全部 VTODO
+///
+/// 文本结构错误时整体返回错误；单个 VTODO 解析失败时在对应位置返回 `(UID, 错误信息)`。
+pub fn parse_vtodos(text: &str) -> Result<Vec<ParsedVTodo>, String> {
+    let roots = Component::parse_all(text)?;
+    if !roots.iter().any(|c| c.name == "VCALENDAR") {
+        return Err(String::from("缺少 VCALENDAR"));
+    }
+    Ok(roots
+        .iter()
+        .filter(|c| c.name == "VCALENDAR")
+        .flat_map(|c| c.components.iter())
+        .filter(|c| c.name == "VTODO")
+        .map(|c| {
+            VTodo::from_component(c).map_err(|e| {
+                let uid = c.property("UID").map(|p| unescape_text(p.value.trim()));
+                (uid, e)
+            })
+        })
+        .collect())
+}
+
+/// 写入一个 VTODO 组件
+///
+/// # 参数
+/// - writer: 输出
+/// - todo: 待办事项
+/// - parent_uid: 父任务的 UID，用于生成 RELATED-TO
+/// - dtstamp: 生成时间
+pub fn write_vtodo(
+    writer: &mut IcsWriter,
+    todo: &todo_list::Model,
+    parent_uid: Option<&str>,
+    dtstamp: &DateTimeWithTimeZone,
+) {
+    writer.begin("VTODO");
+    writer.property("UID", &escape_text(&todo.ical_uid));
+    writer.property("DTSTAMP", &format_utc(dtstamp));
+    if let Some(created_at) = &todo.created_at {
+        writer.property("CREATED", &format_utc(created_at));
+    }
+    if let Some(updated_at) = &todo.updated_at {
+        writer.property("LAST-MODIFIED", &format_utc(updated_at));
+    }
+    writer.property("SUMMARY", &escape_text(&todo.title));
+    if let Some(description) = &todo.description {
+        writer.property("DESCRIPTION", &escape_text(description));
+    }
+    let status = TodoStatus::from_db(todo.status.as_deref());
+    writer.property("STATUS", status_to_ical(status));
+    if let Some(priority) = todo.priority.as_deref().and_then(|p| p.parse().ok()) {
+        writer.property("PRIORITY", &priority_to_ical(priority).to_string());
+    }
+    if let Some(due_date) = &todo.due_date {
+        writer.property("DUE", &format_utc(due_date));
+    }
+    if let Some(completed_at) = &todo.completed_at {
+        writer.property("COMPLETED", &format_utc(completed_at));
+        writer.property("PERCENT-COMPLETE", "100");
+    }
+    if let Some(tags) = todo.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let categories: Vec<String> = tags.iter().map(|t| escape_text(t)).collect();
+        writer.property("CATEGORIES", &categories.join(","));
+    }
+    if let Some(parent_uid) = parent_uid {
+        writer.property_with_params(
+            "RELATED-TO",
+            &[("RELTYPE", "PARENT")],
+            &escape_text(parent_uid),
+        );
+    }
+    writer.end("VTODO");
+}
+
+/// 写入截止时间对应的 VEVENT，供不支持 VTODO 的日历客户端显示
+///
+/// UID 在待办事项 UID 后加上 `-due`，开始时间即截止时间，不设置结束时间（时长为 0）。
+pub fn write_due_event(
+    writer: &mut IcsWriter,
+    todo: &todo_list::Model,
+    due_date: &DateTimeWithTimeZone,
+    dtstamp: &DateTimeWithTimeZone,
+) {
+    writer.begin("VEVENT");
+    writer.property("UID", &escape_text(&format!("{}-due", todo.ical_uid)));
+    writer.property("DTSTAMP", &format_utc(dtstamp));
+    if let Some(updated_at) = &todo.updated_at {
+        writer.property("LAST-MODIFIED", &format_utc(updated_at));
+    }
+    writer.property("SUMMARY", &escape_text(&todo.title));
+    if let Some(description) = &todo.description {
+        writer.property("DESCRIPTION", &escape_text(description));
+    }
+    writer.property("DTSTART", &format_utc(due_date));
+    let status = TodoStatus::from_db(todo.status.as_deref());
+    let event_status = match status {
+        TodoStatus::Cancelled => "CANCELLED",
+        _ => "CONFIRMED",
+    };
+    writer.property("STATUS", event_status);
+    writer.property("TRANSP", "TRANSPARENT");
+    if let Some(tags) = todo.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let categories: Vec<String> = tags.iter().map(|t| escape_text(t)).collect();
+        writer.property("CATEGORIES", &categories.join(","));
+    }
+    writer.end("VEVENT");
+}
+
+/// 写入 VCALENDAR 的开头部分
+fn begin_calendar(writer: &mut IcsWriter, name: &str) {
+    writer.begin("VCALENDAR");
+    writer.property("VERSION", "2.0");
+    writer.property("PRODID", PRODID);
+    writer.property("CALSCALE", "GREGORIAN");
+    writer.property("X-WR-CALNAME", &escape_text(name));
+}
+
+/// 父任务的 UID，用于生成 RELATED-TO
+fn parent_uid_of<'a>(
+    todo: &todo_list::Model,
+    parent_uids: &'a HashMap<i32, String>,
+) -> Option<&'a str> {
+    todo.parent_id
+        .and_then(|id| parent_uids.get(&id))
+        .map(String::as_str)
+}
+
+/// 生成包含若干 VTODO 的完整日历
+///
+/// # 参数
+/// - name: 日历名称（X-WR-CALNAME）
+/// - todos: 待办事项
+/// - parent_uids: 父任务 id 到 UID 的映射，父任务不在导出范围内时也需要提供
+pub fn write_calendar(
+    name: &str,
+    todos: &[todo_list::Model],
+    parent_uids: &HashMap<i32, String>,
+) -> String {
+    let dtstamp = get_local_datetime_with_timezone();
+    let mut writer = IcsWriter::new();
+    begin_calendar(&mut writer, name);
+    for todo in todos {
+        write_vtodo(
+            &mut writer,
+            todo,
+            parent_uid_of(todo, parent_uids),
+            &dtstamp,
+        );
+    }
+    writer.end("VCALENDAR");
+    writer.finish()
+}
+
+/// 生成只包含一个 VTODO 的日历，作为 CalDAV 的单个资源
+///
+/// DTSTAMP 使用待办事项的更新时间，数据不变时输出也不变，与资源的 ETag 保持一致。
+pub fn write_todo_resource(todo: &todo_list::Model, parent_uid: Option<&str>) -> String {
+    let dtstamp = todo
+        .updated_at
+        .unwrap_or_else(get_local_datetime_with_timezone);
+    let mut writer = IcsWriter::new();
+    writer.begin("VCALENDAR");
+    writer.property("VERSION", "2.0");
+    writer.property("PRODID", PRODID);
+    write_vtodo(&mut writer, todo, parent_uid, &dtstamp);
+    writer.end("VCALENDAR");
+    writer.finish()
+}
+
+/// 生成订阅用的日历，每个有截止时间的待办事项同时输出 VTODO 和 VEVENT
+///
+/// 内容只取决于数据本身：DTSTAMP 使用数据的最后修改时间，数据不变时输出也不变。
+///
+/// # 参数
+/// - name: 日历名称（X-WR-CALNAME）
+/// - todos: 待办事项，没有截止时间的会被忽略
+/// - parent_uids: 父任务 id 到 UID 的映射
+/// - dtstamp: 数据的最后修改时间
+/// - refresh_minutes: 建议客户端的刷新间隔
+pub fn write_feed_calendar(
+    name: &str,
+    todos: &[todo_list::Model],
+    parent_uids: &HashMap<i32, String>,
+    dtstamp: &DateTimeWithTimeZone,
+    refresh_minutes: u32,
+) -> String {
+    let mut writer = IcsWriter::new();
+    begin_calendar(&mut writer, name);
+    writer.property("METHOD", "PUBLISH");
+    let refresh = format!("PT{refresh_minutes}M");
+    writer.property_with_params("REFRESH-INTERVAL", &[("VALUE", "DURATION")], &refresh);
+    writer.property("X-PUBLISHED-TTL", &refresh);
+    for todo in todos {
+        let Some(due_date) = &todo.due_date else {
+            continue;
+        };
+        write_vtodo(&mut writer, todo, parent_uid_of(todo, parent_uids), dtstamp);
+        write_due_event(&mut writer, todo, due_date, dtstamp);
+    }
+    writer.end("VCALENDAR");
+    writer.finish()
+}
@@ -23,3 +23,17 @@ where
         StringOrNumber::Number(n) => Ok(n),
     }
 }
+
+/// 区分 PATCH 请求中“字段缺失”和“字段为 null”
+///
+/// 需要配合 `#[serde(default)]` 使用：
+/// - 字段缺失：`None`，不修改
+/// - 字段为 null：`Some(None)`，清空
+/// - 字段有值：`Some(Some(value))`，更新
+pub fn deserialize_optional_field<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Ok(Some(Option::deserialize(deserializer)?))
+}
@@ -1,6 +1,11 @@
+use crate::conf::attachment::AttachmentConfig;
 use crate::conf::base::BaseConfig;
+use crate::conf::batch::BatchConfig;
 use crate::conf::database::DbConfig;
 use crate::conf::redis::RedisConfig;
+use crate::conf::reminder::ReminderConfig;
+use crate::conf::trash::TrashConfig;
+use crate::conf::undo::UndoConfig;
 use anyhow::Context;
 use clap::Parser;
 use config::{Config, Environment, File, FileFormat};
@@ -25,6 +30,16 @@ pub struct AppConfig {
     base: BaseConfig,   // 基础配置信息，共用的相关配置
     database: DbConfig, // 数据库配置信息
     redis: RedisConfig, // redis配置信息
+    #[serde(default)]
+    reminder: ReminderConfig, // 到期提醒配置信息
+    #[serde(default)]
+    batch: BatchConfig, // 批量操作配置信息
+    #[serde(default)]
+    trash: TrashConfig, // 回收站配置信息
+    #[serde(default)]
+    undo: UndoConfig, // 撤销和重做配置信息
+    #[serde(default)]
+    attachment: AttachmentConfig, // 附件配置信息
 }
 impl AppConfig {
     // load the config file
@@ -78,4 +93,24 @@ impl AppConfig {
     pub fn redis(&self) -> &RedisConfig {
         &self.redis
     }
+    /// 获取到期提醒配置信息
+    pub fn reminder(&self) -> &ReminderConfig {
+        &self.reminder
+    }
+    /// 获取批量操作配置信息
+    pub fn batch(&self) -> &BatchConfig {
+        &self.batch
+    }
+    /// 获取回收站配置信息
+    pub fn trash(&self) -> &TrashConfig {
+        &self.trash
+    }
+    /// 获取撤销和重做配置信息
+    pub fn undo(&self) -> &UndoConfig {
+        &self.undo
+    }
+    /// 获取附件配置信息
+    pub fn attachment(&self) -> &AttachmentConfig {
+        &self.attachment
+    }
 }
@@ -0,0 +1,69 @@
+use bytesize::ByteSize;
+
+/// 附件相关配置，整段可以省略，省略时使用默认值
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct AttachmentConfig {
+    dir: String,                // 附件的存储目录，文件按内容的 SHA-256 存放，相同内容只存一份
+    max_file_mib: u64,          // 单个文件的大小上限（MiB），同时受请求体 10 MiB 的限制
+    quota_mib: u64,             // 每个用户可以上传的附件总大小（MiB），按附件记录累计
+    allowed_types: Vec<String>, // 允许上传的 MIME 类型，支持 image/* 形式的通配
+    gc_interval_secs: u64,      // 清理无人引用的文件的间隔（秒）
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            dir: String::from("./data/attachments"),
+            max_file_mib: 10,
+            quota_mib: 200,
+            allowed_types: [
+                "image/*",
+                "text/plain",
+                "text/markdown",
+                "text/csv",
+                "application/pdf",
+                "application/zip",
+                "application/json",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            gc_interval_secs: 3600,
+        }
+    }
+}
+
+/// 获取附件的配置信息
+impl AttachmentConfig {
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+    pub fn max_file_size(&self) -> u64 {
+        ByteSize::mib(self.max_file_mib.max(1)).as_u64()
+    }
+    pub fn quota(&self) -> u64 {
+        ByteSize::mib(self.quota_mib).as_u64()
+    }
+    pub fn gc_interval_secs(&self) -> u64 {
+        self.gc_interval_secs.max(60)
+    }
+    /// MIME 类型是否允许上传，忽略大小写和 `; charset=` 等参数
+    pub fn allows(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        self.allowed_types.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+            match allowed.strip_suffix("/*") {
+                Some(prefix) => essence
+                    .split_once('/')
+                    .is_some_and(|(kind, _)| kind == prefix),
+                None => allowed == essence,
+            }
+        })
+    }
+}
@@ -0,0 +1,19 @@
+/// 批量操作相关配置，整段可以省略，省略时使用默认值
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct BatchConfig {
+    max_size: usize, // 单次批量操作最多包含的待办事项数量
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self { max_size: 100 }
+    }
+}
+
+/// 获取批量操作的配置信息
+impl BatchConfig {
+    pub fn max_size(&self) -> usize {
+        self.max_size.max(1)
+    }
+}
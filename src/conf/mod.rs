@@ -2,9 +2,14 @@ use crate::conf::app::AppConfig;
 use std::sync::LazyLock;
 
 pub mod app;
+pub mod attachment;
 mod base;
+pub mod batch;
 mod database;
 mod redis;
+pub mod reminder;
+pub mod trash;
+pub mod undo;
 
 // set the static config
 static APP_CONFIG: LazyLock<AppConfig> =
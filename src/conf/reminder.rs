@@ -0,0 +1,45 @@
+/// 到期提醒使用的通知方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifierKind {
+    #[default]
+    Log, // 只输出日志
+    Redis, // 发布到 redis 频道 todo:reminders:{user_id}
+}
+
+/// 到期提醒相关配置，整段可以省略，省略时使用默认值
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct ReminderConfig {
+    enabled: bool,          // 是否启动提醒调度
+    interval_secs: u64,     // 扫描间隔（秒）
+    batch_size: u64,        // 每次扫描最多处理的提醒数量
+    notifier: NotifierKind, // 通知方式
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 30,
+            batch_size: 200,
+            notifier: NotifierKind::Log,
+        }
+    }
+}
+
+/// 获取提醒的配置信息
+impl ReminderConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.max(1)
+    }
+    pub fn batch_size(&self) -> u64 {
+        self.batch_size.max(1)
+    }
+    pub fn notifier(&self) -> NotifierKind {
+        self.notifier
+    }
+}
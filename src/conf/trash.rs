@@ -0,0 +1,31 @@
+/// 回收站相关配置，整段可以省略，省略时使用默认值
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct TrashConfig {
+    enabled: bool,       // 是否启动定期清理
+    retention_days: u32, // 删除后在回收站中保留的天数
+    interval_secs: u64,  // 清理间隔（秒）
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 30,
+            interval_secs: 3600,
+        }
+    }
+}
+
+/// 获取回收站的配置信息
+impl TrashConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn retention_days(&self) -> u32 {
+        self.retention_days.max(1)
+    }
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs.max(1)
+    }
+}
@@ -0,0 +1,31 @@
+/// 撤销和重做相关配置，整段可以省略，省略时使用默认值
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct UndoConfig {
+    max_steps: usize,   // 每个会话最多保留的撤销步数
+    max_changes: usize, // 单个步骤最多涉及的待办事项数量，超过时清空撤销记录
+    ttl_secs: u64,      // 撤销记录在 Redis 中的过期时间（秒），每次操作后重新计时
+}
+
+impl Default for UndoConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: 20,
+            max_changes: 1000,
+            ttl_secs: 24 * 3600,
+        }
+    }
+}
+
+/// 获取撤销和重做的配置信息
+impl UndoConfig {
+    pub fn max_steps(&self) -> usize {
+        self.max_steps.max(1)
+    }
+    pub fn max_changes(&self) -> usize {
+        self.max_changes.max(1)
+    }
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs.max(1)
+    }
+}
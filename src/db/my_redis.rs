@@ -108,4 +108,37 @@ impl RedisClient {
         let mut conn = self.get_conn().await?;
         conn.ttl(key).await
     }
+
+    /// 从列表头部压入元素，只保留最新的 `max_len` 个，并重新设置过期时间
+    pub async fn push_capped(
+        &self,
+        key: &str,
+        value: &str,
+        max_len: usize,
+        seconds: i64,
+    ) -> RedisResult<()> {
+        let mut conn = self.get_conn().await?;
+        redis::pipe()
+            .atomic()
+            .lpush(key, value)
+            .ignore()
+            .ltrim(key, 0, max_len as isize - 1)
+            .ignore()
+            .expire(key, seconds)
+            .ignore()
+            .query_async(&mut *conn)
+            .await
+    }
+
+    /// 从列表头部弹出一个元素，列表为空时返回 None
+    pub async fn lpop(&self, key: &str) -> RedisResult<Option<String>> {
+        let mut conn = self.get_conn().await?;
+        conn.lpop(key, None).await
+    }
+
+    /// 向频道发布消息，返回收到消息的订阅者数量
+    pub async fn publish(&self, channel: &str, message: &str) -> RedisResult<i64> {
+        let mut conn = self.get_conn().await?;
+        conn.publish(channel, message).await
+    }
 }
@@ -0,0 +1,74 @@
+/// 请求的字节范围，`end` 包含在内
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// 请求的范围超出了文件大小，应返回 416
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeNotSatisfiable;
+
+impl ByteRange {
+    /// 范围内的字节数，范围至少包含一个字节
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Content-Range 响应头的值，例如 `bytes 0-99/1000`
+    pub fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// 解析 Range 请求头，只支持单个字节范围
+///
+/// - `bytes=0-99`：第 0 到 99 字节，结束位置超出文件大小时截断到文件末尾
+/// - `bytes=100-`：从第 100 字节到文件末尾
+/// - `bytes=-100`：最后 100 个字节
+///
+/// 格式无法识别或请求了多个范围时返回 `Ok(None)`，按完整文件返回；
+/// 起始位置超出文件大小时返回 `RangeNotSatisfiable`。
+pub fn parse_range(header: &str, size: u64) -> Result<Option<ByteRange>, RangeNotSatisfiable> {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Ok(None);
+    };
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        // 后缀范围：最后 n 个字节
+        let Ok(suffix) = end.parse::<u64>() else {
+            return Ok(None);
+        };
+        if suffix == 0 || size == 0 {
+            return Err(RangeNotSatisfiable);
+        }
+        return Ok(Some(ByteRange {
+            start: size.saturating_sub(suffix),
+            end: size - 1,
+        }));
+    }
+    let Ok(start) = start.parse::<u64>() else {
+        return Ok(None);
+    };
+    let end = if end.is_empty() {
+        None
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => Some(end),
+            _ => return Ok(None),
+        }
+    };
+    if start >= size {
+        return Err(RangeNotSatisfiable);
+    }
+    Ok(Some(ByteRange {
+        start,
+        end: end.map_or(size - 1, |end| end.min(size - 1)),
+    }))
+}
@@ -0,0 +1,97 @@
+/// 一条评论最多解析的 @ 用户数量，超出的部分忽略
+pub const MAX_MENTIONS: usize = 20;
+
+/// 用户名中允许出现的字符
+fn is_username_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// 解析 Markdown 正文中 `@username` 形式的提及，按出现顺序去重
+///
+/// - `@` 前面紧挨着用户名字符时不算提及，例如邮箱地址 `a@b.com`
+/// - 用户名末尾的 `.` 和 `-` 视为标点，例如 `@bob.` 解析为 `bob`
+/// - 代码块（```` ``` ```` 或 `~~~` 围起来的行）和行内代码中的内容不解析
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            None => {
+                if let Some(marker) = ["```", "~~~"]
+                    .into_iter()
+                    .find(|marker| trimmed.starts_with(marker))
+                {
+                    fence = Some(marker);
+                    continue;
+                }
+            }
+        }
+        for name in parse_line(line) {
+            if mentions.len() >= MAX_MENTIONS {
+                return mentions;
+            }
+            if !mentions.contains(&name) {
+                mentions.push(name);
+            }
+        }
+    }
+    mentions
+}
+
+/// 解析一行中的提及，跳过行内代码
+fn parse_line(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut names = Vec::new();
+    // 行内代码由相同数量的反引号开始和结束
+    let mut code_ticks = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let start = i;
+            while i < chars.len() && chars[i] == '`' {
+                i += 1;
+            }
+            let ticks = i - start;
+            if code_ticks == 0 {
+                // 没有配对的反引号时按普通字符处理
+                if has_closing_ticks(&chars[i..], ticks) {
+                    code_ticks = ticks;
+                }
+            } else if code_ticks == ticks {
+                code_ticks = 0;
+            }
+            continue;
+        }
+        if code_ticks == 0
+            && chars[i] == '@'
+            && (i == 0 || !(is_username_char(chars[i - 1]) || chars[i - 1] == '@'))
+        {
+            let start = i + 1;
+            let mut end = start;
+            while end < chars.len() && is_username_char(chars[end]) {
+                end += 1;
+            }
+            let name: String = chars[start..end].iter().collect();
+            let name = name.trim_end_matches(['.', '-']);
+            if !name.is_empty() {
+                names.push(name.to_string());
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    names
+}
+
+/// 剩余内容中是否有恰好 `ticks` 个连续的反引号
+fn has_closing_ticks(rest: &[char], ticks: usize) -> bool {
+    rest.split(|c| *c != '`').any(|run| run.len() == ticks)
+}
@@ -0,0 +1,7 @@
+pub mod byte_range;
+pub mod mention;
+pub mod rank;
+pub mod recurrence;
+pub mod todo_role;
+pub mod todo_status;
+pub mod todo_txt;
@@ -0,0 +1,27 @@
+/// 相邻两个排序值之间的默认间隔
+///
+/// sort_order 使用稀疏整数作为“分数排名”：插入到两个任务之间时取两者的中点，
+/// 只需要修改被移动的那一行；当中点不存在（间隔小于 2）时才需要对整个同级分组重新编号。
+pub const RANK_GAP: i64 = 1024;
+
+/// 后台重排的阈值，同级分组中任意相邻间隔小于该值时认为过于密集
+pub const DENSE_RANK_GAP: i64 = 8;
+
+/// 计算位于 `lower` 和 `upper` 之间的排序值
+///
+/// - 两边都为空：分组中没有其他任务，返回默认间隔
+/// - 只有下界：追加到末尾
+/// - 只有上界：插入到开头
+/// - 两边都有：取中点
+///
+/// 没有可用的排序值（间隔耗尽或超出 i32 范围）时返回 `None`，需要先重排分组。
+pub fn rank_between(lower: Option<i32>, upper: Option<i32>) -> Option<i32> {
+    let rank = match (lower.map(i64::from), upper.map(i64::from)) {
+        (None, None) => RANK_GAP,
+        (Some(lower), None) => lower + RANK_GAP,
+        (None, Some(upper)) => upper - RANK_GAP,
+        (Some(lower), Some(upper)) if upper - lower >= 2 => lower + (upper - lower) / 2,
+        (Some(_), Some(_)) => return None,
+    };
+    i32::try_from(rank).ok()
+}
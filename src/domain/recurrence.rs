@@ -0,0 +1,508 @@
+use crate::response::errors::ApiError;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+/// 连续多少个周期没有产生任何日期时认为规则不会再有结果（例如 BYMONTH=2;BYMONTHDAY=30）
+const MAX_EMPTY_PERIODS: u32 = 1000;
+
+/// 重复频率
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// BYDAY 中的一项，例如 `MO`、`2TU`、`-1FR`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    /// 第几个（负数表示倒数第几个），为空表示所有该星期几
+    pub ordinal: Option<i16>,
+    pub weekday: Weekday,
+}
+
+/// UNTIL 的三种写法：日期、UTC 时间、不带时区的本地时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Date(NaiveDate),
+    Utc(DateTime<Utc>),
+    Local(NaiveDateTime),
+}
+
+impl Until {
+    /// 判断某次重复是否仍在 UNTIL 范围内（包含边界）
+    fn allows(&self, occurrence: &DateTimeWithTimeZone) -> bool {
+        match self {
+            Until::Date(date) => occurrence.date_naive() <= *date,
+            Until::Utc(until) => occurrence.with_timezone(&Utc) <= *until,
+            Until::Local(until) => occurrence.naive_local() <= *until,
+        }
+    }
+}
+
+/// RFC 5545 重复规则（RRULE）
+///
+/// 支持 FREQ（DAILY / WEEKLY / MONTHLY / YEARLY）、INTERVAL、COUNT、UNTIL、
+/// BYDAY、BYMONTHDAY、BYMONTH 和 WKST，不支持按小时、分钟等更细粒度的规则以及 BYSETPOS。
+/// 每次重复的时刻与 DTSTART 相同，DTSTART 本身总是第一次重复。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i16>,
+    pub by_month: Vec<u32>,
+    pub week_start: Weekday,
+}
+
+fn invalid(msg: impl fmt::Display) -> ApiError {
+    ApiError::Biz(format!("无效的重复规则：{msg}"))
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, ApiError> {
+    match s {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(invalid(format!("未知的星期 {s}"))),
+    }
+}
+
+fn weekday_str(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_by_day(s: &str) -> Result<ByDay, ApiError> {
+    // 后面按字节位置切分星期，非 ASCII 字符会切在字符中间
+    if !s.is_ascii() {
+        return Err(invalid(format!("BYDAY={s}")));
+    }
+    let split = s.len().saturating_sub(2);
+    let (ordinal, weekday) = s.split_at(split);
+    let ordinal = match ordinal {
+        "" => None,
+        n => {
+            let n: i16 = n.parse().map_err(|_| invalid(format!("BYDAY={s}")))?;
+            if n == 0 || n.abs() > 53 {
+                return Err(invalid(format!("BYDAY={s}")));
+            }
+            Some(n)
+        }
+    };
+    Ok(ByDay {
+        ordinal,
+        weekday: parse_weekday(weekday)?,
+    })
+}
+
+fn parse_until(s: &str) -> Result<Until, ApiError> {
+    if let Some(utc) = s.strip_suffix('Z') {
+        let dt = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map_err(|_| invalid(format!("UNTIL={s}")))?;
+        return Ok(Until::Utc(Utc.from_utc_datetime(&dt)));
+    }
+    if s.contains('T') {
+        return NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S")
+            .map(Until::Local)
+            .map_err(|_| invalid(format!("UNTIL={s}")));
+    }
+    NaiveDate::parse_from_str(s, "%Y%m%d")
+        .map(Until::Date)
+        .map_err(|_| invalid(format!("UNTIL={s}")))
+}
+
+fn parse_list<T>(value: &str, f: impl Fn(&str) -> Result<T, ApiError>) -> Result<Vec<T>, ApiError> {
+    value.split(',').map(|v| f(v.trim())).collect()
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = ApiError;
+
+    /// 解析 RRULE，可以带 `RRULE:` 前缀，键名不区分大小写
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(s, |_| &s[6..]);
+        let mut freq = None;
+        let mut rule = RecurrenceRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+            week_start: Weekday::Mon,
+        };
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("{part} 缺少取值")))?;
+            let value = value.trim().to_ascii_uppercase();
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(format!("不支持的频率 {value}"))),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=1000).contains(n))
+                        .ok_or_else(|| invalid(format!("INTERVAL={value}")))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|n| *n >= 1)
+                            .ok_or_else(|| invalid(format!("COUNT={value}")))?,
+                    )
+                }
+                "UNTIL" => rule.until = Some(parse_until(&value)?),
+                "BYDAY" => rule.by_day = parse_list(&value, parse_by_day)?,
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_list(&value, |v| {
+                        v.parse::<i16>()
+                            .ok()
+                            .filter(|n| *n != 0 && n.abs() <= 31)
+                            .ok_or_else(|| invalid(format!("BYMONTHDAY={v}")))
+                    })?
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_list(&value, |v| {
+                        v.parse::<u32>()
+                            .ok()
+                            .filter(|n| (1..=12).contains(n))
+                            .ok_or_else(|| invalid(format!("BYMONTH={v}")))
+                    })?
+                }
+                "WKST" => rule.week_start = parse_weekday(&value)?,
+                other => return Err(invalid(format!("不支持的规则 {other}"))),
+            }
+        }
+        rule.freq = freq.ok_or_else(|| invalid("缺少 FREQ"))?;
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err(invalid("COUNT 和 UNTIL 不能同时使用"));
+        }
+        let has_ordinal = rule.by_day.iter().any(|d| d.ordinal.is_some());
+        match rule.freq {
+            Frequency::Daily | Frequency::Weekly if has_ordinal => {
+                return Err(invalid("DAILY / WEEKLY 规则的 BYDAY 不能带序号"));
+            }
+            Frequency::Weekly if !rule.by_month_day.is_empty() => {
+                return Err(invalid("WEEKLY 规则不能使用 BYMONTHDAY"));
+            }
+            _ => {}
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    /// 输出规范化的 RRULE（不带 `RRULE:` 前缀）
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        match self.until {
+            Some(Until::Date(date)) => write!(f, ";UNTIL={}", date.format("%Y%m%d"))?,
+            Some(Until::Utc(dt)) => write!(f, ";UNTIL={}", dt.format("%Y%m%dT%H%M%SZ"))?,
+            Some(Until::Local(dt)) => write!(f, ";UNTIL={}", dt.format("%Y%m%dT%H%M%S"))?,
+            None => {}
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|d| match d.ordinal {
+                    Some(n) => format!("{n}{}", weekday_str(d.weekday)),
+                    None => weekday_str(d.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if !self.by_month.is_empty() {
+            let months: Vec<String> = self.by_month.iter().map(|m| m.to_string()).collect();
+            write!(f, ";BYMONTH={}", months.join(","))?;
+        }
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_str(self.week_start))?;
+        }
+        Ok(())
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).expect("valid month");
+    let next = first + Months::new(1);
+    (next - first).num_days() as u32
+}
+
+/// 把 BYMONTHDAY（支持负数）换算成某月中的日期，不存在的日期返回空
+fn month_day(year: i32, month: u32, day: i16) -> Option<NaiveDate> {
+    let last = days_in_month(year, month) as i16;
+    let day = if day > 0 { day } else { last + day + 1 };
+    (1..=last)
+        .contains(&day)
+        .then(|| NaiveDate::from_ymd_opt(year, month, day as u32))
+        .flatten()
+}
+
+/// 在 `[first, last]` 范围内按 BYDAY 展开日期，序号相对该范围计算
+fn expand_by_day(first: NaiveDate, last: NaiveDate, by_day: &ByDay) -> Vec<NaiveDate> {
+    let all: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .filter(|d| d.weekday() == by_day.weekday)
+        .collect();
+    match by_day.ordinal {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+impl RecurrenceRule {
+    /// 某个月内符合 BYMONTHDAY / BYDAY 的日期，两者都没有时使用 `default_day`
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1).expect("valid month");
+        let last = first + Days::new(days_in_month(year, month) as u64 - 1);
+        let by_day: Vec<NaiveDate> = self
+            .by_day
+            .iter()
+            .flat_map(|d| expand_by_day(first, last, d))
+            .collect();
+        match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (true, true) => NaiveDate::from_ymd_opt(year, month, default_day)
+                .into_iter()
+                .collect(),
+            (true, false) => by_day,
+            (false, by_day_empty) => self
+                .by_month_day
+                .iter()
+                .filter_map(|d| month_day(year, month, *d))
+                .filter(|d| by_day_empty || by_day.contains(d))
+                .collect(),
+        }
+    }
+
+    /// 第 `period` 个周期（从 DTSTART 所在周期开始计数）内的候选日期
+    fn period_dates(&self, start: NaiveDate, period: u64) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval as u64)?;
+        let month_ok =
+            |d: &NaiveDate| self.by_month.is_empty() || self.by_month.contains(&d.month());
+        let mut dates = match self.freq {
+            Frequency::Daily => {
+                let day = start.checked_add_days(Days::new(step))?;
+                let weekday_ok = self.by_day.is_empty()
+                    || self.by_day.iter().any(|d| d.weekday == day.weekday());
+                let month_day_ok = self.by_month_day.is_empty()
+                    || self
+                        .by_month_day
+                        .iter()
+                        .any(|d| month_day(day.year(), day.month(), *d) == Some(day));
+                if weekday_ok && month_day_ok && month_ok(&day) {
+                    vec![day]
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Weekly => {
+                let offset = start.weekday().days_since(self.week_start) as u64;
+                let week = (start - Days::new(offset)).checked_add_days(Days::new(step * 7))?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|d| d.weekday).collect()
+                };
+                week.iter_days()
+                    .take(7)
+                    .filter(|d| weekdays.contains(&d.weekday()) && month_ok(d))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let month = NaiveDate::from_ymd_opt(start.year(), start.month(), 1)?
+                    .checked_add_months(Months::new(u32::try_from(step).ok()?))?;
+                if month_ok(&month) {
+                    self.month_dates(month.year(), month.month(), start.day())
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                if self.by_month.is_empty()
+                    && self.by_month_day.is_empty()
+                    && !self.by_day.is_empty()
+                {
+                    // 没有 BYMONTH 时，BYDAY 的序号相对整年计算
+                    let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+                    self.by_day
+                        .iter()
+                        .flat_map(|d| expand_by_day(first, last, d))
+                        .collect()
+                } else {
+                    // 没有 BYMONTH 时，BYMONTHDAY 在每个月展开；两者都没有时只用 DTSTART 所在的月份
+                    let months = if !self.by_month.is_empty() {
+                        self.by_month.clone()
+                    } else if !self.by_month_day.is_empty() {
+                        (1..=12).collect()
+                    } else {
+                        vec![start.month()]
+                    };
+                    months
+                        .into_iter()
+                        .flat_map(|m| self.month_dates(year, m, start.day()))
+                        .collect()
+                }
+            }
+        };
+        dates.sort();
+        dates.dedup();
+        Some(dates)
+    }
+
+    /// 从 `dtstart` 开始依次展开所有重复时刻
+    pub fn occurrences(&self, dtstart: DateTimeWithTimeZone) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            dtstart,
+            period: 0,
+            emitted: 0,
+            started: false,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// 严格晚于 `after` 的下一次重复时刻，规则已经结束时返回空
+    pub fn next_after(
+        &self,
+        dtstart: DateTimeWithTimeZone,
+        after: DateTimeWithTimeZone,
+    ) -> Option<DateTimeWithTimeZone> {
+        self.occurrences(dtstart).find(|dt| *dt > after)
+    }
+}
+
+/// 重复时刻迭代器，按时间先后输出，受 COUNT / UNTIL 限制
+pub struct Occurrences<'a> {
+    rule: &'a RecurrenceRule,
+    dtstart: DateTimeWithTimeZone,
+    period: u64,
+    emitted: u32,
+    started: bool,
+    buffer: VecDeque<DateTimeWithTimeZone>,
+    done: bool,
+}
+
+impl Occurrences<'_> {
+    /// 展开下一个有结果的周期，返回 false 表示不会再有结果
+    fn fill(&mut self) -> bool {
+        let start = self.dtstart.naive_local();
+        let mut empty = 0;
+        while empty < MAX_EMPTY_PERIODS {
+            let Some(dates) = self.rule.period_dates(start.date(), self.period) else {
+                return false;
+            };
+            self.period += 1;
+            for date in dates {
+                let local = date.and_time(start.time());
+                let Some(dt) = self.dtstart.offset().from_local_datetime(&local).single() else {
+                    continue;
+                };
+                if dt > self.dtstart {
+                    self.buffer.push_back(dt);
+                }
+            }
+            if !self.buffer.is_empty() {
+                return true;
+            }
+            empty += 1;
+        }
+        false
+    }
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = DateTimeWithTimeZone;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.rule.count.is_some_and(|count| self.emitted >= count) {
+            return None;
+        }
+        // DTSTART 本身总是第一次重复
+        let next = if !self.started {
+            self.started = true;
+            Some(self.dtstart)
+        } else {
+            if self.buffer.is_empty() && !self.fill() {
+                self.done = true;
+                return None;
+            }
+            self.buffer.pop_front()
+        };
+        match next {
+            Some(dt) if self.rule.until.is_none_or(|until| until.allows(&dt)) => {
+                self.emitted += 1;
+                Some(dt)
+            }
+            _ => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::response::errors::ApiError;
+
+/// 协作成员的角色，与数据库中 todo_shares.role 字段的 CHECK 约束保持一致
+///
+/// 按权限从低到高排列，高权限包含低权限的全部操作：
+/// - viewer：查看待办事项、子任务和修改历史
+/// - editor：修改内容、状态和子任务，计时和设置提醒
+/// - owner：与创建者相同，还可以管理协作成员
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoRole {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl TodoRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoRole::Viewer => "viewer",
+            TodoRole::Editor => "editor",
+            TodoRole::Owner => "owner",
+        }
+    }
+
+    /// 展示给用户的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            TodoRole::Viewer => "查看者",
+            TodoRole::Editor => "编辑者",
+            TodoRole::Owner => "所有者",
+        }
+    }
+}
+
+impl std::fmt::Display for TodoRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TodoRole {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(TodoRole::Viewer),
+            "editor" => Ok(TodoRole::Editor),
+            "owner" => Ok(TodoRole::Owner),
+            _ => Err(ApiError::Biz(format!("未知的协作角色：{s}"))),
+        }
+    }
+}
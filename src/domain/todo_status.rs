@@ -0,0 +1,109 @@
+use crate::entities::todo_list;
+use crate::response::errors::ApiError;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use sea_orm::Set;
+
+/// 待办事项状态，与数据库中 status 字段的 CHECK 约束保持一致
+///
+/// 合法的状态流转：
+/// - pending / in_progress 之间可以互相切换，也可以进入 completed 或 cancelled
+/// - completed / cancelled 是终止状态，只有显式重新打开（reopen）时才能回到 pending 或 in_progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+impl TodoStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "pending",
+            TodoStatus::InProgress => "in_progress",
+            TodoStatus::Completed => "completed",
+            TodoStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// 从数据库中的值解析状态，空值按数据库默认值 pending 处理
+    pub fn from_db(value: Option<&str>) -> Self {
+        value
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(TodoStatus::Pending)
+    }
+
+    /// 是否为终止状态
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TodoStatus::Completed | TodoStatus::Cancelled)
+    }
+
+    /// 校验状态流转是否合法
+    ///
+    /// # 参数
+    /// - to: 目标状态
+    /// - reopen: 是否显式重新打开终止状态的任务
+    pub fn check_transition(self, to: TodoStatus, reopen: bool) -> Result<(), ApiError> {
+        let allowed = match (self, to) {
+            (from, to) if from == to => false,
+            (from, _) if !from.is_terminal() => true,
+            (_, TodoStatus::Pending | TodoStatus::InProgress) => reopen,
+            _ => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(ApiError::IllegalStatusTransition {
+                from: self.as_str(),
+                to: to.as_str(),
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TodoStatus {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(TodoStatus::Pending),
+            "in_progress" => Ok(TodoStatus::InProgress),
+            "completed" => Ok(TodoStatus::Completed),
+            "cancelled" => Ok(TodoStatus::Cancelled),
+            _ => Err(ApiError::Biz(format!("未知的任务状态：{s}"))),
+        }
+    }
+}
+
+/// 校验并应用状态流转，同时维护 completed_at
+///
+/// 进入 completed 时记录当前时间（东八区），离开 completed 时清空完成时间。
+///
+/// # 参数
+/// - todo: 待更新的 ActiveModel
+/// - from: 当前状态
+/// - to: 目标状态
+/// - reopen: 是否允许重新打开终止状态的任务
+pub fn apply_status_transition(
+    todo: &mut todo_list::ActiveModel,
+    from: TodoStatus,
+    to: TodoStatus,
+    reopen: bool,
+) -> Result<(), ApiError> {
+    from.check_transition(to, reopen)?;
+    todo.status = Set(Some(to.as_str().into()));
+    todo.completed_at = Set(completed_at_for(to));
+    Ok(())
+}
+
+/// 新建任务或不经过状态机直接写入状态时，计算对应的完成时间
+pub fn completed_at_for(status: TodoStatus) -> Option<sea_orm::prelude::DateTimeWithTimeZone> {
+    (status == TodoStatus::Completed).then(get_local_datetime_with_timezone)
+}
@@ -0,0 +1,214 @@
+use crate::domain::todo_status::TodoStatus;
+use crate::handlers::todo::model::TodoPriority;
+use chrono::NaiveDate;
+use std::fmt;
+
+/// 日期格式，todo.txt 中的日期都是 `YYYY-MM-DD`
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// todo.txt 中的一行任务
+///
+/// 格式参考 <https://github.com/todotxt/todo.txt>：
+/// - `x` 开头表示已完成，后面依次是完成日期和创建日期
+/// - `(A)` 到 `(D)` 分别对应 urgent、high、medium、low，`(E)` 之后的字母都按 low 处理
+/// - `+project` 和 `@context` 对应标签，project 保存时去掉 `+`，context 保留 `@`
+/// - `due:YYYY-MM-DD` 对应截止日期
+///
+/// 为了能原样导回，导出时额外写入几个扩展字段：
+/// - `pri:` 已完成任务的优先级（规范要求完成时去掉行首的优先级）
+/// - `status:` pending / completed 之外的状态
+/// - `uid:` 待办事项的 UID，导入时按 UID 更新已有的待办事项
+///
+/// 其他 `key:value` 不做解析，原样保留在标题中。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTxtTask {
+    pub status: TodoStatus,
+    pub priority: Option<TodoPriority>,
+    pub completion_date: Option<NaiveDate>,
+    pub creation_date: Option<NaiveDate>,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due: Option<NaiveDate>,
+    pub uid: Option<String>,
+}
+
+fn priority_from_letter(letter: char) -> Option<TodoPriority> {
+    match letter {
+        'A' => Some(TodoPriority::Urgent),
+        'B' => Some(TodoPriority::High),
+        'C' => Some(TodoPriority::Medium),
+        'D'..='Z' => Some(TodoPriority::Low),
+        _ => None,
+    }
+}
+
+fn priority_to_letter(priority: TodoPriority) -> char {
+    match priority {
+        TodoPriority::Urgent => 'A',
+        TodoPriority::High => 'B',
+        TodoPriority::Medium => 'C',
+        TodoPriority::Low => 'D',
+    }
+}
+
+/// 解析 `(A)` 形式的优先级
+fn parse_priority_token(token: &str) -> Option<TodoPriority> {
+    let letter = token.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) => priority_from_letter(letter),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    // 严格要求 10 个字符，避免把 `2025-1-1` 这类写法当成日期
+    (value.len() == 10)
+        .then(|| NaiveDate::parse_from_str(value, DATE_FORMAT).ok())
+        .flatten()
+}
+
+/// 标签写成 project 或 context，标签中的空白替换成 `_`
+fn tag_token(tag: &str) -> String {
+    let tag: String = tag
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    if tag.starts_with('@') || tag.starts_with('+') {
+        tag
+    } else {
+        format!("+{tag}")
+    }
+}
+
+impl TodoTxtTask {
+    /// 解析一行 todo.txt，空行由调用方跳过
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut tokens = line.split_whitespace().peekable();
+        let mut status = TodoStatus::Pending;
+        let mut priority = None;
+        let mut completion_date = None;
+        let mut creation_date = None;
+        if tokens.peek() == Some(&"x") {
+            tokens.next();
+            status = TodoStatus::Completed;
+            if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+                tokens.next();
+                completion_date = Some(date);
+            }
+        } else if let Some(p) = tokens.peek().and_then(|t| parse_priority_token(t)) {
+            tokens.next();
+            priority = Some(p);
+        }
+        // 创建日期只能跟在完成日期之后，或者出现在未完成的任务中
+        if (status != TodoStatus::Completed || completion_date.is_some())
+            && let Some(date) = tokens.peek().and_then(|t| parse_date(t))
+        {
+            tokens.next();
+            creation_date = Some(date);
+        }
+        let mut words = vec![];
+        let mut tags: Vec<String> = vec![];
+        let mut due = None;
+        let mut uid = None;
+        for token in tokens {
+            if let Some(project) = token.strip_prefix('+').filter(|p| !p.is_empty()) {
+                if !tags.iter().any(|t| t == project) {
+                    tags.push(project.to_string());
+                }
+                continue;
+            }
+            if token.len() > 1 && token.starts_with('@') {
+                if !tags.iter().any(|t| t == token) {
+                    tags.push(token.to_string());
+                }
+                continue;
+            }
+            match token.split_once(':') {
+                Some(("due", value)) => {
+                    due =
+                        Some(parse_date(value).ok_or_else(|| format!("无效的截止日期：{value}"))?);
+                }
+                Some(("pri", value)) => {
+                    priority = Some(
+                        value
+                            .chars()
+                            .next()
+                            .filter(|_| value.len() == 1)
+                            .and_then(priority_from_letter)
+                            .ok_or_else(|| format!("无效的优先级：{value}"))?,
+                    );
+                }
+                Some(("status", value)) => {
+                    status = value.parse().map_err(|_| format!("未知的状态：{value}"))?;
+                }
+                Some(("uid", value)) if !value.is_empty() => uid = Some(value.to_string()),
+                _ => words.push(token),
+            }
+        }
+        if words.is_empty() {
+            return Err(String::from("缺少任务描述"));
+        }
+        Ok(Self {
+            status,
+            priority,
+            completion_date,
+            creation_date,
+            title: words.join(" "),
+            tags,
+            due,
+            uid,
+        })
+    }
+}
+
+/// 按 todo.txt 的格式输出一行，不包含换行符
+impl fmt::Display for TodoTxtTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let done = self.status.is_terminal();
+        let mut parts: Vec<String> = vec![];
+        if done {
+            parts.push(String::from("x"));
+            if let Some(date) = self.completion_date {
+                parts.push(date.format(DATE_FORMAT).to_string());
+            }
+        } else if let Some(priority) = self.priority {
+            parts.push(format!("({})", priority_to_letter(priority)));
+        }
+        // 规范要求已完成任务有创建日期时必须同时有完成日期
+        if let Some(date) = self.creation_date
+            && (!done || self.completion_date.is_some())
+        {
+            parts.push(date.format(DATE_FORMAT).to_string());
+        }
+        parts.push(self.title.split_whitespace().collect::<Vec<_>>().join(" "));
+        parts.extend(self.tags.iter().map(|tag| tag_token(tag)));
+        if let Some(due) = self.due {
+            parts.push(format!("due:{}", due.format(DATE_FORMAT)));
+        }
+        if done && let Some(priority) = self.priority {
+            parts.push(format!("pri:{}", priority_to_letter(priority)));
+        }
+        if !matches!(self.status, TodoStatus::Pending | TodoStatus::Completed) {
+            parts.push(format!("status:{}", self.status.as_str()));
+        }
+        if let Some(uid) = &self.uid {
+            parts.push(format!("uid:{uid}"));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// 解析整个 todo.txt 文件，返回每个非空行的行号和解析结果
+pub fn parse_todo_txt(text: &str) -> Vec<(u64, Result<TodoTxtTask, String>)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index as u64 + 1, TodoTxtTask::parse(line)))
+        .collect()
+}
+
+/// 输出整个 todo.txt 文件，每个任务一行
+pub fn write_todo_txt(tasks: &[TodoTxtTask]) -> String {
+    tasks.iter().map(|task| format!("{task}\n")).collect()
+}
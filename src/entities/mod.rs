@@ -2,5 +2,18 @@
 
 pub mod prelude;
 
+pub mod app_passwords;
+pub mod calendar_feeds;
+pub mod projects;
+pub mod reminder_deliveries;
+pub mod time_entries;
+pub mod todo_attachments;
+pub mod todo_comment_edits;
+pub mod todo_comment_mentions;
+pub mod todo_comments;
+pub mod todo_history;
 pub mod todo_list;
+pub mod todo_recurrences;
+pub mod todo_reminders;
+pub mod todo_shares;
 pub mod users;
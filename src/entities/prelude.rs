@@ -1,4 +1,17 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::app_passwords::Entity as AppPasswords;
+pub use super::calendar_feeds::Entity as CalendarFeeds;
+pub use super::projects::Entity as Projects;
+pub use super::reminder_deliveries::Entity as ReminderDeliveries;
+pub use super::time_entries::Entity as TimeEntries;
+pub use super::todo_attachments::Entity as TodoAttachments;
+pub use super::todo_comment_edits::Entity as TodoCommentEdits;
+pub use super::todo_comment_mentions::Entity as TodoCommentMentions;
+pub use super::todo_comments::Entity as TodoComments;
+pub use super::todo_history::Entity as TodoHistory;
 pub use super::todo_list::Entity as TodoList;
+pub use super::todo_recurrences::Entity as TodoRecurrences;
+pub use super::todo_reminders::Entity as TodoReminders;
+pub use super::todo_shares::Entity as TodoShares;
 pub use super::users::Entity as Users;
@@ -27,6 +27,11 @@ pub struct Model {
     pub sort_order: Option<i32>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
+    pub recurrence_id: Option<i32>,
+    pub ical_uid: String,
+    pub caldav_name: Option<String>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub project_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -39,6 +44,32 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     SelfRef,
+    #[sea_orm(
+        belongs_to = "super::projects::Entity",
+        from = "Column::ProjectId",
+        to = "super::projects::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Projects,
+    #[sea_orm(has_many = "super::time_entries::Entity")]
+    TimeEntries,
+    #[sea_orm(has_many = "super::todo_attachments::Entity")]
+    TodoAttachments,
+    #[sea_orm(has_many = "super::todo_comments::Entity")]
+    TodoComments,
+    #[sea_orm(
+        belongs_to = "super::todo_recurrences::Entity",
+        from = "Column::RecurrenceId",
+        to = "super::todo_recurrences::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    TodoRecurrences,
+    #[sea_orm(has_many = "super::todo_reminders::Entity")]
+    TodoReminders,
+    #[sea_orm(has_many = "super::todo_shares::Entity")]
+    TodoShares,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -49,6 +80,42 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::projects::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Projects.def()
+    }
+}
+
+impl Related<super::time_entries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeEntries.def()
+    }
+}
+
+impl Related<super::todo_attachments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoAttachments.def()
+    }
+}
+
+impl Related<super::todo_comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoComments.def()
+    }
+}
+
+impl Related<super::todo_reminders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoReminders.def()
+    }
+}
+
+impl Related<super::todo_shares::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoShares.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -25,8 +25,76 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::app_passwords::Entity")]
+    AppPasswords,
+    #[sea_orm(has_many = "super::calendar_feeds::Entity")]
+    CalendarFeeds,
+    #[sea_orm(has_many = "super::projects::Entity")]
+    Projects,
+    #[sea_orm(has_many = "super::time_entries::Entity")]
+    TimeEntries,
+    #[sea_orm(has_many = "super::todo_attachments::Entity")]
+    TodoAttachments,
+    #[sea_orm(has_many = "super::todo_comment_mentions::Entity")]
+    TodoCommentMentions,
+    #[sea_orm(has_many = "super::todo_comments::Entity")]
+    TodoComments,
+    #[sea_orm(has_many = "super::todo_history::Entity")]
+    TodoHistory,
     #[sea_orm(has_many = "super::todo_list::Entity")]
     TodoList,
+    #[sea_orm(has_many = "super::todo_recurrences::Entity")]
+    TodoRecurrences,
+    #[sea_orm(has_many = "super::todo_reminders::Entity")]
+    TodoReminders,
+}
+
+impl Related<super::app_passwords::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AppPasswords.def()
+    }
+}
+
+impl Related<super::calendar_feeds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CalendarFeeds.def()
+    }
+}
+
+impl Related<super::projects::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Projects.def()
+    }
+}
+
+impl Related<super::time_entries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeEntries.def()
+    }
+}
+
+impl Related<super::todo_attachments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoAttachments.def()
+    }
+}
+
+impl Related<super::todo_comment_mentions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoCommentMentions.def()
+    }
+}
+
+impl Related<super::todo_comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoComments.def()
+    }
+}
+
+impl Related<super::todo_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoHistory.def()
+    }
 }
 
 impl Related<super::todo_list::Entity> for Entity {
@@ -35,4 +103,16 @@ impl Related<super::todo_list::Entity> for Entity {
     }
 }
 
+impl Related<super::todo_recurrences::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoRecurrences.def()
+    }
+}
+
+impl Related<super::todo_reminders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoReminders.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
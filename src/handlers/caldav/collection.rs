@@ -0,0 +1,265 @@
+use crate::calendar::dav::{
+    CalendarFilter, Multistatus, NS_DAV, PropRequest, ReportRequest, parse_propfind, parse_report,
+};
+use crate::calendar::vtodo::status_to_ical;
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::caldav::{
+    DavResource, bad_request, collection_href, dav_error, depth, find_by_names, home_href,
+    load_ctag, load_parent_uids, method_not_allowed, multistatus_response, name_from_href,
+    options_response, principal_href, resource_name, root_href, wants_calendar_data, write_todos,
+};
+use crate::handlers::todo::access::accessible_todo_condition;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::ApiResult;
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::response::Response;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Select,
+};
+use std::collections::HashMap;
+
+/// 只读集合（入口、principal、日历主目录）支持的方法
+const READONLY_ALLOW: &str = "OPTIONS, PROPFIND";
+/// 日历集合支持的方法
+const COLLECTION_ALLOW: &str = "OPTIONS, PROPFIND, REPORT";
+
+/// 只读集合的 PROPFIND，Depth 为 1 时同时输出子资源
+fn propfind_static(
+    resource: DavResource,
+    href: &str,
+    children: Vec<(String, DavResource)>,
+    headers: &HeaderMap,
+    body: &str,
+    principal: &Principal,
+) -> Response {
+    let request = match parse_propfind(body) {
+        Ok(request) => request,
+        Err(e) => return bad_request(e),
+    };
+    let mut multistatus = Multistatus::new();
+    resource.write(&mut multistatus, href, &request, principal);
+    if depth(headers) > 0 {
+        for (href, child) in children {
+            child.write(&mut multistatus, &href, &request, principal);
+        }
+    }
+    multistatus_response(multistatus)
+}
+
+/// CalDAV 入口 `/caldav/`，客户端从这里发现 current-user-principal
+#[debug_handler]
+pub async fn caldav_root_handler(
+    Extension(principal): Extension<Principal>,
+    method: Method,
+    headers: HeaderMap,
+    body: String,
+) -> ApiResult<Response> {
+    Ok(match method.as_str() {
+        "OPTIONS" => options_response(READONLY_ALLOW),
+        "PROPFIND" => propfind_static(
+            DavResource::Root,
+            &root_href(),
+            vec![
+                (principal_href(), DavResource::Principal),
+                (home_href(), DavResource::Home),
+            ],
+            &headers,
+            &body,
+            &principal,
+        ),
+        _ => method_not_allowed(READONLY_ALLOW),
+    })
+}
+
+/// 当前用户 `/caldav/principal/`，提供 calendar-home-set
+#[debug_handler]
+pub async fn caldav_principal_handler(
+    Extension(principal): Extension<Principal>,
+    method: Method,
+    headers: HeaderMap,
+    body: String,
+) -> ApiResult<Response> {
+    Ok(match method.as_str() {
+        "OPTIONS" => options_response(READONLY_ALLOW),
+        "PROPFIND" => propfind_static(
+            DavResource::Principal,
+            &principal_href(),
+            vec![],
+            &headers,
+            &body,
+            &principal,
+        ),
+        _ => method_not_allowed(READONLY_ALLOW),
+    })
+}
+
+/// 日历主目录 `/caldav/calendars/`，只包含一个待办事项日历集合
+#[debug_handler]
+pub async fn caldav_home_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    method: Method,
+    headers: HeaderMap,
+    body: String,
+) -> ApiResult<Response> {
+    Ok(match method.as_str() {
+        "OPTIONS" => options_response(READONLY_ALLOW),
+        "PROPFIND" => {
+            let ctag = if depth(&headers) > 0 {
+                load_ctag(db_pool, principal.id as i32).await?
+            } else {
+                String::new()
+            };
+            propfind_static(
+                DavResource::Home,
+                &home_href(),
+                vec![(collection_href(), DavResource::Collection { ctag: &ctag })],
+                &headers,
+                &body,
+                &principal,
+            )
+        }
+        _ => method_not_allowed(READONLY_ALLOW),
+    })
+}
+
+/// 当前用户可以访问的全部待办事项（包括共享的）
+fn user_todos(user_id: i32) -> Select<TodoList> {
+    TodoList::find()
+        .filter(accessible_todo_condition(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .order_by_asc(todo_list::Column::Id)
+}
+
+/// 需要 calendar-data 时查询父任务的 UID
+async fn parent_uids_for<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todos: &[todo_list::Model],
+    request: &PropRequest,
+) -> ApiResult<HashMap<i32, String>> {
+    if wants_calendar_data(request) {
+        load_parent_uids(db, user_id, todos).await
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+/// 按 calendar-query 的过滤条件查询待办事项
+///
+/// 截止时间按 RFC 4791 9.9 处理：有截止时间时要求 start < DUE <= end，没有截止时间的总是匹配。
+async fn query_todos<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    filter: &CalendarFilter,
+) -> ApiResult<Vec<todo_list::Model>> {
+    if !filter.vtodo {
+        return Ok(vec![]);
+    }
+    let mut due_in_range = Condition::all().add(todo_list::Column::DueDate.is_not_null());
+    if let Some(start) = filter.start {
+        due_in_range = due_in_range.add(todo_list::Column::DueDate.gt(start));
+    }
+    if let Some(end) = filter.end {
+        due_in_range = due_in_range.add(todo_list::Column::DueDate.lte(end));
+    }
+    let mut select = user_todos(user_id).filter(
+        Condition::any()
+            .add(todo_list::Column::DueDate.is_null())
+            .add(due_in_range),
+    );
+    match filter.completed {
+        Some(true) => select = select.filter(todo_list::Column::CompletedAt.is_not_null()),
+        Some(false) => select = select.filter(todo_list::Column::CompletedAt.is_null()),
+        None => {}
+    }
+    let todos = select.all(db).await?;
+    let Some(text_match) = &filter.status else {
+        return Ok(todos);
+    };
+    let value = text_match.value.to_ascii_uppercase();
+    Ok(todos
+        .into_iter()
+        .filter(|todo| {
+            let status = status_to_ical(TodoStatus::from_db(todo.status.as_deref()));
+            status.contains(&value) != text_match.negate
+        })
+        .collect())
+}
+
+/// 待办事项日历集合 `/caldav/calendars/todos/`，支持 PROPFIND 和 REPORT
+#[debug_handler]
+#[tracing::instrument(name = "caldav collection", skip_all, fields(user_id = %principal.id, method = %method))]
+pub async fn caldav_collection_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    method: Method,
+    headers: HeaderMap,
+    body: String,
+) -> ApiResult<Response> {
+    let user_id = principal.id as i32;
+    match method.as_str() {
+        "OPTIONS" => Ok(options_response(COLLECTION_ALLOW)),
+        "PROPFIND" => {
+            let request = match parse_propfind(&body) {
+                Ok(request) => request,
+                Err(e) => return Ok(bad_request(e)),
+            };
+            let ctag = load_ctag(db_pool, user_id).await?;
+            let mut multistatus = Multistatus::new();
+            DavResource::Collection { ctag: &ctag }.write(
+                &mut multistatus,
+                &collection_href(),
+                &request,
+                &principal,
+            );
+            if depth(&headers) > 0 {
+                let todos = user_todos(user_id).all(db_pool).await?;
+                let parent_uids = parent_uids_for(db_pool, user_id, &todos, &request).await?;
+                write_todos(&mut multistatus, &todos, &parent_uids, &request, &principal);
+            }
+            Ok(multistatus_response(multistatus))
+        }
+        "REPORT" => {
+            let report = match parse_report(&body) {
+                Ok(report) => report,
+                Err(e) => return Ok(bad_request(e)),
+            };
+            let mut multistatus = Multistatus::new();
+            match report {
+                ReportRequest::CalendarQuery { props, filter } => {
+                    let todos = query_todos(db_pool, user_id, &filter).await?;
+                    let parent_uids = parent_uids_for(db_pool, user_id, &todos, &props).await?;
+                    write_todos(&mut multistatus, &todos, &parent_uids, &props, &principal);
+                }
+                ReportRequest::CalendarMultiget { props, hrefs } => {
+                    let names: Vec<String> =
+                        hrefs.iter().filter_map(|h| name_from_href(h)).collect();
+                    let todos = find_by_names(db_pool, user_id, &names).await?;
+                    let parent_uids = parent_uids_for(db_pool, user_id, &todos, &props).await?;
+                    write_todos(&mut multistatus, &todos, &parent_uids, &props, &principal);
+                    // 不存在的资源逐个返回 404
+                    for href in &hrefs {
+                        let found = name_from_href(href)
+                            .is_some_and(|name| todos.iter().any(|t| resource_name(t) == name));
+                        if !found {
+                            multistatus.response_status(href, "404 Not Found");
+                        }
+                    }
+                }
+                ReportRequest::Unsupported(name) => {
+                    tracing::warn!("不支持的 REPORT：{{{}}}{}", name.ns, name.name);
+                    return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "supported-report"));
+                }
+            }
+            Ok(multistatus_response(multistatus))
+        }
+        _ => Ok(method_not_allowed(COLLECTION_ALLOW)),
+    }
+}
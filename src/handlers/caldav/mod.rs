@@ -0,0 +1,497 @@
+//! CalDAV（RFC 4791）接口，把当前用户可以访问的待办事项映射为一个只包含 VTODO 的日历集合
+//!
+//! 集合中包括自己创建的和其他用户共享的待办事项，与 REST 接口使用同一套共享鉴权：
+//! 查看者可以读取，修改和删除共享的待办事项需要编辑者及以上角色，否则返回 403；
+//! 新建的资源总是属于当前用户。
+//!
+//! 资源结构：
+//! - `/caldav/`：入口，用于发现 current-user-principal
+//! - `/caldav/principal/`：当前用户
+//! - `/caldav/calendars/`：日历主目录（calendar-home-set）
+//! - `/caldav/calendars/todos/`：待办事项日历集合
+//! - `/caldav/calendars/todos/{name}.ics`：单个待办事项
+
+use crate::calendar::dav::{
+    DavName, Multistatus, NS_CALDAV, NS_CALSERVER, NS_DAV, PropRequest, decode_path_segment,
+    encode_path_segment, error_body, escape_xml, href_value,
+};
+use crate::calendar::vtodo::write_todo_resource;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::accessible_todo_condition;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::ApiResult;
+use crate::response::errors::ApiError;
+use crate::router::API_PREFIX;
+use axum::http::{HeaderMap, HeaderName, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::{HashMap, HashSet};
+
+pub mod collection;
+pub mod resource;
+
+/// 待办事项资源的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct DavResourceParam {
+    #[validate(length(min = 1, max = 255, message = "资源名长度必须在 1 到 255 之间"))]
+    pub file: String,
+}
+
+/// 支持的 WebDAV / CalDAV 功能，写在 OPTIONS 响应的 DAV 头中
+const DAV_COMPLIANCE: &str = "1, 3, calendar-access";
+/// 日历集合的名称
+const COLLECTION_NAME: &str = "todos";
+
+/// CalDAV 的根路径
+pub fn root_href() -> String {
+    format!("{API_PREFIX}/caldav/")
+}
+
+/// 当前用户（principal）的路径
+pub fn principal_href() -> String {
+    format!("{API_PREFIX}/caldav/principal/")
+}
+
+/// 日历主目录的路径
+pub fn home_href() -> String {
+    format!("{API_PREFIX}/caldav/calendars/")
+}
+
+/// 待办事项日历集合的路径
+pub fn collection_href() -> String {
+    format!("{API_PREFIX}/caldav/calendars/{COLLECTION_NAME}/")
+}
+
+/// 待办事项的资源名，客户端没有指定时使用 `{ical_uid}.ics`
+pub fn resource_name(todo: &todo_list::Model) -> String {
+    todo.caldav_name
+        .clone()
+        .unwrap_or_else(|| format!("{}.ics", todo.ical_uid))
+}
+
+/// 待办事项资源的路径
+pub fn todo_href(todo: &todo_list::Model) -> String {
+    format!(
+        "{}{}",
+        collection_href(),
+        encode_path_segment(&resource_name(todo))
+    )
+}
+
+/// 从 multiget 请求中的 href 解析出资源名，href 可以是完整的 URL，也可以只有路径
+pub fn name_from_href(href: &str) -> Option<String> {
+    let path = match href.find("://") {
+        Some(i) => &href[i + 3..][href[i + 3..].find('/')?..],
+        None => href,
+    };
+    let name = path.strip_prefix(&collection_href())?;
+    let name = decode_path_segment(name)?;
+    (!name.is_empty() && !name.contains('/')).then_some(name)
+}
+
+/// 待办事项的 ETag，由 id 和更新时间组成
+pub fn todo_etag(todo: &todo_list::Model) -> String {
+    let version = todo.updated_at.map_or(0, |t| t.timestamp_micros());
+    format!("\"{}-{}\"", todo.id, version)
+}
+
+/// HTTP 日期格式，例如 `Sun, 06 Nov 1994 08:49:37 GMT`
+fn http_date(todo: &todo_list::Model) -> Option<String> {
+    todo.updated_at.map(|t| {
+        t.with_timezone(&Utc)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string()
+    })
+}
+
+/// 按资源名批量查询当前用户可以访问的待办事项
+///
+/// 共享的待办事项与自己的资源名相同时（例如从同一个文件导入），优先返回自己的。
+pub async fn find_by_names<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    names: &[String],
+) -> ApiResult<Vec<todo_list::Model>> {
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+    let uids: Vec<&str> = names
+        .iter()
+        .filter_map(|n| n.strip_suffix(".ics"))
+        .collect();
+    let mut todos = TodoList::find()
+        .filter(accessible_todo_condition(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(todo_list::Column::CaldavName.is_in(names))
+                .add(
+                    Condition::all()
+                        .add(todo_list::Column::CaldavName.is_null())
+                        .add(todo_list::Column::IcalUid.is_in(uids)),
+                ),
+        )
+        .all(db)
+        .await?;
+    todos.sort_by_key(|t| (t.user_id != user_id, t.id));
+    let mut seen = HashSet::new();
+    // 按 UID 匹配到的记录如果已经有了自己的资源名，就不是请求中的资源
+    Ok(todos
+        .into_iter()
+        .filter(|t| {
+            let name = resource_name(t);
+            names.contains(&name) && seen.insert(name)
+        })
+        .collect())
+}
+
+/// 按资源名查询当前用户可以访问的待办事项
+pub async fn find_by_name<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    name: &str,
+) -> ApiResult<Option<todo_list::Model>> {
+    Ok(find_by_names(db, user_id, &[name.to_string()])
+        .await?
+        .into_iter()
+        .next())
+}
+
+/// 计算日历集合的版本（getctag），由全部可以访问的待办事项的 id 和更新时间计算，
+/// 删除、新增共享和取消共享也会改变
+pub async fn load_ctag<C: ConnectionTrait>(db: &C, user_id: i32) -> ApiResult<String> {
+    TodoList::find()
+        .select_only()
+        .column_as(
+            Expr::cust(
+                r#"md5(COALESCE(
+                    string_agg(todo_list.id::TEXT || ':' || COALESCE(todo_list.updated_at::TEXT, ''), ',' ORDER BY todo_list.id),
+                    ''
+                ))"#,
+            ),
+            "ctag",
+        )
+        .filter(accessible_todo_condition(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .into_tuple::<String>()
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("无法计算日历集合的 ctag")))
+}
+
+/// 查询待办事项的父任务 UID，用于生成 calendar-data 中的 RELATED-TO
+///
+/// 父任务不在当前用户可以访问的范围内时（例如共享的是子树）不输出。
+pub async fn load_parent_uid<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo: &todo_list::Model,
+) -> ApiResult<Option<String>> {
+    let todos = std::slice::from_ref(todo);
+    Ok(load_parent_uids(db, user_id, todos).await?.remove(&todo.id))
+}
+
+/// 批量查询父任务 UID，返回待办事项 id 到父任务 UID 的映射
+pub async fn load_parent_uids<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todos: &[todo_list::Model],
+) -> ApiResult<HashMap<i32, String>> {
+    let parent_ids: Vec<i32> = todos.iter().filter_map(|t| t.parent_id).collect();
+    if parent_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let parents: HashMap<i32, String> = TodoList::find()
+        .filter(accessible_todo_condition(user_id))
+        .filter(todo_list::Column::Id.is_in(parent_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t.ical_uid))
+        .collect();
+    Ok(todos
+        .iter()
+        .filter_map(|t| Some((t.id, parents.get(&t.parent_id?)?.clone())))
+        .collect())
+}
+
+/// CalDAV 中的资源
+pub enum DavResource<'a> {
+    Root,
+    Principal,
+    Home,
+    Collection {
+        ctag: &'a str,
+    },
+    Todo {
+        todo: &'a todo_list::Model,
+        /// 只有请求了 calendar-data 时才生成
+        calendar_data: Option<String>,
+    },
+}
+
+impl DavResource<'_> {
+    /// 资源支持的全部属性
+    fn supported_props(&self) -> Vec<DavName> {
+        let mut props = vec![
+            DavName::new(NS_DAV, "resourcetype"),
+            DavName::new(NS_DAV, "displayname"),
+            DavName::new(NS_DAV, "current-user-principal"),
+            DavName::new(NS_DAV, "current-user-privilege-set"),
+        ];
+        match self {
+            DavResource::Root | DavResource::Home => {
+                props.push(DavName::new(NS_CALDAV, "calendar-home-set"));
+            }
+            DavResource::Principal => {
+                props.push(DavName::new(NS_DAV, "principal-URL"));
+                props.push(DavName::new(NS_CALDAV, "calendar-home-set"));
+            }
+            DavResource::Collection { .. } => {
+                props.push(DavName::new(NS_DAV, "owner"));
+                props.push(DavName::new(NS_DAV, "getetag"));
+                props.push(DavName::new(NS_DAV, "supported-report-set"));
+                props.push(DavName::new(NS_CALSERVER, "getctag"));
+                props.push(DavName::new(NS_CALDAV, "supported-calendar-component-set"));
+                props.push(DavName::new(NS_CALDAV, "supported-calendar-data"));
+            }
+            DavResource::Todo { .. } => {
+                props.push(DavName::new(NS_DAV, "owner"));
+                props.push(DavName::new(NS_DAV, "getetag"));
+                props.push(DavName::new(NS_DAV, "getcontenttype"));
+                props.push(DavName::new(NS_DAV, "getlastmodified"));
+                props.push(DavName::new(NS_CALDAV, "calendar-data"));
+            }
+        }
+        props
+    }
+
+    /// allprop 返回的属性（不包含 calendar-data 等开销较大的属性）
+    fn all_props(&self) -> Vec<DavName> {
+        self.supported_props()
+            .into_iter()
+            .filter(|p| {
+                p.is(NS_DAV, "resourcetype")
+                    || p.is(NS_DAV, "displayname")
+                    || p.is(NS_DAV, "getetag")
+                    || p.is(NS_DAV, "getcontenttype")
+                    || p.is(NS_DAV, "getlastmodified")
+                    || p.is(NS_CALSERVER, "getctag")
+            })
+            .collect()
+    }
+
+    /// 属性值（已经转义好的 XML 片段），不支持的属性返回空
+    fn prop_value(&self, prop: &DavName, principal: &Principal) -> Option<String> {
+        let writable = matches!(
+            self,
+            DavResource::Collection { .. } | DavResource::Todo { .. }
+        );
+        let value = match (prop.ns.as_str(), prop.name.as_str()) {
+            (NS_DAV, "resourcetype") => match self {
+                DavResource::Root | DavResource::Home => String::from("<d:collection/>"),
+                DavResource::Principal => String::from("<d:principal/>"),
+                DavResource::Collection { .. } => String::from("<d:collection/><c:calendar/>"),
+                DavResource::Todo { .. } => String::new(),
+            },
+            (NS_DAV, "displayname") => escape_xml(match self {
+                DavResource::Root => "CalDAV",
+                DavResource::Principal => &principal.name,
+                DavResource::Home => "日历",
+                DavResource::Collection { .. } => "待办事项",
+                DavResource::Todo { todo, .. } => &todo.title,
+            }),
+            (NS_DAV, "current-user-principal") => href_value(&principal_href()),
+            (NS_DAV, "current-user-privilege-set") => {
+                let privileges: &[&str] = if writable {
+                    &[
+                        "read",
+                        "write",
+                        "write-content",
+                        "bind",
+                        "unbind",
+                        "read-current-user-privilege-set",
+                    ]
+                } else {
+                    &["read", "read-current-user-privilege-set"]
+                };
+                privileges
+                    .iter()
+                    .map(|p| format!("<d:privilege><d:{p}/></d:privilege>"))
+                    .collect()
+            }
+            (NS_DAV, "principal-URL") if matches!(self, DavResource::Principal) => {
+                href_value(&principal_href())
+            }
+            (NS_CALDAV, "calendar-home-set") if !writable => href_value(&home_href()),
+            (NS_DAV, "owner") if writable => href_value(&principal_href()),
+            (NS_DAV, "getetag") => match self {
+                DavResource::Collection { ctag } => escape_xml(&format!("\"{ctag}\"")),
+                DavResource::Todo { todo, .. } => escape_xml(&todo_etag(todo)),
+                _ => return None,
+            },
+            (NS_CALSERVER, "getctag") => match self {
+                DavResource::Collection { ctag } => escape_xml(ctag),
+                _ => return None,
+            },
+            (NS_DAV, "supported-report-set") if matches!(self, DavResource::Collection { .. }) => {
+                ["calendar-query", "calendar-multiget"]
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "<d:supported-report><d:report><c:{r}/></d:report></d:supported-report>"
+                        )
+                    })
+                    .collect()
+            }
+            (NS_CALDAV, "supported-calendar-component-set")
+                if matches!(self, DavResource::Collection { .. }) =>
+            {
+                String::from(r#"<c:comp name="VTODO"/>"#)
+            }
+            (NS_CALDAV, "supported-calendar-data")
+                if matches!(self, DavResource::Collection { .. }) =>
+            {
+                String::from(r#"<c:calendar-data content-type="text/calendar" version="2.0"/>"#)
+            }
+            (NS_DAV, "getcontenttype") if matches!(self, DavResource::Todo { .. }) => {
+                String::from("text/calendar; charset=utf-8; component=VTODO")
+            }
+            (NS_DAV, "getlastmodified") => match self {
+                DavResource::Todo { todo, .. } => http_date(todo)?,
+                _ => return None,
+            },
+            (NS_CALDAV, "calendar-data") => match self {
+                DavResource::Todo {
+                    calendar_data: Some(data),
+                    ..
+                } => escape_xml(data),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// 按请求输出资源的属性
+    pub fn write(
+        &self,
+        multistatus: &mut Multistatus,
+        href: &str,
+        request: &PropRequest,
+        principal: &Principal,
+    ) {
+        match request {
+            PropRequest::PropName => multistatus.response(
+                href,
+                self.supported_props()
+                    .into_iter()
+                    .map(|p| (p, String::new()))
+                    .collect(),
+                vec![],
+            ),
+            PropRequest::AllProp => multistatus.response(
+                href,
+                self.all_props()
+                    .into_iter()
+                    .filter_map(|p| self.prop_value(&p, principal).map(|v| (p, v)))
+                    .collect(),
+                vec![],
+            ),
+            PropRequest::Prop(props) => {
+                let mut found = vec![];
+                let mut missing = vec![];
+                for prop in props {
+                    match self.prop_value(prop, principal) {
+                        Some(value) => found.push((prop.clone(), value)),
+                        None => missing.push(prop.clone()),
+                    }
+                }
+                multistatus.response(href, found, missing);
+            }
+        }
+    }
+}
+
+/// 请求中是否要求返回 calendar-data
+pub fn wants_calendar_data(request: &PropRequest) -> bool {
+    matches!(request, PropRequest::Prop(props) if props.iter().any(|p| p.is(NS_CALDAV, "calendar-data")))
+}
+
+/// 输出待办事项资源，需要时生成 calendar-data
+pub fn write_todos(
+    multistatus: &mut Multistatus,
+    todos: &[todo_list::Model],
+    parent_uids: &HashMap<i32, String>,
+    request: &PropRequest,
+    principal: &Principal,
+) {
+    let with_data = wants_calendar_data(request);
+    for todo in todos {
+        let calendar_data = with_data.then(|| {
+            let parent_uid = todo
+                .parent_id
+                .and_then(|id| parent_uids.get(&id))
+                .map(String::as_str);
+            write_todo_resource(todo, parent_uid)
+        });
+        DavResource::Todo {
+            todo,
+            calendar_data,
+        }
+        .write(multistatus, &todo_href(todo), request, principal);
+    }
+}
+
+/// PROPFIND 的 Depth 请求头，缺省和 infinity 按 1 处理
+pub fn depth(headers: &HeaderMap) -> u8 {
+    match headers.get("depth").and_then(|v| v.to_str().ok()) {
+        Some("0") => 0,
+        _ => 1,
+    }
+}
+
+/// 207 Multi-Status 响应
+pub fn multistatus_response(multistatus: Multistatus) -> Response {
+    (
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        multistatus.finish(),
+    )
+        .into_response()
+}
+
+/// 带 WebDAV 前置条件的错误响应，例如 403 `<c:no-uid-conflict/>`
+pub fn dav_error(status: StatusCode, ns: &str, condition: &str) -> Response {
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        error_body(&DavName::new(ns, condition)),
+    )
+        .into_response()
+}
+
+/// 请求体格式错误
+pub fn bad_request(message: String) -> Response {
+    (StatusCode::BAD_REQUEST, message).into_response()
+}
+
+/// OPTIONS 响应，告诉客户端支持 CalDAV
+pub fn options_response(allow: &'static str) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::ALLOW, allow),
+            (HeaderName::from_static("dav"), DAV_COMPLIANCE),
+        ],
+    )
+        .into_response()
+}
+
+/// 405 Method Not Allowed
+pub fn method_not_allowed(allow: &'static str) -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)]).into_response()
+}
@@ -0,0 +1,295 @@
+use crate::calendar::dav::{Multistatus, NS_CALDAV, parse_propfind};
+use crate::calendar::vtodo::{parse_vtodos, write_todo_resource};
+use crate::common::valid::ValidPath;
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::caldav::{
+    DavResource, DavResourceParam, bad_request, dav_error, find_by_name, load_parent_uid,
+    method_not_allowed, multistatus_response, options_response, todo_etag, todo_href,
+    wants_calendar_data,
+};
+use crate::handlers::todo::access::todo_role;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::ical::{IcsImportAction, find_by_uid, link_parent, upsert_vtodo};
+use crate::handlers::todo::recurrence::spawn_next_occurrence;
+use crate::handlers::todo::trash::soft_delete_todo;
+use crate::handlers::todo::tree::lock_user_tree;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::ApiResult;
+use crate::response::errors::ApiError;
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+
+/// 待办事项资源支持的方法
+const RESOURCE_ALLOW: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND";
+
+/// ETag 列表（If-Match / If-None-Match）中是否包含指定的 ETag，`*` 匹配任意存在的资源
+fn etag_matches(value: &str, etag: Option<&str>) -> bool {
+    value.split(',').map(str::trim).any(|tag| match etag {
+        Some(etag) => tag == "*" || tag.trim_start_matches("W/") == etag,
+        None => false,
+    })
+}
+
+/// 检查 If-Match / If-None-Match 前置条件，不满足时返回 412
+fn check_preconditions(headers: &HeaderMap, existing: Option<&todo_list::Model>) -> bool {
+    let etag = existing.map(todo_etag);
+    if let Some(if_match) = headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok())
+        && !etag_matches(if_match, etag.as_deref())
+    {
+        return false;
+    }
+    if let Some(if_none_match) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        && etag_matches(if_none_match, etag.as_deref())
+    {
+        return false;
+    }
+    true
+}
+
+fn precondition_failed() -> Response {
+    StatusCode::PRECONDITION_FAILED.into_response()
+}
+
+/// 带 ETag 的空响应，用于 PUT 成功
+fn with_etag(status: StatusCode, todo: &todo_list::Model) -> Response {
+    (status, [(header::ETAG, todo_etag(todo))]).into_response()
+}
+
+/// GET / HEAD：返回只包含一个 VTODO 的日历
+async fn get_resource<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo: &todo_list::Model,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    let etag = todo_etag(todo);
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, Some(&etag)))
+    {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    let parent_uid = load_parent_uid(db, user_id, todo).await?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                String::from("text/calendar; charset=utf-8; component=VTODO"),
+            ),
+            (header::ETAG, etag),
+        ],
+        write_todo_resource(todo, parent_uid.as_deref()),
+    )
+        .into_response())
+}
+
+/// 是否可以修改待办事项：自己创建的，或者通过共享获得编辑者及以上角色
+async fn can_edit<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo: &todo_list::Model,
+) -> ApiResult<bool> {
+    Ok(todo_role(db, user_id, todo).await? >= Some(TodoRole::Editor))
+}
+
+/// PUT：按资源名新增或更新待办事项
+///
+/// 请求体必须是只包含一个 VTODO 的日历；同一个 UID 不能出现在两个资源中，也不能修改已有资源的 UID。
+/// 没有 RELATED-TO 时保留原来的父任务，避免不支持子任务的客户端把层级关系清空。
+/// 修改共享的待办事项需要编辑者及以上角色，修改结果仍然属于创建者。
+async fn put_resource(
+    db_pool: &sea_orm::DatabaseConnection,
+    history: &HistoryContext,
+    user_id: i32,
+    name: &str,
+    headers: &HeaderMap,
+    body: &str,
+) -> ApiResult<Response> {
+    let vtodos = match parse_vtodos(body) {
+        Ok(vtodos) => vtodos,
+        Err(e) => {
+            tracing::warn!("无法解析 CalDAV 资源 {}：{}", name, e);
+            return Ok(dav_error(
+                StatusCode::BAD_REQUEST,
+                NS_CALDAV,
+                "valid-calendar-data",
+            ));
+        }
+    };
+    let vtodo = match vtodos.as_slice() {
+        [] => {
+            return Ok(dav_error(
+                StatusCode::FORBIDDEN,
+                NS_CALDAV,
+                "supported-calendar-component",
+            ));
+        }
+        [Ok(vtodo)] => vtodo,
+        [Err((_, e))] => {
+            tracing::warn!("CalDAV 资源 {} 无效：{}", name, e);
+            return Ok(dav_error(
+                StatusCode::FORBIDDEN,
+                NS_CALDAV,
+                "valid-calendar-object-resource",
+            ));
+        }
+        _ => {
+            return Ok(dav_error(
+                StatusCode::FORBIDDEN,
+                NS_CALDAV,
+                "valid-calendar-object-resource",
+            ));
+        }
+    };
+    let txn = history.begin(db_pool).await?;
+    // 共享的待办事项写入创建者的任务树，按用户 id 顺序加锁，避免两个用户互相修改时死锁
+    let mut owners = vec![user_id];
+    if let Some(todo) = find_by_name(&txn, user_id, name).await?
+        && todo.user_id != user_id
+    {
+        owners.push(todo.user_id);
+        owners.sort_unstable();
+    }
+    for owner_id in owners {
+        lock_user_tree(&txn, owner_id).await?;
+    }
+    let existing = find_by_name(&txn, user_id, name).await?;
+    if let Some(todo) = &existing
+        && !can_edit(&txn, user_id, todo).await?
+    {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    if !check_preconditions(headers, existing.as_ref()) {
+        return Ok(precondition_failed());
+    }
+    let owner_id = existing.as_ref().map_or(user_id, |t| t.user_id);
+    let uid_taken = match &existing {
+        Some(todo) => todo.ical_uid != vtodo.uid,
+        None => find_by_uid(&txn, user_id, &vtodo.uid).await?.is_some(),
+    };
+    if uid_taken {
+        return Ok(dav_error(
+            StatusCode::FORBIDDEN,
+            NS_CALDAV,
+            "no-uid-conflict",
+        ));
+    }
+    let previous_status = existing
+        .as_ref()
+        .map(|t| TodoStatus::from_db(t.status.as_deref()));
+    let (action, todo) = upsert_vtodo(&txn, owner_id, vtodo).await?;
+    // 资源名与 UID 对应时不需要单独保存
+    let caldav_name = (name != format!("{}.ics", vtodo.uid)).then(|| name.to_string());
+    if todo.caldav_name != caldav_name {
+        TodoList::update_many()
+            .col_expr(todo_list::Column::CaldavName, Expr::value(caldav_name))
+            .filter(todo_list::Column::Id.eq(todo.id))
+            .exec(&txn)
+            .await?;
+    }
+    if let Some(parent_uid) = &vtodo.parent_uid
+        && let Some(message) =
+            link_parent(&txn, owner_id, todo.id, parent_uid, &HashMap::new()).await?
+    {
+        tracing::warn!("CalDAV 资源 {} {}", name, message);
+    }
+    if todo.status.as_deref() == Some(TodoStatus::Completed.as_str())
+        && previous_status != Some(TodoStatus::Completed)
+    {
+        spawn_next_occurrence(&txn, &todo).await?;
+    }
+    let todo = TodoList::find_by_id(todo.id)
+        .one(&txn)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    txn.commit().await?;
+    let status = match action {
+        IcsImportAction::Created => StatusCode::CREATED,
+        _ => StatusCode::NO_CONTENT,
+    };
+    Ok(with_etag(status, &todo))
+}
+
+/// DELETE：把待办事项连同子任务移入回收站，共享的待办事项需要编辑者及以上角色
+async fn delete_resource(
+    db_pool: &sea_orm::DatabaseConnection,
+    history: &HistoryContext,
+    user_id: i32,
+    name: &str,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    let txn = history.begin(db_pool).await?;
+    let Some(todo) = find_by_name(&txn, user_id, name).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    if !can_edit(&txn, user_id, &todo).await? {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    if !check_preconditions(headers, Some(&todo)) {
+        return Ok(precondition_failed());
+    }
+    soft_delete_todo(&txn, &todo).await?;
+    txn.commit().await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// 单个待办事项 `/caldav/calendars/todos/{name}.ics`
+#[debug_handler]
+#[tracing::instrument(name = "caldav resource", skip_all, fields(user_id = %principal.id, method = %method, file = %params.file))]
+pub async fn caldav_resource_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    method: Method,
+    ValidPath(params): ValidPath<DavResourceParam>,
+    headers: HeaderMap,
+    body: String,
+) -> ApiResult<Response> {
+    let user_id = principal.id as i32;
+    let name = params.file;
+    match method.as_str() {
+        "OPTIONS" => Ok(options_response(RESOURCE_ALLOW)),
+        "GET" | "HEAD" => match find_by_name(db_pool, user_id, &name).await? {
+            Some(todo) => get_resource(db_pool, user_id, &todo, &headers).await,
+            None => Ok(StatusCode::NOT_FOUND.into_response()),
+        },
+        "PUT" => put_resource(db_pool, &history, user_id, &name, &headers, &body).await,
+        "DELETE" => delete_resource(db_pool, &history, user_id, &name, &headers).await,
+        "PROPFIND" => {
+            let request = match parse_propfind(&body) {
+                Ok(request) => request,
+                Err(e) => return Ok(bad_request(e)),
+            };
+            let Some(todo) = find_by_name(db_pool, user_id, &name).await? else {
+                return Ok(StatusCode::NOT_FOUND.into_response());
+            };
+            let calendar_data = if wants_calendar_data(&request) {
+                let parent_uid = load_parent_uid(db_pool, user_id, &todo).await?;
+                Some(write_todo_resource(&todo, parent_uid.as_deref()))
+            } else {
+                None
+            };
+            let mut multistatus = Multistatus::new();
+            DavResource::Todo {
+                todo: &todo,
+                calendar_data,
+            }
+            .write(&mut multistatus, &todo_href(&todo), &request, &principal);
+            Ok(multistatus_response(multistatus))
+        }
+        _ => Ok(method_not_allowed(RESOURCE_ALLOW)),
+    }
+}
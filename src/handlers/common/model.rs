@@ -4,3 +4,17 @@ pub struct QueryUserByIdParam {
     #[validate(range(min = 1, message = "查询用户的 id 必须大于 0"))]
     pub id: i32,
 }
+
+/// 创建应用专用密码的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateAppPasswordParam {
+    #[validate(length(min = 1, max = 50, message = "名称长度必须在 1 到 50 之间"))]
+    pub name: String,
+}
+
+/// 应用专用密码 id 的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct AppPasswordIdParam {
+    #[validate(range(min = 1, message = "应用专用密码的 id 必须大于 0"))]
+    pub id: i32,
+}
@@ -1,2 +1,6 @@
+pub mod caldav;
 pub mod common;
+pub mod project;
+pub mod share;
+pub mod todo;
 pub mod user;
@@ -0,0 +1,357 @@
+use crate::common::valid::{ValidJson, ValidPath, ValidQuery};
+use crate::domain::rank::rank_between;
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::prelude::Projects;
+use crate::entities::{projects, todo_list};
+use crate::handlers::project::model::{
+    CreateProjectParam, PatchProjectParam, ProjectIdParam, ProjectListQuery,
+};
+use crate::handlers::todo::history::HistoryContext;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::{Expr, ExprTrait, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait,
+    FromQueryResult, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    Statement,
+};
+use std::collections::HashMap;
+
+/// 项目中待办事项的数量，包括子任务，不包括回收站中的待办事项
+#[derive(Debug, Default, Clone, serde::Serialize, FromQueryResult)]
+pub struct ProjectCounts {
+    pub total: i64,
+    /// 待处理和进行中
+    pub open: i64,
+    pub completed: i64,
+    /// 未完成且已过截止时间
+    pub overdue: i64,
+}
+
+/// 带有待办事项数量的项目
+#[derive(Debug, serde::Serialize)]
+pub struct ProjectWithCounts {
+    #[serde(flatten)]
+    pub project: projects::Model,
+    pub counts: ProjectCounts,
+}
+
+/// 项目列表，同时返回收件箱（不属于任何项目）中的数量
+#[derive(Debug, serde::Serialize)]
+pub struct ProjectOverview {
+    pub projects: Vec<ProjectWithCounts>,
+    pub inbox: ProjectCounts,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ProjectCountsRow {
+    project_id: Option<i32>,
+    total: i64,
+    open: i64,
+    completed: i64,
+    overdue: i64,
+}
+
+/// 查询属于当前用户的项目，不存在或不属于该用户时返回业务错误
+pub async fn find_user_project<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+) -> ApiResult<projects::Model> {
+    Projects::find_by_id(id)
+        .filter(projects::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("项目不存在或无权访问！")))
+}
+
+/// 查询可以放入待办事项的项目，已归档的项目不能再放入
+pub async fn find_writable_project<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+) -> ApiResult<projects::Model> {
+    let project = find_user_project(db, user_id, id).await?;
+    if project.archived {
+        return Err(ApiError::Biz(format!(
+            "项目「{}」已归档，请先取消归档！",
+            project.name
+        )));
+    }
+    Ok(project)
+}
+
+/// 默认视图的筛选条件：不显示已归档项目中的待办事项
+pub fn visible_project_condition() -> Condition {
+    Condition::any()
+        .add(todo_list::Column::ProjectId.is_null())
+        .add(
+            todo_list::Column::ProjectId.not_in_subquery(
+                Query::select()
+                    .column(projects::Column::Id)
+                    .from(projects::Entity)
+                    .and_where(projects::Column::Archived.eq(true))
+                    .to_owned(),
+            ),
+        )
+}
+
+/// 校验颜色格式为 #RRGGBB，统一转成小写
+fn normalize_color(color: &str) -> ApiResult<String> {
+    let hex = color.strip_prefix('#').unwrap_or_default();
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::ValidationError(String::from(
+            "颜色格式必须是 #RRGGBB",
+        )));
+    }
+    Ok(color.to_ascii_lowercase())
+}
+
+/// 去掉首尾空白后的项目名称，同一用户下不能重名
+async fn unique_name<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    name: &str,
+    except_id: Option<i32>,
+) -> ApiResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError::ValidationError(String::from("项目名称不能为空")));
+    }
+    let mut select = Projects::find()
+        .filter(projects::Column::UserId.eq(user_id))
+        .filter(projects::Column::Name.eq(name));
+    if let Some(id) = except_id {
+        select = select.filter(projects::Column::Id.ne(id));
+    }
+    if select.one(db).await?.is_some() {
+        return Err(ApiError::Biz(format!("项目「{name}」已存在！")));
+    }
+    Ok(name.to_string())
+}
+
+/// 统计各项目的待办事项数量，键为 None 的是收件箱
+async fn load_counts<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> ApiResult<HashMap<Option<i32>, ProjectCounts>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT project_id,
+                COUNT(*) AS total,
+                COUNT(*) FILTER (WHERE status IN ($2, $3)) AS open,
+                COUNT(*) FILTER (WHERE status = $4) AS completed,
+                COUNT(*) FILTER (WHERE status IN ($2, $3) AND due_date < $5) AS overdue
+            FROM todo_list
+            WHERE user_id = $1 AND deleted_at IS NULL
+            GROUP BY project_id"#,
+        [
+            user_id.into(),
+            TodoStatus::Pending.as_str().into(),
+            TodoStatus::InProgress.as_str().into(),
+            TodoStatus::Completed.as_str().into(),
+            get_local_datetime_with_timezone().into(),
+        ],
+    );
+    Ok(ProjectCountsRow::find_by_statement(stmt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| {
+            (
+                row.project_id,
+                ProjectCounts {
+                    total: row.total,
+                    open: row.open,
+                    completed: row.completed,
+                    overdue: row.overdue,
+                },
+            )
+        })
+        .collect())
+}
+
+/// 查询当前用户的项目及各项目中的待办事项数量
+#[debug_handler]
+pub async fn list_projects_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<ProjectListQuery>,
+) -> ApiResult<ApiResponse<ProjectOverview>> {
+    let user_id = principal.id as i32;
+    let mut select = Projects::find().filter(projects::Column::UserId.eq(user_id));
+    if params.include_archived != Some(true) {
+        select = select.filter(projects::Column::Archived.eq(false));
+    }
+    let projects = select
+        .order_by_asc(projects::Column::SortOrder)
+        .order_by_asc(projects::Column::Id)
+        .all(db_pool)
+        .await?;
+    let mut counts = load_counts(db_pool, user_id).await?;
+    let projects = projects
+        .into_iter()
+        .map(|project| ProjectWithCounts {
+            counts: counts.remove(&Some(project.id)).unwrap_or_default(),
+            project,
+        })
+        .collect();
+    Ok(ApiResponse::success(ProjectOverview {
+        projects,
+        inbox: counts.remove(&None).unwrap_or_default(),
+    }))
+}
+
+/// 按 id 查询项目及其中的待办事项数量
+#[debug_handler]
+pub async fn get_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<ProjectIdParam>,
+) -> ApiResult<ApiResponse<ProjectWithCounts>> {
+    let user_id = principal.id as i32;
+    let project = find_user_project(db_pool, user_id, params.id).await?;
+    let counts = load_counts(db_pool, user_id)
+        .await?
+        .remove(&Some(project.id))
+        .unwrap_or_default();
+    Ok(ApiResponse::success(ProjectWithCounts { project, counts }))
+}
+
+/// 创建项目
+#[debug_handler]
+#[tracing::instrument(name = "create project", skip_all, fields(user_id = %principal.id))]
+pub async fn create_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(params): ValidJson<CreateProjectParam>,
+) -> ApiResult<ApiResponse<projects::Model>> {
+    let user_id = principal.id as i32;
+    let name = unique_name(db_pool, user_id, &params.name, None).await?;
+    let color = params.color.as_deref().map(normalize_color).transpose()?;
+    // 未指定排序值时追加到末尾
+    let sort_order = match params.sort_order {
+        Some(sort_order) => sort_order,
+        None => {
+            let last: Option<Option<i32>> = Projects::find()
+                .select_only()
+                .expr(Expr::col(projects::Column::SortOrder).max())
+                .filter(projects::Column::UserId.eq(user_id))
+                .into_tuple()
+                .one(db_pool)
+                .await?;
+            rank_between(last.flatten(), None).unwrap_or(i32::MAX)
+        }
+    };
+    let project = projects::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(name),
+        color: Set(color),
+        icon: Set(params.icon),
+        sort_order: Set(sort_order),
+        ..Default::default()
+    }
+    .insert(db_pool)
+    .await?;
+    tracing::info!("ID为: {} 的用户创建了项目 {}", user_id, project.id);
+    Ok(ApiResponse::ok("创建成功！", Some(project)))
+}
+
+/// 部分更新项目（PATCH），只修改请求中出现的字段
+#[debug_handler]
+#[tracing::instrument(name = "patch project", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn patch_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<ProjectIdParam>,
+    ValidJson(params): ValidJson<PatchProjectParam>,
+) -> ApiResult<ApiResponse<projects::Model>> {
+    let user_id = principal.id as i32;
+    let model = find_user_project(db_pool, user_id, path.id).await?;
+    let mut project = model.clone().into_active_model();
+    if let Some(name) = params.name {
+        project
+            .name
+            .set_if_not_equals(unique_name(db_pool, user_id, &name, Some(model.id)).await?);
+    }
+    if let Some(color) = params.color {
+        project
+            .color
+            .set_if_not_equals(color.as_deref().map(normalize_color).transpose()?);
+    }
+    if let Some(icon) = params.icon {
+        project.icon.set_if_not_equals(icon);
+    }
+    if let Some(sort_order) = params.sort_order {
+        project.sort_order.set_if_not_equals(sort_order);
+    }
+    if !project.is_changed() {
+        return Ok(ApiResponse::ok("没有需要更新的内容", Some(model)));
+    }
+    let project = project.update(db_pool).await?;
+    Ok(ApiResponse::ok("更新成功！", Some(project)))
+}
+
+/// 修改项目的归档状态
+async fn set_archived(
+    db_pool: &sea_orm::DatabaseConnection,
+    user_id: i32,
+    id: i32,
+    archived: bool,
+) -> ApiResult<projects::Model> {
+    let model = find_user_project(db_pool, user_id, id).await?;
+    if model.archived == archived {
+        return Ok(model);
+    }
+    let mut project = model.into_active_model();
+    project.archived = Set(archived);
+    Ok(project.update(db_pool).await?)
+}
+
+/// 归档项目，项目中的待办事项不再出现在默认视图中，按项目查询时仍然可以看到
+#[debug_handler]
+#[tracing::instrument(name = "archive project", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn archive_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<ProjectIdParam>,
+) -> ApiResult<ApiResponse<projects::Model>> {
+    let project = set_archived(db_pool, principal.id as i32, params.id, true).await?;
+    Ok(ApiResponse::ok("已归档！", Some(project)))
+}
+
+/// 取消归档项目
+#[debug_handler]
+#[tracing::instrument(name = "unarchive project", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn unarchive_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<ProjectIdParam>,
+) -> ApiResult<ApiResponse<projects::Model>> {
+    let project = set_archived(db_pool, principal.id as i32, params.id, false).await?;
+    Ok(ApiResponse::ok("已取消归档！", Some(project)))
+}
+
+/// 删除项目，其中的待办事项不会被删除，而是回到收件箱
+#[debug_handler]
+#[tracing::instrument(name = "delete project", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn delete_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<ProjectIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    // 待办事项的 project_id 由外键置空，在事务中执行以便修改历史记录操作人
+    let txn = history.begin(db_pool).await?;
+    let project = find_user_project(&txn, principal.id as i32, params.id).await?;
+    project.delete(&txn).await?;
+    txn.commit().await?;
+    tracing::info!("ID为: {} 的用户删除了项目 {}", principal.id, params.id);
+    Ok(ApiResponse::success_with_msg("删除成功！"))
+}
@@ -0,0 +1,43 @@
+use crate::common::serde::deserialize_optional_field;
+
+/// 按 id 操作项目时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct ProjectIdParam {
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub id: i32,
+}
+
+/// 创建项目的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateProjectParam {
+    #[validate(length(min = 1, max = 50, message = "项目名称长度必须在 1 到 50 之间"))]
+    pub name: String,
+    /// 颜色，格式为 #RRGGBB
+    #[validate(length(equal = 7, message = "颜色格式必须是 #RRGGBB"))]
+    pub color: Option<String>,
+    #[validate(length(min = 1, max = 50, message = "图标长度必须在 1 到 50 之间"))]
+    pub icon: Option<String>,
+    /// 未指定时排在最后
+    pub sort_order: Option<i32>,
+}
+
+/// 部分更新项目的参数（PATCH），只修改传入的字段，颜色和图标传 null 表示清空
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct PatchProjectParam {
+    #[validate(length(min = 1, max = 50, message = "项目名称长度必须在 1 到 50 之间"))]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    #[validate(length(equal = 7, message = "颜色格式必须是 #RRGGBB"))]
+    pub color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    #[validate(length(min = 1, max = 50, message = "图标长度必须在 1 到 50 之间"))]
+    pub icon: Option<Option<String>>,
+    pub sort_order: Option<i32>,
+}
+
+/// 查询项目列表的参数
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct ProjectListQuery {
+    /// 是否包含已归档的项目，默认不包含
+    pub include_archived: Option<bool>,
+}
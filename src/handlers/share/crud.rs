@@ -0,0 +1,385 @@
+use crate::common::valid::{ValidJson, ValidPath, ValidQuery};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::{Projects, TodoShares, Users};
+use crate::entities::{projects, todo_shares, users};
+use crate::handlers::share::model::{
+    CreateShareParam, PatchShareParam, ShareIdParam, ShareTargetQuery,
+};
+use crate::handlers::todo::access::authorize_todo;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult,
+    IntoActiveModel, ModelTrait, QueryFilter, Set, Statement, TransactionTrait, Value,
+};
+
+/// 共享详情，附带创建者、成员的用户名以及共享对象的名称
+#[derive(Debug, serde::Serialize, FromQueryResult)]
+pub struct ShareDetail {
+    pub id: i32,
+    pub owner_id: i32,
+    pub owner_name: String,
+    pub member_id: i32,
+    pub member_name: String,
+    pub todo_id: Option<i32>,
+    pub todo_title: Option<String>,
+    pub project_id: Option<i32>,
+    pub project_name: Option<String>,
+    pub role: String,
+    pub invited_by: i32,
+    /// 为空表示邀请还没有被接受
+    pub accepted_at: Option<DateTimeWithTimeZone>,
+    pub created_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 共享的对象，`owner_id` 是待办事项或项目的创建者
+#[derive(Debug, Clone, Copy)]
+struct ShareTarget {
+    owner_id: i32,
+    todo_id: Option<i32>,
+    project_id: Option<i32>,
+}
+
+/// 按筛选条件查询共享详情，`condition` 中的参数从 $1 开始编号
+async fn load_shares<C: ConnectionTrait>(
+    db: &C,
+    condition: &str,
+    values: Vec<Value>,
+) -> ApiResult<Vec<ShareDetail>> {
+    let sql = format!(
+        r#"SELECT s.id, s.owner_id, o.username AS owner_name, s.member_id, m.username AS member_name,
+                s.todo_id, t.title AS todo_title, s.project_id, p.name AS project_name,
+                s.role, s.invited_by, s.accepted_at, s.created_at
+            FROM todo_shares s
+            JOIN users o ON o.id = s.owner_id
+            JOIN users m ON m.id = s.member_id
+            LEFT JOIN todo_list t ON t.id = s.todo_id
+            LEFT JOIN projects p ON p.id = s.project_id
+            WHERE {condition}
+            ORDER BY s.id"#
+    );
+    Ok(
+        ShareDetail::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            values,
+        ))
+        .all(db)
+        .await?,
+    )
+}
+
+/// 查询一条共享的详情
+async fn load_share<C: ConnectionTrait>(db: &C, id: i32) -> ApiResult<ShareDetail> {
+    load_shares(db, "s.id = $1", vec![id.into()])
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::Biz(String::from("共享不存在！")))
+}
+
+/// 当前用户在项目上的角色：创建者是所有者，其他用户取已接受的共享中的角色
+async fn project_role<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    project: &projects::Model,
+) -> ApiResult<Option<TodoRole>> {
+    if project.user_id == user_id {
+        return Ok(Some(TodoRole::Owner));
+    }
+    let share = TodoShares::find()
+        .filter(todo_shares::Column::ProjectId.eq(project.id))
+        .filter(todo_shares::Column::MemberId.eq(user_id))
+        .filter(todo_shares::Column::AcceptedAt.is_not_null())
+        .one(db)
+        .await?;
+    Ok(share.and_then(|share| share.role.parse().ok()))
+}
+
+/// 校验当前用户在共享对象上的角色不低于 `required`
+async fn authorize_target<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo_id: Option<i32>,
+    project_id: Option<i32>,
+    required: TodoRole,
+) -> ApiResult<ShareTarget> {
+    match (todo_id, project_id) {
+        (Some(todo_id), None) => {
+            let todo = authorize_todo(db, user_id, todo_id, required).await?;
+            Ok(ShareTarget {
+                owner_id: todo.user_id,
+                todo_id: Some(todo.id),
+                project_id: None,
+            })
+        }
+        (None, Some(project_id)) => {
+            let no_access = || ApiError::Biz(String::from("项目不存在或无权访问！"));
+            let project = Projects::find_by_id(project_id)
+                .one(db)
+                .await?
+                .ok_or_else(no_access)?;
+            match project_role(db, user_id, &project).await? {
+                None => Err(no_access()),
+                Some(role) if role < required => Err(ApiError::Biz(format!(
+                    "你是该项目的{}，需要{}权限才能执行此操作！",
+                    role.label(),
+                    required.label()
+                ))),
+                Some(_) => Ok(ShareTarget {
+                    owner_id: project.user_id,
+                    todo_id: None,
+                    project_id: Some(project.id),
+                }),
+            }
+        }
+        _ => Err(ApiError::Biz(String::from(
+            "请指定要共享的待办事项或项目（只能指定一个）！",
+        ))),
+    }
+}
+
+/// 查询共享本身，并校验当前用户是共享对象的所有者
+async fn find_managed_share<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+) -> ApiResult<todo_shares::Model> {
+    let share = TodoShares::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("共享不存在！")))?;
+    authorize_target(
+        db,
+        user_id,
+        share.todo_id,
+        share.project_id,
+        TodoRole::Owner,
+    )
+    .await?;
+    Ok(share)
+}
+
+/// 查询共享给当前用户、还没有接受的邀请
+async fn find_invitation<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+) -> ApiResult<todo_shares::Model> {
+    TodoShares::find_by_id(id)
+        .filter(todo_shares::Column::MemberId.eq(user_id))
+        .filter(todo_shares::Column::AcceptedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("邀请不存在或已处理！")))
+}
+
+/// 查询待办事项或项目的协作成员（包括还没有接受邀请的），查看者及以上角色可以查询
+#[debug_handler]
+pub async fn list_shares_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<ShareTargetQuery>,
+) -> ApiResult<ApiResponse<Vec<ShareDetail>>> {
+    let target = authorize_target(
+        db_pool,
+        principal.id as i32,
+        params.todo_id,
+        params.project_id,
+        TodoRole::Viewer,
+    )
+    .await?;
+    let shares = match (target.todo_id, target.project_id) {
+        (Some(todo_id), _) => load_shares(db_pool, "s.todo_id = $1", vec![todo_id.into()]).await?,
+        (_, Some(project_id)) => {
+            load_shares(db_pool, "s.project_id = $1", vec![project_id.into()]).await?
+        }
+        _ => Vec::new(),
+    };
+    Ok(ApiResponse::success(shares))
+}
+
+/// 邀请其他用户协作，对方接受邀请后才能访问
+///
+/// 只有所有者可以邀请，待办事项的共享包括它的全部子任务，项目的共享包括项目中的全部待办事项。
+#[debug_handler]
+#[tracing::instrument(name = "create share", skip_all, fields(user_id = %principal.id, username = %params.username, role = %params.role))]
+pub async fn create_share_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(params): ValidJson<CreateShareParam>,
+) -> ApiResult<ApiResponse<ShareDetail>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let target = authorize_target(
+        &txn,
+        user_id,
+        params.todo_id,
+        params.project_id,
+        TodoRole::Owner,
+    )
+    .await?;
+    let member = Users::find()
+        .filter(users::Column::Username.eq(&params.username))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| ApiError::Biz(format!("用户「{}」不存在！", params.username)))?;
+    if member.id == user_id {
+        return Err(ApiError::Biz(String::from("不能邀请自己！")));
+    }
+    if member.id == target.owner_id {
+        return Err(ApiError::Biz(String::from("该用户是创建者，无需邀请！")));
+    }
+    let existing = TodoShares::find()
+        .filter(todo_shares::Column::MemberId.eq(member.id))
+        .filter(match (target.todo_id, target.project_id) {
+            (Some(todo_id), _) => todo_shares::Column::TodoId.eq(todo_id),
+            _ => todo_shares::Column::ProjectId.eq(target.project_id),
+        })
+        .one(&txn)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::Biz(format!(
+            "已经邀请过用户「{}」，请直接修改角色！",
+            params.username
+        )));
+    }
+    let share = todo_shares::ActiveModel {
+        owner_id: Set(target.owner_id),
+        member_id: Set(member.id),
+        todo_id: Set(target.todo_id),
+        project_id: Set(target.project_id),
+        role: Set(params.role.as_str().to_string()),
+        invited_by: Set(user_id),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let detail = load_share(&txn, share.id).await?;
+    txn.commit().await?;
+    tracing::info!(
+        "ID为: {} 的用户邀请了用户 {} 作为{}",
+        principal.id,
+        member.id,
+        params.role.label()
+    );
+    Ok(ApiResponse::ok("邀请已发送，等待对方接受！", Some(detail)))
+}
+
+/// 修改成员的角色，只有所有者可以修改
+#[debug_handler]
+#[tracing::instrument(name = "patch share", skip_all, fields(user_id = %principal.id, id = %path.id, role = %params.role))]
+pub async fn patch_share_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<ShareIdParam>,
+    ValidJson(params): ValidJson<PatchShareParam>,
+) -> ApiResult<ApiResponse<ShareDetail>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let share = find_managed_share(&txn, user_id, path.id).await?;
+    if share.member_id == user_id {
+        return Err(ApiError::Biz(String::from("不能修改自己的角色！")));
+    }
+    let mut share = share.into_active_model();
+    share
+        .role
+        .set_if_not_equals(params.role.as_str().to_string());
+    if share.is_changed() {
+        share.update(&txn).await?;
+    }
+    let detail = load_share(&txn, path.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("角色已修改！", Some(detail)))
+}
+
+/// 移除成员或撤回邀请；成员也可以通过这个接口退出共享
+#[debug_handler]
+#[tracing::instrument(name = "delete share", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn delete_share_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<ShareIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let user_id = principal.id as i32;
+    let share = TodoShares::find_by_id(path.id)
+        .filter(todo_shares::Column::MemberId.eq(user_id))
+        .one(db_pool)
+        .await?;
+    let share = match share {
+        Some(share) => share,
+        None => find_managed_share(db_pool, user_id, path.id).await?,
+    };
+    share.delete(db_pool).await?;
+    tracing::info!("ID为: {} 的用户删除了共享 {}", principal.id, path.id);
+    Ok(ApiResponse::success_with_msg("已移除！"))
+}
+
+/// 查询发给当前用户、还没有处理的邀请
+#[debug_handler]
+pub async fn list_invitations_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Vec<ShareDetail>>> {
+    let shares = load_shares(
+        db_pool,
+        "s.member_id = $1 AND s.accepted_at IS NULL",
+        vec![(principal.id as i32).into()],
+    )
+    .await?;
+    Ok(ApiResponse::success(shares))
+}
+
+/// 接受邀请，接受之后共享的待办事项会出现在列表中
+#[debug_handler]
+#[tracing::instrument(name = "accept invitation", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn accept_invitation_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<ShareIdParam>,
+) -> ApiResult<ApiResponse<ShareDetail>> {
+    let invitation = find_invitation(db_pool, principal.id as i32, path.id).await?;
+    let mut invitation = invitation.into_active_model();
+    invitation.accepted_at = Set(Some(get_local_datetime_with_timezone()));
+    invitation.update(db_pool).await?;
+    Ok(ApiResponse::ok(
+        "已接受邀请！",
+        Some(load_share(db_pool, path.id).await?),
+    ))
+}
+
+/// 拒绝邀请，邀请会被删除，之后可以重新邀请
+#[debug_handler]
+#[tracing::instrument(name = "decline invitation", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn decline_invitation_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<ShareIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    find_invitation(db_pool, principal.id as i32, path.id)
+        .await?
+        .delete(db_pool)
+        .await?;
+    Ok(ApiResponse::success_with_msg("已拒绝邀请！"))
+}
+
+/// 查询其他用户共享给当前用户的待办事项和项目
+#[debug_handler]
+pub async fn list_shared_with_me_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Vec<ShareDetail>>> {
+    let shares = load_shares(
+        db_pool,
+        "s.member_id = $1 AND s.accepted_at IS NOT NULL",
+        vec![(principal.id as i32).into()],
+    )
+    .await?;
+    Ok(ApiResponse::success(shares))
+}
@@ -0,0 +1,35 @@
+use crate::domain::todo_role::TodoRole;
+
+/// 按 id 操作共享时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct ShareIdParam {
+    #[validate(range(min = 1, message = "共享的 id 必须大于 0"))]
+    pub id: i32,
+}
+
+/// 共享的对象：一个待办事项（连同子任务）或一个项目，二者只能指定一个
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct ShareTargetQuery {
+    #[validate(range(min = 1, message = "待办事项的 id 必须大于 0"))]
+    pub todo_id: Option<i32>,
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub project_id: Option<i32>,
+}
+
+/// 邀请其他用户协作的参数，对方接受邀请后共享才生效
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateShareParam {
+    #[validate(range(min = 1, message = "待办事项的 id 必须大于 0"))]
+    pub todo_id: Option<i32>,
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub project_id: Option<i32>,
+    #[validate(length(min = 1, max = 50, message = "用户名长度必须在 1 到 50 之间"))]
+    pub username: String,
+    pub role: TodoRole,
+}
+
+/// 修改成员角色的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct PatchShareParam {
+    pub role: TodoRole,
+}
@@ -0,0 +1,174 @@
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::tree::{MAX_TODO_DEPTH, TodoTreeNode, load_user_subtree};
+use crate::response::ApiResult;
+use crate::response::errors::ApiError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, Statement,
+};
+
+/// 没有任何权限时的错误，不区分待办事项是否存在，避免泄露其他用户的数据
+fn no_access() -> ApiError {
+    ApiError::Biz(String::from("待办事项不存在或无权访问！"))
+}
+
+/// 当前用户在待办事项上的角色，没有权限时返回 None
+///
+/// 创建者始终是所有者；其他用户的角色取以下已接受的共享中最高的一个：
+/// 共享了该待办事项本身或它的任意一个祖先，或者共享了它所在的项目。
+pub async fn todo_role<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo: &todo_list::Model,
+) -> ApiResult<Option<TodoRole>> {
+    if todo.user_id == user_id {
+        return Ok(Some(TodoRole::Owner));
+    }
+    let rows = db
+        .query_all_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"WITH RECURSIVE ancestors AS (
+                    SELECT id, parent_id, 1 AS depth FROM todo_list WHERE id = $1
+                    UNION ALL
+                    SELECT t.id, t.parent_id, a.depth + 1 FROM todo_list t
+                    JOIN ancestors a ON t.id = a.parent_id
+                    WHERE a.depth <= $4
+                )
+                SELECT role FROM todo_shares
+                WHERE member_id = $2 AND accepted_at IS NOT NULL
+                  AND (todo_id IN (SELECT id FROM ancestors) OR project_id = $3)"#,
+            [
+                todo.id.into(),
+                user_id.into(),
+                todo.project_id.into(),
+                (MAX_TODO_DEPTH as i32).into(),
+            ],
+        ))
+        .await?;
+    let mut role = None;
+    for row in rows {
+        let value: String = row.try_get_by_index(0)?;
+        role = role.max(value.parse::<TodoRole>().ok());
+    }
+    Ok(role)
+}
+
+/// 校验当前用户在待办事项上的角色不低于 `required`
+pub fn ensure_role(role: Option<TodoRole>, required: TodoRole) -> ApiResult<TodoRole> {
+    match role {
+        None => Err(no_access()),
+        Some(role) if role < required => Err(ApiError::Biz(format!(
+            "你是该待办事项的{}，需要{}权限才能执行此操作！",
+            role.label(),
+            required.label()
+        ))),
+        Some(role) => Ok(role),
+    }
+}
+
+/// 查询当前用户有权访问的待办事项（不包括回收站中的），角色不低于 `required`
+///
+/// 所有按 id 操作待办事项的接口都通过这里鉴权。
+///
+/// # 参数
+/// - db: 数据库连接（连接池或事务）
+/// - user_id: 当前登陆用户的 id
+/// - id: 待办事项的 id
+/// - required: 操作需要的最低角色
+pub async fn authorize_todo<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+    required: TodoRole,
+) -> ApiResult<todo_list::Model> {
+    let todo = TodoList::find_by_id(id)
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(no_access)?;
+    ensure_role(todo_role(db, user_id, &todo).await?, required)?;
+    Ok(todo)
+}
+
+/// 查询属于 `owner_id` 的待办事项（不包括回收站中的），不做共享鉴权
+///
+/// 用于两类场景：只允许创建者本人使用的接口（回收站等），
+/// 以及鉴权之后校验相关的待办事项（父任务、相邻任务）与被操作的待办事项属于同一个创建者。
+pub async fn find_owned_todo<C: ConnectionTrait>(
+    db: &C,
+    owner_id: i32,
+    id: i32,
+) -> ApiResult<todo_list::Model> {
+    TodoList::find_by_id(id)
+        .filter(todo_list::Column::UserId.eq(owner_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(no_access)
+}
+
+/// 鉴权后查询以 `root_id` 为根的整棵子树，子树属于根节点的创建者
+pub async fn authorize_subtree<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    root_id: i32,
+    required: TodoRole,
+) -> ApiResult<TodoTreeNode> {
+    let root = authorize_todo(db, user_id, root_id, required).await?;
+    load_user_subtree(db, root.user_id, root_id).await
+}
+
+/// 列表查询的筛选条件：自己创建的，以及通过共享可以访问的待办事项
+pub fn accessible_todo_condition(user_id: i32) -> Condition {
+    Condition::any()
+        .add(todo_list::Column::UserId.eq(user_id))
+        .add(shared_todo_condition(user_id))
+}
+
+/// 批量修改的筛选条件：自己创建的，以及通过共享获得编辑者及以上角色的待办事项
+pub fn editable_todo_condition(user_id: i32) -> Condition {
+    Condition::any()
+        .add(todo_list::Column::UserId.eq(user_id))
+        .add(shared_condition(user_id, TodoRole::Editor))
+}
+
+/// 列表查询的筛选条件：其他用户共享给当前用户的待办事项（包括共享的子树和项目）
+pub fn shared_todo_condition(user_id: i32) -> Condition {
+    shared_condition(user_id, TodoRole::Viewer)
+}
+
+/// 其他用户共享给当前用户、角色不低于 `required` 的待办事项
+fn shared_condition(user_id: i32, required: TodoRole) -> Condition {
+    // 角色名是固定的常量，直接拼进 SQL
+    let roles = [TodoRole::Viewer, TodoRole::Editor, TodoRole::Owner]
+        .into_iter()
+        .filter(|role| *role >= required)
+        .map(|role| format!("'{role}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Condition::all()
+        .add(todo_list::Column::UserId.ne(user_id))
+        .add(Expr::cust_with_values(
+            format!(
+                r#"(todo_list.project_id IN (
+                    SELECT project_id FROM todo_shares
+                    WHERE member_id = $1 AND accepted_at IS NOT NULL AND project_id IS NOT NULL
+                      AND role IN ({roles})
+                ) OR todo_list.id IN (
+                    WITH RECURSIVE shared AS (
+                        SELECT todo_id AS id, 1 AS depth FROM todo_shares
+                        WHERE member_id = $1 AND accepted_at IS NOT NULL AND todo_id IS NOT NULL
+                          AND role IN ({roles})
+                        UNION ALL
+                        SELECT c.id, s.depth + 1 FROM todo_list c
+                        JOIN shared s ON c.parent_id = s.id
+                        WHERE s.depth <= $2
+                    )
+                    SELECT id FROM shared
+                ))"#
+            ),
+            [user_id, MAX_TODO_DEPTH as i32],
+        ))
+}
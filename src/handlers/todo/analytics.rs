@@ -0,0 +1,166 @@
+use crate::common::valid::ValidQuery;
+use crate::handlers::todo::model::{EstimateGroupBy, EstimateReportQuery};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{ConnectionTrait, DbBackend, FromQueryResult, Statement};
+
+/// 样本数量少于该值时不给出修正系数，避免个别极端值误导
+const MIN_SAMPLES_FOR_FACTOR: i64 = 5;
+
+/// 一组已完成任务的估时误差统计（时间单位均为分钟）
+#[derive(Debug, serde::Serialize, FromQueryResult)]
+pub struct EstimateStats {
+    /// 分组的键，汇总结果中为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_key: Option<String>,
+    pub samples: i64,
+    pub total_estimated: f64,
+    pub total_actual: f64,
+    /// 实际总用时 / 预估总用时，大于 1 表示整体低估
+    pub ratio: f64,
+    /// 绝对误差 |actual - estimated| 的中位数
+    pub median_abs_error: f64,
+    /// 平均误差 actual - estimated，正数表示习惯性低估
+    pub bias: f64,
+    /// 单个任务 actual / estimated 的中位数
+    pub median_ratio: f64,
+}
+
+/// 估时误差统计及建议的修正系数
+#[derive(Debug, serde::Serialize)]
+pub struct EstimateAccuracy {
+    #[serde(flatten)]
+    pub stats: EstimateStats,
+    /// 新任务的预估时间乘以该系数即为修正后的预估，样本不足时为空
+    pub suggested_factor: Option<f64>,
+}
+
+impl From<EstimateStats> for EstimateAccuracy {
+    fn from(stats: EstimateStats) -> Self {
+        let stats = EstimateStats {
+            total_estimated: round2(stats.total_estimated),
+            total_actual: round2(stats.total_actual),
+            ratio: round2(stats.ratio),
+            median_abs_error: round2(stats.median_abs_error),
+            bias: round2(stats.bias),
+            median_ratio: round2(stats.median_ratio),
+            ..stats
+        };
+        // 使用单任务比值的中位数作为修正系数，比总量比值更不容易被个别大任务左右
+        let suggested_factor =
+            (stats.samples >= MIN_SAMPLES_FOR_FACTOR).then_some(stats.median_ratio);
+        Self {
+            stats,
+            suggested_factor,
+        }
+    }
+}
+
+/// 估时分析报告
+#[derive(Debug, serde::Serialize)]
+pub struct EstimateReport {
+    pub group_by: EstimateGroupBy,
+    pub overall: Option<EstimateAccuracy>,
+    pub groups: Vec<EstimateAccuracy>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl EstimateGroupBy {
+    /// 分组键的 SQL 表达式以及需要额外关联的数据来源
+    fn key_sql(&self) -> (&'static str, &'static str) {
+        match self {
+            EstimateGroupBy::Tag => ("tag", "CROSS JOIN LATERAL unnest(t.tags) AS tag"),
+            EstimateGroupBy::Priority => ("t.priority", ""),
+            EstimateGroupBy::Week => (
+                r#"to_char(t.completed_at AT TIME ZONE 'Asia/Shanghai', 'IYYY-"W"IW')"#,
+                "",
+            ),
+        }
+    }
+}
+
+/// 统计当前用户已完成且同时填写了预估和实际用时的任务
+///
+/// 只统计叶子任务：父任务的 actual_time 包含了子任务汇总上来的用时，计入会重复统计。
+async fn query_stats<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    group_by: Option<EstimateGroupBy>,
+    from: Option<DateTimeWithTimeZone>,
+    to: Option<DateTimeWithTimeZone>,
+) -> ApiResult<Vec<EstimateStats>> {
+    let (key, join) = group_by.map_or(("NULL::TEXT", ""), |g| g.key_sql());
+    let sql = format!(
+        r#"WITH samples AS (
+                SELECT {key} AS group_key,
+                    t.estimated_time::FLOAT8 AS estimated,
+                    t.actual_time::FLOAT8 AS actual
+                FROM todo_list t {join}
+                WHERE t.user_id = $1
+                    AND t.deleted_at IS NULL
+                    AND t.status = 'completed'
+                    AND t.estimated_time > 0
+                    AND t.actual_time IS NOT NULL
+                    AND NOT EXISTS (
+                        SELECT 1 FROM todo_list c WHERE c.parent_id = t.id AND c.deleted_at IS NULL
+                    )
+                    AND ($2::TIMESTAMPTZ IS NULL OR t.completed_at >= $2)
+                    AND ($3::TIMESTAMPTZ IS NULL OR t.completed_at < $3)
+            )
+            SELECT group_key,
+                COUNT(*) AS samples,
+                SUM(estimated) AS total_estimated,
+                SUM(actual) AS total_actual,
+                SUM(actual) / SUM(estimated) AS ratio,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY abs(actual - estimated)) AS median_abs_error,
+                AVG(actual - estimated) AS bias,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY actual / estimated) AS median_ratio
+            FROM samples
+            GROUP BY group_key
+            ORDER BY group_key"#
+    );
+    Ok(
+        EstimateStats::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [user_id.into(), from.into(), to.into()],
+        ))
+        .all(db)
+        .await?,
+    )
+}
+
+/// 估时准确度分析：按标签、优先级或周统计预估误差，并给出修正系数
+#[debug_handler]
+#[tracing::instrument(name = "estimate report", skip_all, fields(user_id = %principal.id))]
+pub async fn estimate_report_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<EstimateReportQuery>,
+) -> ApiResult<ApiResponse<EstimateReport>> {
+    let user_id = principal.id as i32;
+    let group_by = params.group_by.unwrap_or_default();
+    let overall = query_stats(db_pool, user_id, None, params.from, params.to)
+        .await?
+        .into_iter()
+        .next()
+        .map(EstimateAccuracy::from);
+    let groups = query_stats(db_pool, user_id, Some(group_by), params.from, params.to)
+        .await?
+        .into_iter()
+        .map(EstimateAccuracy::from)
+        .collect();
+    Ok(ApiResponse::success(EstimateReport {
+        group_by,
+        overall,
+        groups,
+    }))
+}
@@ -0,0 +1,358 @@
+use crate::common::valid::ValidPath;
+use crate::conf::get_app_config;
+use crate::domain::byte_range::{RangeNotSatisfiable, parse_range};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoAttachments;
+use crate::entities::todo_attachments;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::model::{AttachmentIdParam, TodoIdParam};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::storage::blob::{BlobStore, TempBlob};
+use axum::Extension;
+use axum::body::Body;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::extract::multipart::{Multipart, MultipartError, MultipartRejection};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use bytesize::ByteSize;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, Set, Statement, TransactionTrait,
+};
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+/// 当前用户的附件存储空间使用情况（字节）
+#[derive(Debug, serde::Serialize)]
+pub struct AttachmentUsage {
+    pub used: u64,
+    pub quota: u64,
+    pub max_file_size: u64,
+}
+
+/// 已经接收完成、等待保存的文件
+struct PendingUpload {
+    file_name: String,
+    content_type: String,
+    blob: TempBlob,
+}
+
+fn io_error(e: std::io::Error) -> ApiError {
+    ApiError::Internal(e.into())
+}
+
+fn multipart_error(e: MultipartError) -> ApiError {
+    ApiError::ValidationError(format!("读取上传文件失败：{}", e.body_text()))
+}
+
+/// 去掉文件名中的路径和控制字符，最长保留 255 个字符
+fn sanitize_file_name(file_name: &str) -> String {
+    let name: String = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(255)
+        .collect();
+    match name.trim() {
+        "" | "." | ".." => String::from("unnamed"),
+        name => name.to_string(),
+    }
+}
+
+/// 下载时的 Content-Disposition，非 ASCII 文件名按 RFC 5987 编码
+fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// 用户已经使用的存储空间，同一个文件被多个附件引用时重复计算
+async fn used_storage<C: ConnectionTrait>(db: &C, user_id: i32) -> ApiResult<u64> {
+    let row = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT COALESCE(SUM(size), 0)::BIGINT FROM todo_attachments WHERE user_id = $1",
+            [user_id.into()],
+        ))
+        .await?;
+    let used: i64 = match row {
+        Some(row) => row.try_get_by_index(0)?,
+        None => 0,
+    };
+    Ok(used.max(0) as u64)
+}
+
+/// 查询附件，并校验当前用户在附件所属的待办事项上的角色不低于 `required`
+async fn find_attachment<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+    required: TodoRole,
+) -> ApiResult<todo_attachments::Model> {
+    let attachment = TodoAttachments::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("附件不存在或无权访问！")))?;
+    authorize_todo(db, user_id, attachment.todo_id, required).await?;
+    Ok(attachment)
+}
+
+/// 接收一个文件字段，边接收边写入临时文件，超过大小限制时立即停止
+async fn receive_file(
+    store: &BlobStore,
+    field: &mut axum::extract::multipart::Field<'_>,
+    file_name: &str,
+    max_file_size: u64,
+) -> ApiResult<TempBlob> {
+    let mut blob = store.create_temp().await.map_err(io_error)?;
+    while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+        if blob.size() + chunk.len() as u64 > max_file_size {
+            return Err(ApiError::Biz(format!(
+                "文件「{file_name}」超过了 {} 的大小限制！",
+                ByteSize::b(max_file_size)
+            )));
+        }
+        blob.write(&chunk).await.map_err(io_error)?;
+    }
+    blob.finish().await.map_err(io_error)?;
+    Ok(blob)
+}
+
+/// 查询待办事项的附件，能看到该待办事项的用户都可以查询
+#[debug_handler]
+pub async fn list_attachments_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<Vec<todo_attachments::Model>>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    let attachments = todo
+        .find_related(TodoAttachments)
+        .order_by_asc(todo_attachments::Column::Id)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(attachments))
+}
+
+/// 上传附件（multipart/form-data），一次可以上传多个文件，编辑者及以上角色可以上传
+///
+/// 每个文件都要通过 MIME 类型和大小校验，全部文件的大小之和不能超过上传人剩余的存储空间，
+/// 任意一个文件校验失败时整个请求都不会保存。
+#[debug_handler]
+#[tracing::instrument(name = "upload attachments", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn upload_attachments_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> ApiResult<ApiResponse<Vec<todo_attachments::Model>>> {
+    let user_id = principal.id as i32;
+    let config = get_app_config().attachment();
+    // 先鉴权再接收文件，无权访问时不必读取请求体
+    let todo = authorize_todo(db_pool, user_id, path.id, TodoRole::Editor).await?;
+    let mut multipart = multipart?;
+    let store = BlobStore::new(config.dir());
+    let mut uploads = Vec::new();
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        // 没有文件名的普通表单字段直接忽略
+        let Some(file_name) = field.file_name().map(sanitize_file_name) else {
+            continue;
+        };
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        if !config.allows(&content_type) {
+            return Err(ApiError::Biz(format!(
+                "不允许上传 {content_type} 类型的文件「{file_name}」！"
+            )));
+        }
+        let blob = receive_file(&store, &mut field, &file_name, config.max_file_size()).await?;
+        uploads.push(PendingUpload {
+            file_name,
+            content_type,
+            blob,
+        });
+    }
+    if uploads.is_empty() {
+        return Err(ApiError::Biz(String::from("请选择要上传的文件！")));
+    }
+    let txn = db_pool.begin().await?;
+    // 锁住上传人，同一个用户的并发上传依次校验配额
+    txn.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT id FROM users WHERE id = $1 FOR UPDATE",
+        [user_id.into()],
+    ))
+    .await?;
+    let used = used_storage(&txn, user_id).await?;
+    let total: u64 = uploads.iter().map(|upload| upload.blob.size()).sum();
+    if used + total > config.quota() {
+        return Err(ApiError::Biz(format!(
+            "存储空间不足：已使用 {}，本次上传 {}，总共可用 {}！",
+            ByteSize::b(used),
+            ByteSize::b(total),
+            ByteSize::b(config.quota())
+        )));
+    }
+    let mut attachments = Vec::with_capacity(uploads.len());
+    for upload in uploads {
+        store.persist(&upload.blob).await.map_err(io_error)?;
+        let attachment = todo_attachments::ActiveModel {
+            todo_id: Set(todo.id),
+            user_id: Set(user_id),
+            file_name: Set(upload.file_name),
+            content_type: Set(upload.content_type),
+            size: Set(upload.blob.size() as i64),
+            sha256: Set(upload.blob.sha256().unwrap_or_default().to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        attachments.push(attachment);
+    }
+    txn.commit().await?;
+    tracing::info!(
+        "ID为: {} 的用户为待办事项 {} 上传了 {} 个附件，共 {} 字节",
+        principal.id,
+        todo.id,
+        attachments.len(),
+        total
+    );
+    Ok(ApiResponse::ok("上传成功！", Some(attachments)))
+}
+
+/// 下载附件，支持 Range 请求断点续传，能看到该待办事项的用户都可以下载
+#[debug_handler]
+#[tracing::instrument(name = "download attachment", skip_all, fields(user_id = %principal.id, attachment_id = %path.attachment_id))]
+pub async fn download_attachment_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<AttachmentIdParam>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let attachment = find_attachment(
+        db_pool,
+        principal.id as i32,
+        path.attachment_id,
+        TodoRole::Viewer,
+    )
+    .await?;
+    let store = BlobStore::new(get_app_config().attachment().dir());
+    let mut file = tokio::fs::File::open(store.path_of(&attachment.sha256))
+        .await
+        .map_err(|e| {
+            ApiError::Internal(anyhow::anyhow!(
+                "附件 {} 的文件无法读取：{e}",
+                attachment.id
+            ))
+        })?;
+    let size = file.metadata().await.map_err(io_error)?.len();
+    let range = match headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => match parse_range(value, size) {
+            Ok(range) => range,
+            Err(RangeNotSatisfiable) => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{size}"))],
+                )
+                    .into_response());
+            }
+        },
+        None => None,
+    };
+    let (status, length) = match range {
+        Some(range) => {
+            file.seek(SeekFrom::Start(range.start))
+                .await
+                .map_err(io_error)?;
+            (StatusCode::PARTIAL_CONTENT, range.length())
+        }
+        None => (StatusCode::OK, size),
+    };
+    let mut builder = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, &attachment.content_type)
+        .header(header::CONTENT_LENGTH, length)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, format!("\"{}\"", attachment.sha256))
+        .header(
+            header::CONTENT_DISPOSITION,
+            content_disposition(&attachment.file_name),
+        )
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    if let Some(range) = range {
+        builder = builder.header(header::CONTENT_RANGE, range.content_range(size));
+    }
+    builder
+        .body(Body::from_stream(ReaderStream::new(file.take(length))))
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+/// 删除附件，编辑者及以上角色可以删除；文件没有其他附件引用时由后台任务回收
+#[debug_handler]
+#[tracing::instrument(name = "delete attachment", skip_all, fields(user_id = %principal.id, attachment_id = %path.attachment_id))]
+pub async fn delete_attachment_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<AttachmentIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let attachment = find_attachment(
+        db_pool,
+        principal.id as i32,
+        path.attachment_id,
+        TodoRole::Editor,
+    )
+    .await?;
+    TodoAttachments::delete_many()
+        .filter(todo_attachments::Column::Id.eq(attachment.id))
+        .exec(db_pool)
+        .await?;
+    tracing::info!(
+        "ID为: {} 的用户删除了附件 {}",
+        principal.id,
+        path.attachment_id
+    );
+    Ok(ApiResponse::success_with_msg("删除成功！"))
+}
+
+/// 查询当前用户的附件存储空间使用情况
+#[debug_handler]
+pub async fn attachment_usage_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<AttachmentUsage>> {
+    let config = get_app_config().attachment();
+    Ok(ApiResponse::success(AttachmentUsage {
+        used: used_storage(db_pool, principal.id as i32).await?,
+        quota: config.quota(),
+        max_file_size: config.max_file_size(),
+    }))
+}
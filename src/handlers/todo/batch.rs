@@ -0,0 +1,368 @@
+use crate::common::valid::ValidJson;
+use crate::conf::get_app_config;
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::{TodoStatus, apply_status_transition};
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::{accessible_todo_condition, authorize_todo, find_owned_todo};
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{BatchOperation, BatchTodoParam};
+use crate::handlers::todo::project::follow_parent_project;
+use crate::handlers::todo::recurrence::spawn_next_occurrence;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::time_tracking::rollup_actual_time;
+use crate::handlers::todo::trash::soft_delete_todo;
+use crate::handlers::todo::tree::{ensure_child_depth, load_user_subtree, lock_user_tree};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::{needs_reindex, refresh_todo_index};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QuerySelect, Set, TransactionTrait,
+};
+use std::collections::{BTreeSet, HashSet};
+
+/// 每个待办事项最多的标签数量，与创建接口的校验保持一致
+const MAX_TAGS: usize = 20;
+/// 标签的最大长度，与重命名标签的校验保持一致
+const MAX_TAG_CHARS: usize = 50;
+
+/// 单个待办事项的执行结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Updated,
+    Deleted,
+    Failed,
+}
+
+/// 单个待办事项的执行明细
+#[derive(Debug, serde::Serialize)]
+pub struct BatchItemResult {
+    pub id: i32,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<todo_list::Model>,
+}
+
+/// 批量操作的结果
+#[derive(Debug, serde::Serialize)]
+pub struct BatchResult {
+    pub committed: bool,
+    pub updated: usize,
+    pub deleted: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemResult>,
+}
+
+/// 对一个待办事项执行全部操作后的结果
+enum BatchOutcome {
+    Updated(Box<todo_list::Model>),
+    /// 移入回收站的待办事项及其子任务的 id
+    Deleted(Vec<i32>),
+}
+
+/// 校验操作列表：删除只能是最后一项，标签不能为空且长度合法
+fn check_operations(operations: &[BatchOperation]) -> ApiResult<()> {
+    if let Some(index) = operations
+        .iter()
+        .position(|op| matches!(op, BatchOperation::Delete))
+        && index + 1 != operations.len()
+    {
+        return Err(ApiError::Biz(String::from("删除只能是最后一项操作！")));
+    }
+    for op in operations {
+        if let BatchOperation::AddTags { tags } | BatchOperation::RemoveTags { tags } = op {
+            if tags.is_empty() {
+                return Err(ApiError::Biz(String::from("标签不能为空！")));
+            }
+            if tags
+                .iter()
+                .any(|tag| tag.trim().is_empty() || tag.chars().count() > MAX_TAG_CHARS)
+            {
+                return Err(ApiError::Biz(format!(
+                    "标签长度必须在 1 到 {MAX_TAG_CHARS} 之间"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 执行全部操作需要的最低角色，与单个接口保持一致：
+/// 删除和提升为顶层任务需要所有者，其余操作需要编辑者
+fn required_role(operations: &[BatchOperation]) -> TodoRole {
+    let owner_only = operations.iter().any(|op| {
+        matches!(
+            op,
+            BatchOperation::Delete | BatchOperation::Move { parent_id: None }
+        )
+    });
+    if owner_only {
+        TodoRole::Owner
+    } else {
+        TodoRole::Editor
+    }
+}
+
+/// 对涉及的全部创建者的任务树加锁，按用户 id 顺序加锁，避免并发的批量操作互相等待形成死锁
+async fn lock_owner_trees<C: ConnectionTrait>(db: &C, user_id: i32, ids: &[i32]) -> ApiResult<()> {
+    let owners: BTreeSet<i32> = TodoList::find()
+        .select_only()
+        .column(todo_list::Column::UserId)
+        .filter(todo_list::Column::Id.is_in(ids.iter().copied()))
+        .filter(accessible_todo_condition(user_id))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    for owner_id in owners {
+        lock_user_tree(db, owner_id).await?;
+    }
+    Ok(())
+}
+
+/// 去掉重复的 id，保留第一次出现的顺序
+fn dedup_ids(ids: Vec<i32>) -> Vec<i32> {
+    let mut seen = HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
+
+/// 校验移动目标并返回新的排序值，父任务没有变化时返回空
+///
+/// 新父任务同样需要编辑者及以上角色，并且与被移动的任务属于同一个创建者。
+async fn plan_move<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo: &todo_list::Model,
+    current_parent_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> ApiResult<Option<i32>> {
+    if current_parent_id == parent_id {
+        return Ok(None);
+    }
+    let (id, owner_id) = (todo.id, todo.user_id);
+    if let Some(parent_id) = parent_id {
+        if parent_id == id {
+            return Err(ApiError::Biz(String::from("不能把任务移动到自己下面！")));
+        }
+        let parent = authorize_todo(db, user_id, parent_id, TodoRole::Editor).await?;
+        if parent.user_id != owner_id {
+            return Err(ApiError::Biz(String::from(
+                "不能把任务移动到其他用户创建的任务下面！",
+            )));
+        }
+        let tree = load_user_subtree(db, owner_id, id).await?;
+        let ancestors = ensure_child_depth(db, parent_id, tree.height()).await?;
+        if ancestors.contains(&id) {
+            return Err(ApiError::Biz(String::from(
+                "不能把任务移动到它自己的子任务下面！",
+            )));
+        }
+    }
+    Ok(Some(append_rank(db, owner_id, parent_id).await?))
+}
+
+/// 对一个待办事项依次执行全部操作，共享的待办事项按当前用户的角色鉴权
+async fn apply_operations<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+    operations: &[BatchOperation],
+) -> ApiResult<BatchOutcome> {
+    authorize_todo(db, user_id, id, required_role(operations)).await?;
+    let model = TodoList::find_by_id(id)
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待办事项不存在或无权访问！")))?;
+    let mut todo = model.clone().into_active_model();
+    let mut status = TodoStatus::from_db(model.status.as_deref());
+    let mut parent_id = model.parent_id;
+    let mut tags = model.tags.clone().unwrap_or_default();
+    let mut completed = false;
+    for op in operations {
+        match op {
+            BatchOperation::SetStatus { status: to, reopen } => {
+                if *to != status {
+                    apply_status_transition(&mut todo, status, *to, *reopen)?;
+                    completed = *to == TodoStatus::Completed;
+                    status = *to;
+                }
+            }
+            BatchOperation::SetPriority { priority } => {
+                todo.priority = Set(Some(priority.as_str().into()));
+            }
+            BatchOperation::AddTags { tags: added } => {
+                for tag in added {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+                if tags.len() > MAX_TAGS {
+                    return Err(ApiError::Biz(format!("标签数量不能超过 {MAX_TAGS} 个")));
+                }
+                todo.tags = Set((!tags.is_empty()).then(|| tags.clone()));
+            }
+            BatchOperation::RemoveTags { tags: removed } => {
+                tags.retain(|tag| !removed.contains(tag));
+                todo.tags = Set((!tags.is_empty()).then(|| tags.clone()));
+            }
+            BatchOperation::Move { parent_id: to } => {
+                if let Some(rank) = plan_move(db, user_id, &model, parent_id, *to).await? {
+                    todo.parent_id = Set(*to);
+                    todo.sort_order = Set(Some(rank));
+                    parent_id = *to;
+                }
+            }
+            BatchOperation::Delete => {
+                // 前面的操作（例如移动）需要先保存，再把整棵子树移入回收站
+                let updated = if todo.is_changed() {
+                    todo.update(db).await?
+                } else {
+                    model.clone()
+                };
+                let ids = soft_delete_todo(db, &updated).await?;
+                if let Some(parent_id) = model.parent_id.filter(|_| parent_id != model.parent_id) {
+                    rollup_actual_time(db, parent_id).await?;
+                }
+                return Ok(BatchOutcome::Deleted(ids));
+            }
+        }
+    }
+    if !todo.is_changed() {
+        return Ok(BatchOutcome::Updated(Box::new(model)));
+    }
+    let mut updated = todo.update(db).await?;
+    // 移动到新的父任务下之后跟随父任务所在的项目
+    if let Some(parent_id) = updated
+        .parent_id
+        .filter(|_| model.parent_id != updated.parent_id)
+        && follow_parent_project(db, id, parent_id).await? > 0
+    {
+        updated = find_owned_todo(db, updated.user_id, id).await?;
+    }
+    if needs_reindex(&model, &updated) {
+        refresh_todo_index(db, &updated).await?;
+    }
+    // 原父任务和新父任务链上的用时都需要重新汇总
+    if model.parent_id != updated.parent_id {
+        for parent_id in [model.parent_id, updated.parent_id].into_iter().flatten() {
+            rollup_actual_time(db, parent_id).await?;
+        }
+    }
+    if completed {
+        spawn_next_occurrence(db, &updated).await?;
+    }
+    Ok(BatchOutcome::Updated(Box::new(updated)))
+}
+
+/// 批量操作待办事项
+///
+/// 与单个接口使用同一套共享鉴权：删除和提升为顶层任务需要所有者，其余操作需要编辑者。
+/// 所有待办事项在同一个事务中依次处理，每个待办事项使用一个保存点：
+/// 某一项失败（不存在、无权访问、非法的状态流转等）只回滚这一项，其余的照常提交；
+/// atomic 为 true 时只要有一项失败就回滚整个批次。
+#[debug_handler]
+#[tracing::instrument(name = "batch todo", skip_all, fields(user_id = %principal.id, size = params.ids.len()))]
+pub async fn batch_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidJson(params): ValidJson<BatchTodoParam>,
+) -> ApiResult<ApiResponse<BatchResult>> {
+    let user_id = principal.id as i32;
+    let max_size = get_app_config().batch().max_size();
+    let ids = dedup_ids(params.ids);
+    if ids.len() > max_size {
+        return Err(ApiError::Biz(format!(
+            "单次最多批量操作 {max_size} 个待办事项"
+        )));
+    }
+    check_operations(&params.operations)?;
+    let txn = history.begin(db_pool).await?;
+    lock_owner_trees(&txn, user_id, &ids).await?;
+    let mut deleted: HashSet<i32> = HashSet::new();
+    let mut items = Vec::with_capacity(ids.len());
+    for id in ids {
+        // 父任务在前面已经被删除时，子任务也随之删除了
+        if deleted.contains(&id) {
+            items.push(BatchItemResult {
+                id,
+                status: BatchItemStatus::Deleted,
+                message: Some(String::from("已随父任务一起删除")),
+                todo: None,
+            });
+            continue;
+        }
+        let savepoint = txn.begin().await?;
+        match apply_operations(&savepoint, user_id, id, &params.operations).await {
+            Ok(outcome) => {
+                savepoint.commit().await?;
+                items.push(match outcome {
+                    BatchOutcome::Updated(todo) => BatchItemResult {
+                        id,
+                        status: BatchItemStatus::Updated,
+                        message: None,
+                        todo: Some(*todo),
+                    },
+                    BatchOutcome::Deleted(ids) => {
+                        deleted.extend(ids);
+                        BatchItemResult {
+                            id,
+                            status: BatchItemStatus::Deleted,
+                            message: None,
+                            todo: None,
+                        }
+                    }
+                });
+            }
+            Err(e @ (ApiError::Biz(_) | ApiError::IllegalStatusTransition { .. })) => {
+                savepoint.rollback().await?;
+                items.push(BatchItemResult {
+                    id,
+                    status: BatchItemStatus::Failed,
+                    message: Some(e.to_string()),
+                    todo: None,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let count = |status| items.iter().filter(|i| i.status == status).count();
+    let failed = count(BatchItemStatus::Failed);
+    let committed = !(params.atomic && failed > 0);
+    let result = BatchResult {
+        committed,
+        updated: count(BatchItemStatus::Updated),
+        deleted: count(BatchItemStatus::Deleted),
+        failed,
+        items,
+    };
+    if !committed {
+        txn.rollback().await?;
+        return Ok(ApiResponse::new(
+            -1,
+            format!("有 {failed} 个待办事项操作失败，已全部回滚！"),
+            Some(result),
+        ));
+    }
+    txn.commit().await?;
+    tracing::info!(
+        "用户 {} 批量操作：更新 {}，删除 {}，失败 {}",
+        user_id,
+        result.updated,
+        result.deleted,
+        result.failed
+    );
+    Ok(ApiResponse::ok("批量操作完成！", Some(result)))
+}
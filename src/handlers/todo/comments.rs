@@ -0,0 +1,423 @@
+use crate::common::valid::{ValidJson, ValidPath, ValidQuery};
+use crate::domain::mention::parse_mentions;
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::{TodoCommentEdits, TodoCommentMentions, TodoComments, Users};
+use crate::entities::{todo_comment_edits, todo_comment_mentions, todo_comments, todo_list, users};
+use crate::handlers::todo::access::{authorize_todo, ensure_role, todo_role};
+use crate::handlers::todo::model::{
+    CommentIdParam, CreateCommentParam, MentionQuery, TodoIdParam, UpdateCommentParam,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult,
+    IntoActiveModel, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, Statement,
+    TransactionTrait, Value,
+};
+use std::collections::HashMap;
+
+/// 评论中 @ 到的用户
+#[derive(Debug, Clone, serde::Serialize, FromQueryResult)]
+pub struct MentionedUser {
+    pub user_id: i32,
+    pub username: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct MentionRow {
+    comment_id: i32,
+    user_id: i32,
+    username: String,
+}
+
+/// 评论内容，附带评论人的用户名
+#[derive(Debug, serde::Serialize, FromQueryResult)]
+pub struct CommentRow {
+    pub id: i32,
+    pub todo_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub parent_id: Option<i32>,
+    /// Markdown 正文，已删除的评论为空
+    pub body: String,
+    pub edited_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub created_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 评论详情
+#[derive(Debug, serde::Serialize)]
+pub struct CommentDetail {
+    #[serde(flatten)]
+    pub comment: CommentRow,
+    pub mentions: Vec<MentionedUser>,
+}
+
+/// 讨论串：顶层评论及其全部回复，回复按时间先后排列
+#[derive(Debug, serde::Serialize)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: CommentDetail,
+    pub replies: Vec<CommentDetail>,
+}
+
+/// 查询评论详情，`tail` 是 FROM 之后的 WHERE、ORDER BY 等子句，参数从 $1 开始编号
+async fn load_comments<C: ConnectionTrait>(
+    db: &C,
+    tail: &str,
+    values: Vec<Value>,
+) -> ApiResult<Vec<CommentDetail>> {
+    let rows = CommentRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        format!(
+            r#"SELECT c.id, c.todo_id, c.user_id, u.username, c.parent_id, c.body,
+                    c.edited_at, c.deleted_at, c.created_at
+                FROM todo_comments c
+                JOIN users u ON u.id = c.user_id
+                {tail}"#
+        ),
+        values,
+    ))
+    .all(db)
+    .await?;
+    let ids: Vec<i32> = rows.iter().map(|row| row.id).collect();
+    let mut mentions: HashMap<i32, Vec<MentionedUser>> = HashMap::new();
+    if !ids.is_empty() {
+        let mention_rows = MentionRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT m.comment_id, u.id AS user_id, u.username
+                FROM todo_comment_mentions m
+                JOIN users u ON u.id = m.user_id
+                WHERE m.comment_id = ANY($1)
+                ORDER BY m.comment_id, u.username"#,
+            [ids.into()],
+        ))
+        .all(db)
+        .await?;
+        for row in mention_rows {
+            mentions
+                .entry(row.comment_id)
+                .or_default()
+                .push(MentionedUser {
+                    user_id: row.user_id,
+                    username: row.username,
+                });
+        }
+    }
+    Ok(rows
+        .into_iter()
+        .map(|comment| CommentDetail {
+            mentions: mentions.remove(&comment.id).unwrap_or_default(),
+            comment,
+        })
+        .collect())
+}
+
+/// 查询一条评论的详情
+async fn load_comment<C: ConnectionTrait>(db: &C, id: i32) -> ApiResult<CommentDetail> {
+    load_comments(db, "WHERE c.id = $1", vec![id.into()])
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::Biz(String::from("评论不存在！")))
+}
+
+/// 查询未删除的评论，并校验当前用户在评论所属的待办事项上的角色不低于 `required`
+async fn find_comment<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    comment_id: i32,
+    required: TodoRole,
+) -> ApiResult<(todo_comments::Model, todo_list::Model)> {
+    let comment = TodoComments::find_by_id(comment_id)
+        .filter(todo_comments::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("评论不存在或已删除！")))?;
+    let todo = authorize_todo(db, user_id, comment.todo_id, required).await?;
+    Ok((comment, todo))
+}
+
+/// 解析正文中的 @ 并替换评论的提及记录，返回提及的用户数量
+///
+/// 只记录存在且能看到该待办事项的用户，@ 自己不记录。
+async fn save_mentions<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+    comment: &todo_comments::Model,
+) -> ApiResult<usize> {
+    TodoCommentMentions::delete_many()
+        .filter(todo_comment_mentions::Column::CommentId.eq(comment.id))
+        .exec(db)
+        .await?;
+    let names = parse_mentions(&comment.body);
+    if names.is_empty() {
+        return Ok(0);
+    }
+    let candidates = Users::find()
+        .filter(users::Column::Username.is_in(names))
+        .filter(users::Column::Id.ne(comment.user_id))
+        .all(db)
+        .await?;
+    let mut mentions = Vec::with_capacity(candidates.len());
+    for user in candidates {
+        if todo_role(db, user.id, todo).await?.is_some() {
+            mentions.push(todo_comment_mentions::ActiveModel {
+                comment_id: Set(comment.id),
+                user_id: Set(user.id),
+                ..Default::default()
+            });
+        }
+    }
+    let count = mentions.len();
+    if count > 0 {
+        TodoCommentMentions::insert_many(mentions).exec(db).await?;
+    }
+    Ok(count)
+}
+
+/// 查询待办事项的评论，按讨论串返回，能看到该待办事项的用户都可以查询
+#[debug_handler]
+pub async fn list_comments_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<Vec<CommentThread>>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    let comments = load_comments(
+        db_pool,
+        "WHERE c.todo_id = $1 ORDER BY c.id",
+        vec![todo.id.into()],
+    )
+    .await?;
+    let (roots, replies): (Vec<_>, Vec<_>) = comments
+        .into_iter()
+        .partition(|detail| detail.comment.parent_id.is_none());
+    let mut replies_by_parent: HashMap<i32, Vec<CommentDetail>> = HashMap::new();
+    for reply in replies {
+        if let Some(parent_id) = reply.comment.parent_id {
+            replies_by_parent.entry(parent_id).or_default().push(reply);
+        }
+    }
+    let threads = roots
+        .into_iter()
+        .map(|comment| CommentThread {
+            replies: replies_by_parent
+                .remove(&comment.comment.id)
+                .unwrap_or_default(),
+            comment,
+        })
+        .collect();
+    Ok(ApiResponse::success(threads))
+}
+
+/// 发表评论或回复，能看到该待办事项的用户都可以评论
+///
+/// 讨论串只有一层：回复某条回复时，挂在它所在的顶层评论下。
+#[debug_handler]
+#[tracing::instrument(name = "create comment", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn create_comment_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<CreateCommentParam>,
+) -> ApiResult<ApiResponse<CommentDetail>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let todo = authorize_todo(&txn, user_id, path.id, TodoRole::Viewer).await?;
+    let parent_id = match params.parent_id {
+        Some(parent_id) => {
+            let parent = TodoComments::find_by_id(parent_id)
+                .filter(todo_comments::Column::TodoId.eq(todo.id))
+                .filter(todo_comments::Column::DeletedAt.is_null())
+                .one(&txn)
+                .await?
+                .ok_or_else(|| ApiError::Biz(String::from("回复的评论不存在或已删除！")))?;
+            Some(parent.parent_id.unwrap_or(parent.id))
+        }
+        None => None,
+    };
+    let comment = todo_comments::ActiveModel {
+        todo_id: Set(todo.id),
+        user_id: Set(user_id),
+        parent_id: Set(parent_id),
+        body: Set(params.body),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    save_mentions(&txn, &todo, &comment).await?;
+    let detail = load_comment(&txn, comment.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("评论成功！", Some(detail)))
+}
+
+/// 编辑自己的评论，编辑前的正文保存到编辑历史中，@ 的用户重新解析
+#[debug_handler]
+#[tracing::instrument(name = "update comment", skip_all, fields(user_id = %principal.id, comment_id = %path.comment_id))]
+pub async fn update_comment_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<CommentIdParam>,
+    ValidJson(params): ValidJson<UpdateCommentParam>,
+) -> ApiResult<ApiResponse<CommentDetail>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let (comment, todo) = find_comment(&txn, user_id, path.comment_id, TodoRole::Viewer).await?;
+    if comment.user_id != user_id {
+        return Err(ApiError::Biz(String::from("只能编辑自己的评论！")));
+    }
+    if comment.body == params.body {
+        return Ok(ApiResponse::ok(
+            "内容没有变化！",
+            Some(load_comment(&txn, comment.id).await?),
+        ));
+    }
+    todo_comment_edits::ActiveModel {
+        comment_id: Set(comment.id),
+        body: Set(comment.body.clone()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let mut comment = comment.into_active_model();
+    comment.body = Set(params.body);
+    comment.edited_at = Set(Some(get_local_datetime_with_timezone()));
+    let comment = comment.update(&txn).await?;
+    save_mentions(&txn, &todo, &comment).await?;
+    let detail = load_comment(&txn, comment.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("编辑成功！", Some(detail)))
+}
+
+/// 删除评论，评论人和待办事项的所有者可以删除
+///
+/// 有回复的顶层评论只清空正文保留占位，最后一条回复删除后占位随之删除。
+#[debug_handler]
+#[tracing::instrument(name = "delete comment", skip_all, fields(user_id = %principal.id, comment_id = %path.comment_id))]
+pub async fn delete_comment_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<CommentIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let (comment, todo) = find_comment(&txn, user_id, path.comment_id, TodoRole::Viewer).await?;
+    if comment.user_id != user_id {
+        ensure_role(todo_role(&txn, user_id, &todo).await?, TodoRole::Owner)?;
+    }
+    let replies = TodoComments::find()
+        .filter(todo_comments::Column::ParentId.eq(comment.id))
+        .count(&txn)
+        .await?;
+    if replies > 0 {
+        // 编辑历史和提及记录随正文一起清除
+        TodoCommentEdits::delete_many()
+            .filter(todo_comment_edits::Column::CommentId.eq(comment.id))
+            .exec(&txn)
+            .await?;
+        TodoCommentMentions::delete_many()
+            .filter(todo_comment_mentions::Column::CommentId.eq(comment.id))
+            .exec(&txn)
+            .await?;
+        let mut comment = comment.into_active_model();
+        comment.body = Set(String::new());
+        comment.deleted_at = Set(Some(get_local_datetime_with_timezone()));
+        comment.update(&txn).await?;
+    } else {
+        let parent_id = comment.parent_id;
+        comment.delete(&txn).await?;
+        if let Some(parent_id) = parent_id {
+            txn.execute_raw(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM todo_comments p
+                    WHERE p.id = $1 AND p.deleted_at IS NOT NULL
+                      AND NOT EXISTS (SELECT 1 FROM todo_comments r WHERE r.parent_id = p.id)"#,
+                [parent_id.into()],
+            ))
+            .await?;
+        }
+    }
+    txn.commit().await?;
+    tracing::info!(
+        "ID为: {} 的用户删除了评论 {}",
+        principal.id,
+        path.comment_id
+    );
+    Ok(ApiResponse::success_with_msg("删除成功！"))
+}
+
+/// 查询评论的编辑历史，按编辑时间倒序，每条记录是编辑前的正文
+#[debug_handler]
+pub async fn list_comment_edits_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<CommentIdParam>,
+) -> ApiResult<ApiResponse<Vec<todo_comment_edits::Model>>> {
+    let (comment, _) = find_comment(
+        db_pool,
+        principal.id as i32,
+        path.comment_id,
+        TodoRole::Viewer,
+    )
+    .await?;
+    let edits = comment
+        .find_related(TodoCommentEdits)
+        .order_by_desc(todo_comment_edits::Column::Id)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(edits))
+}
+
+/// 查询 @ 到当前用户的评论，按时间倒序，已经无权访问的待办事项中的评论不返回
+#[debug_handler]
+pub async fn list_mentions_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<MentionQuery>,
+) -> ApiResult<ApiResponse<Vec<CommentDetail>>> {
+    let user_id = principal.id as i32;
+    let comments = load_comments(
+        db_pool,
+        r#"JOIN todo_comment_mentions m ON m.comment_id = c.id AND m.user_id = $1
+            JOIN todo_list t ON t.id = c.todo_id AND t.deleted_at IS NULL
+            WHERE c.deleted_at IS NULL
+            ORDER BY c.id DESC
+            LIMIT $2 OFFSET $3"#,
+        vec![
+            user_id.into(),
+            (params.limit.unwrap_or(20) as i64).into(),
+            (params.offset.unwrap_or(0) as i64).into(),
+        ],
+    )
+    .await?;
+    // 提及之后共享可能已被移除，逐个待办事项重新鉴权
+    let mut visible: HashMap<i32, bool> = HashMap::new();
+    let mut result = Vec::with_capacity(comments.len());
+    for detail in comments {
+        let todo_id = detail.comment.todo_id;
+        let allowed = match visible.get(&todo_id) {
+            Some(allowed) => *allowed,
+            None => {
+                let allowed =
+                    match authorize_todo(db_pool, user_id, todo_id, TodoRole::Viewer).await {
+                        Ok(_) => true,
+                        Err(ApiError::Biz(_)) => false,
+                        Err(e) => return Err(e),
+                    };
+                visible.insert(todo_id, allowed);
+                allowed
+            }
+        };
+        if allowed {
+            result.push(detail);
+        }
+    }
+    Ok(ApiResponse::success(result))
+}
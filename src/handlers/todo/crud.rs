@@ -0,0 +1,224 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::{TodoStatus, apply_status_transition, completed_at_for};
+use crate::entities::todo_list;
+use crate::handlers::project::crud::find_writable_project;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{
+    CreateTodoParam, PatchTodoParam, TodoIdParam, TodoPriority, UpdateTodoParam,
+};
+use crate::handlers::todo::recurrence::spawn_next_occurrence;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::trash::soft_delete_todo;
+use crate::handlers::todo::tree::ensure_child_depth;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::{needs_reindex, refresh_todo_index};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
+
+/// 创建待办事项，在共享的待办事项下创建子任务时，子任务属于父任务的创建者
+#[debug_handler]
+#[tracing::instrument(name = "create todo", skip_all, fields(user_id = %principal.id))]
+pub async fn create_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidJson(params): ValidJson<CreateTodoParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let user_id = principal.id as i32;
+    let txn = history.begin(db_pool).await?;
+    // 父任务必须属于当前用户，且不能超过最大层级
+    let mut project_id = params.project_id;
+    let mut owner_id = user_id;
+    if let Some(parent_id) = params.parent_id {
+        let parent = authorize_todo(&txn, user_id, parent_id, TodoRole::Editor).await?;
+        owner_id = parent.user_id;
+        ensure_child_depth(&txn, parent_id, 1).await?;
+        // 子任务跟随父任务所在的项目
+        if project_id.is_some_and(|id| parent.project_id != Some(id)) {
+            return Err(ApiError::Biz(String::from(
+                "子任务必须与父任务位于同一个项目！",
+            )));
+        }
+        project_id = parent.project_id;
+    }
+    if let Some(id) = project_id.filter(|_| params.parent_id.is_none()) {
+        find_writable_project(&txn, user_id, id).await?;
+    }
+    // 未指定排序值时追加到同一层级的末尾
+    let sort_order = match params.sort_order {
+        Some(sort_order) => sort_order,
+        None => append_rank(&txn, owner_id, params.parent_id).await?,
+    };
+    let status = params.status.unwrap_or(TodoStatus::Pending);
+    let todo = todo_list::ActiveModel {
+        user_id: Set(owner_id),
+        title: Set(params.title),
+        description: Set(params.description),
+        summary: Set(params.summary),
+        status: Set(Some(status.as_str().into())),
+        completed_at: Set(completed_at_for(status)),
+        priority: Set(Some(
+            params
+                .priority
+                .unwrap_or(TodoPriority::Medium)
+                .as_str()
+                .into(),
+        )),
+        due_date: Set(params.due_date),
+        is_important: Set(Some(params.is_important.unwrap_or(false))),
+        is_urgent: Set(Some(params.is_urgent.unwrap_or(false))),
+        tags: Set(params.tags),
+        estimated_time: Set(params.estimated_time),
+        parent_id: Set(params.parent_id),
+        sort_order: Set(Some(sort_order)),
+        project_id: Set(project_id),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    refresh_todo_index(&txn, &todo).await?;
+    txn.commit().await?;
+    tracing::info!("ID为: {} 的用户创建了待办事项 {}", user_id, todo.id);
+    Ok(ApiResponse::ok("创建成功！", Some(todo)))
+}
+
+/// 按 id 查询待办事项
+#[debug_handler]
+pub async fn get_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    Ok(ApiResponse::success(todo))
+}
+
+/// 全量更新待办事项（PUT）
+#[debug_handler]
+#[tracing::instrument(name = "update todo", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn update_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<UpdateTodoParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let txn = history.begin(db_pool).await?;
+    let model = authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Editor).await?;
+    let current_status = TodoStatus::from_db(model.status.as_deref());
+    let mut todo = model.clone().into_active_model();
+    // 状态变化必须符合状态机，重新打开任务请使用状态变更接口
+    let completed = params.status != current_status && params.status == TodoStatus::Completed;
+    if params.status != current_status {
+        apply_status_transition(&mut todo, current_status, params.status, false)?;
+    }
+    todo.title = Set(params.title);
+    todo.description = Set(params.description);
+    todo.summary = Set(params.summary);
+    todo.priority = Set(Some(params.priority.as_str().into()));
+    todo.due_date = Set(params.due_date);
+    todo.is_important = Set(Some(params.is_important));
+    todo.is_urgent = Set(Some(params.is_urgent));
+    todo.tags = Set(params.tags);
+    todo.estimated_time = Set(params.estimated_time);
+    todo.sort_order = Set(Some(params.sort_order));
+    let todo = todo.update(&txn).await?;
+    if needs_reindex(&model, &todo) {
+        refresh_todo_index(&txn, &todo).await?;
+    }
+    if completed {
+        spawn_next_occurrence(&txn, &todo).await?;
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::ok("更新成功！", Some(todo)))
+}
+
+/// 部分更新待办事项（PATCH），只修改请求中出现的字段
+#[debug_handler]
+#[tracing::instrument(name = "patch todo", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn patch_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<PatchTodoParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let txn = history.begin(db_pool).await?;
+    let model = authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Editor).await?;
+    let mut todo = model.clone().into_active_model();
+    let mut completed = false;
+    if let Some(title) = params.title {
+        todo.title = Set(title);
+    }
+    if let Some(description) = params.description {
+        todo.description = Set(description);
+    }
+    if let Some(summary) = params.summary {
+        todo.summary = Set(summary);
+    }
+    if let Some(status) = params.status {
+        let current_status = TodoStatus::from_db(model.status.as_deref());
+        if status != current_status {
+            apply_status_transition(&mut todo, current_status, status, false)?;
+            completed = status == TodoStatus::Completed;
+        }
+    }
+    if let Some(priority) = params.priority {
+        todo.priority = Set(Some(priority.as_str().into()));
+    }
+    if let Some(due_date) = params.due_date {
+        todo.due_date = Set(due_date);
+    }
+    if let Some(is_important) = params.is_important {
+        todo.is_important = Set(Some(is_important));
+    }
+    if let Some(is_urgent) = params.is_urgent {
+        todo.is_urgent = Set(Some(is_urgent));
+    }
+    if let Some(tags) = params.tags {
+        todo.tags = Set(tags);
+    }
+    if let Some(estimated_time) = params.estimated_time {
+        todo.estimated_time = Set(estimated_time);
+    }
+    if let Some(sort_order) = params.sort_order {
+        todo.sort_order = Set(Some(sort_order));
+    }
+    // 没有任何字段变化时直接返回原数据
+    if !todo.is_changed() {
+        return Ok(ApiResponse::ok("没有需要更新的内容", Some(model)));
+    }
+    let todo = todo.update(&txn).await?;
+    if needs_reindex(&model, &todo) {
+        refresh_todo_index(&txn, &todo).await?;
+    }
+    if completed {
+        spawn_next_occurrence(&txn, &todo).await?;
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::ok("更新成功！", Some(todo)))
+}
+
+/// 删除待办事项，连同子任务一起移入回收站
+#[debug_handler]
+#[tracing::instrument(name = "delete todo", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn delete_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, params.id, TodoRole::Editor).await?;
+    soft_delete_todo(&txn, &todo).await?;
+    txn.commit().await?;
+    tracing::info!("ID为: {} 的用户删除了待办事项 {}", principal.id, params.id);
+    Ok(ApiResponse::success_with_msg("删除成功！"))
+}
@@ -0,0 +1,260 @@
+use crate::calendar::vtodo::write_feed_calendar;
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::entities::calendar_feeds;
+use crate::entities::prelude::{CalendarFeeds, TodoList};
+use crate::entities::todo_list;
+use crate::handlers::todo::ical::load_parent_uids;
+use crate::handlers::todo::model::{
+    CalendarFeedFileParam, CalendarFeedIdParam, CreateCalendarFeedParam,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::router::API_PREFIX;
+use crate::state::app_state::AppState;
+use crate::utils::crypto::{generate_token, sha256_hex};
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, Statement,
+};
+
+/// 每个用户最多创建的日历订阅数量
+const MAX_FEEDS_PER_USER: u64 = 10;
+/// 建议客户端刷新订阅的间隔（分钟）
+const FEED_REFRESH_MINUTES: u32 = 15;
+/// 展示用的令牌前缀长度
+const TOKEN_PREFIX_CHARS: usize = 8;
+
+/// 新建的日历订阅，令牌只在创建时返回一次
+#[derive(Debug, serde::Serialize)]
+pub struct CreatedCalendarFeed {
+    #[serde(flatten)]
+    pub feed: calendar_feeds::Model,
+    pub token: String,
+    /// 订阅地址的路径部分
+    pub path: String,
+}
+
+/// 订阅内容的版本：摘要由待办事项的 id 和更新时间计算，删除待办事项也会改变摘要
+#[derive(Debug, FromQueryResult)]
+struct FeedVersion {
+    digest: String,
+}
+
+/// 查询当前用户的全部日历订阅
+#[debug_handler]
+pub async fn list_calendar_feeds_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Vec<calendar_feeds::Model>>> {
+    let feeds = CalendarFeeds::find()
+        .filter(calendar_feeds::Column::UserId.eq(principal.id as i32))
+        .order_by_asc(calendar_feeds::Column::Id)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(feeds))
+}
+
+/// 创建日历订阅，返回的令牌需要妥善保存，之后无法再次查看
+#[debug_handler]
+#[tracing::instrument(name = "create calendar feed", skip_all, fields(user_id = %principal.id))]
+pub async fn create_calendar_feed_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(params): ValidJson<CreateCalendarFeedParam>,
+) -> ApiResult<ApiResponse<CreatedCalendarFeed>> {
+    let user_id = principal.id as i32;
+    let count = CalendarFeeds::find()
+        .filter(calendar_feeds::Column::UserId.eq(user_id))
+        .count(db_pool)
+        .await?;
+    if count >= MAX_FEEDS_PER_USER {
+        return Err(ApiError::Biz(format!(
+            "每个用户最多创建 {MAX_FEEDS_PER_USER} 个日历订阅"
+        )));
+    }
+    let token = generate_token();
+    let feed = CalendarFeeds::insert(calendar_feeds::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(params.name.trim().to_string()),
+        token_hash: Set(sha256_hex(&token)),
+        token_prefix: Set(token.chars().take(TOKEN_PREFIX_CHARS).collect()),
+        ..Default::default()
+    })
+    .exec_with_returning(db_pool)
+    .await?;
+    tracing::info!("用户 {} 创建日历订阅 {}", user_id, feed.id);
+    Ok(ApiResponse::ok(
+        "订阅已创建，请妥善保存订阅地址！",
+        Some(CreatedCalendarFeed {
+            path: format!("{API_PREFIX}/feed/{token}.ics"),
+            feed,
+            token,
+        }),
+    ))
+}
+
+/// 撤销日历订阅，撤销后订阅地址立即失效
+#[debug_handler]
+#[tracing::instrument(name = "revoke calendar feed", skip_all, fields(user_id = %principal.id, feed_id = %params.feed_id))]
+pub async fn revoke_calendar_feed_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<CalendarFeedIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let result = CalendarFeeds::delete_many()
+        .filter(calendar_feeds::Column::Id.eq(params.feed_id))
+        .filter(calendar_feeds::Column::UserId.eq(principal.id as i32))
+        .exec(db_pool)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::Biz(String::from("日历订阅不存在")));
+    }
+    Ok(ApiResponse::ok("订阅已撤销！", None))
+}
+
+/// 计算订阅内容的摘要，只读取 id 和更新时间，不加载待办事项
+async fn load_feed_version<C: ConnectionTrait>(db: &C, user_id: i32) -> ApiResult<FeedVersion> {
+    FeedVersion::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"
+        SELECT md5(COALESCE(
+            string_agg(id::TEXT || ':' || COALESCE(updated_at::TEXT, ''), ',' ORDER BY id),
+            ''
+        )) AS digest
+        FROM todo_list
+        WHERE user_id = $1 AND due_date IS NOT NULL AND deleted_at IS NULL
+        "#,
+        [user_id.into()],
+    ))
+    .one(db)
+    .await?
+    .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("无法计算订阅内容的摘要")))
+}
+
+/// 记录访问时间，内容摘要变化时更新内容的修改时间，返回内容的修改时间
+///
+/// 访问时间每小时最多更新一次，避免客户端每次轮询都写数据库。
+async fn touch_feed<C: ConnectionTrait>(
+    db: &C,
+    feed: &calendar_feeds::Model,
+    digest: &str,
+) -> ApiResult<DateTimeWithTimeZone> {
+    let row = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            UPDATE calendar_feeds
+            SET content_modified_at = CASE
+                    WHEN content_digest IS DISTINCT FROM $2 THEN CURRENT_TIMESTAMP
+                    ELSE content_modified_at
+                END,
+                content_digest = $2,
+                last_used_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+              AND (content_digest IS DISTINCT FROM $2
+                   OR content_modified_at IS NULL
+                   OR last_used_at IS NULL
+                   OR last_used_at < CURRENT_TIMESTAMP - INTERVAL '1 hour')
+            RETURNING content_modified_at
+            "#,
+            [feed.id.into(), digest.into()],
+        ))
+        .await?;
+    let modified_at = match row {
+        Some(row) => row.try_get("", "content_modified_at")?,
+        None => feed.content_modified_at,
+    };
+    Ok(modified_at.unwrap_or_else(get_local_datetime_with_timezone))
+}
+
+/// HTTP 日期格式，例如 `Sun, 06 Nov 1994 08:49:37 GMT`
+fn http_date(dt: &DateTimeWithTimeZone) -> String {
+    dt.with_timezone(&Utc)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// 根据 If-None-Match / If-Modified-Since 判断客户端缓存是否仍然有效
+///
+/// 同时存在时以 If-None-Match 为准（RFC 9110 13.1.3）。
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified_at: &DateTimeWithTimeZone) -> bool {
+    if let Some(if_none_match) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+    {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+        .is_some_and(|since| modified_at.timestamp() <= since.timestamp())
+}
+
+/// 日历订阅地址 `GET /feed/{token}.ics`，不需要登录，凭令牌访问
+///
+/// 输出有截止时间的待办事项（VTODO 和 VEVENT），支持 ETag / Last-Modified 条件请求，
+/// 内容没有变化时返回 304，只需要计算一次摘要。
+#[debug_handler]
+#[tracing::instrument(name = "calendar feed", skip_all, fields(feed_id = tracing::field::Empty))]
+pub async fn calendar_feed_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    ValidPath(params): ValidPath<CalendarFeedFileParam>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let token = params.file.strip_suffix(".ics").ok_or(ApiError::NotFound)?;
+    let feed = CalendarFeeds::find()
+        .filter(calendar_feeds::Column::TokenHash.eq(sha256_hex(token)))
+        .one(db_pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    tracing::Span::current().record("feed_id", feed.id);
+    let version = load_feed_version(db_pool, feed.user_id).await?;
+    let modified_at = touch_feed(db_pool, &feed, &version.digest).await?;
+    let etag = format!("\"{}\"", version.digest);
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::LAST_MODIFIED, http_date(&modified_at)),
+        (header::CACHE_CONTROL, String::from("private, no-cache")),
+    ];
+    if is_not_modified(&headers, &etag, &modified_at) {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    let todos = TodoList::find()
+        .filter(todo_list::Column::UserId.eq(feed.user_id))
+        .filter(todo_list::Column::DueDate.is_not_null())
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .order_by_asc(todo_list::Column::DueDate)
+        .order_by_asc(todo_list::Column::Id)
+        .all(db_pool)
+        .await?;
+    let parent_uids = load_parent_uids(db_pool, feed.user_id, &todos).await?;
+    let body = write_feed_calendar(
+        &feed.name,
+        &todos,
+        &parent_uids,
+        &modified_at,
+        FEED_REFRESH_MINUTES,
+    );
+    Ok((
+        cache_headers,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/calendar; charset=utf-8"),
+        )],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,169 @@
+use crate::common::valid::{ValidPath, ValidQuery};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::{TodoHistory, TodoList};
+use crate::entities::{todo_history, todo_list};
+use crate::handlers::todo::access::{authorize_todo, ensure_role, todo_role};
+use crate::handlers::todo::model::{TodoHistoryQuery, TodoIdParam, TodoRevisionParam};
+use crate::middlewares::auth::principal::Principal;
+use crate::middlewares::request_id::make_request_id::request_id_of;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::{needs_reindex, refresh_todo_index};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::{FromRequestParts, State};
+use axum::http::request::Parts;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    DbBackend, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Statement,
+    TransactionTrait,
+};
+use tower_http::request_id::RequestId;
+
+/// 修改历史的上下文：操作人和请求 id
+///
+/// 修改历史由数据库触发器记录，写操作开启事务时通过 `begin` 把上下文传给触发器，
+/// 只在当前事务内有效；没有上下文的修改（例如后台任务）操作人为空。
+#[derive(Debug, Clone)]
+pub struct HistoryContext {
+    actor_id: i32,
+    request_id: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for HistoryContext
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let principal = parts
+            .extensions
+            .get::<Principal>()
+            .ok_or_else(|| ApiError::Unauthenticated(String::from("没有登陆或登陆已过期")))?;
+        Ok(Self {
+            actor_id: principal.id as i32,
+            request_id: parts.extensions.get::<RequestId>().and_then(request_id_of),
+        })
+    }
+}
+
+impl HistoryContext {
+    /// 开启事务并写入修改历史的上下文
+    pub async fn begin(&self, db: &DatabaseConnection) -> ApiResult<DatabaseTransaction> {
+        let txn = db.begin().await?;
+        self.apply(&txn).await?;
+        Ok(txn)
+    }
+
+    /// 在已经开启的事务中写入修改历史的上下文
+    pub async fn apply<C: ConnectionTrait>(&self, db: &C) -> ApiResult<()> {
+        db.execute_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT set_config('todo.actor_id', $1, true), set_config('todo.request_id', $2, true)",
+            [
+                self.actor_id.to_string().into(),
+                self.request_id.clone().unwrap_or_default().into(),
+            ],
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// 回滚时恢复的字段
+///
+/// 父任务、排序、用时、UID 等由各自的接口维护，回滚只恢复待办事项自身的内容。
+#[derive(Debug, serde::Deserialize)]
+struct RevisionSnapshot {
+    title: String,
+    description: Option<String>,
+    summary: Option<Vec<String>>,
+    status: Option<String>,
+    priority: Option<String>,
+    due_date: Option<DateTimeWithTimeZone>,
+    completed_at: Option<DateTimeWithTimeZone>,
+    is_important: Option<bool>,
+    is_urgent: Option<bool>,
+    tags: Option<Vec<String>>,
+    estimated_time: Option<i32>,
+}
+
+/// 查询待办事项的修改历史，按版本号倒序，回收站中的待办事项也可以查询
+#[debug_handler]
+pub async fn list_todo_history_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidQuery(params): ValidQuery<TodoHistoryQuery>,
+) -> ApiResult<ApiResponse<Vec<todo_history::Model>>> {
+    let todo = TodoList::find_by_id(path.id)
+        .one(db_pool)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待办事项不存在或无权访问！")))?;
+    ensure_role(
+        todo_role(db_pool, principal.id as i32, &todo).await?,
+        TodoRole::Viewer,
+    )?;
+    let history = TodoHistory::find()
+        .filter(todo_history::Column::TodoId.eq(path.id))
+        .filter(todo_history::Column::UserId.eq(todo.user_id))
+        .order_by_desc(todo_history::Column::Revision)
+        .limit(params.limit.unwrap_or(20))
+        .offset(params.offset.unwrap_or(0))
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(history))
+}
+
+/// 把待办事项回滚到指定版本的内容，回滚本身也会记录为一个新版本
+#[debug_handler]
+#[tracing::instrument(name = "revert todo", skip_all, fields(user_id = %principal.id, id = %path.id, revision = %path.revision))]
+pub async fn revert_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoRevisionParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let user_id = principal.id as i32;
+    let txn = history.begin(db_pool).await?;
+    let model = authorize_todo(&txn, user_id, path.id, TodoRole::Editor).await?;
+    let revision = TodoHistory::find()
+        .filter(todo_history::Column::TodoId.eq(path.id))
+        .filter(todo_history::Column::Revision.eq(path.revision))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| ApiError::Biz(format!("版本 {} 不存在！", path.revision)))?;
+    let snapshot: RevisionSnapshot = serde_json::from_value(revision.snapshot)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("无法解析版本 {}：{e}", path.revision)))?;
+    let mut todo = model.clone().into_active_model();
+    todo.title.set_if_not_equals(snapshot.title);
+    todo.description.set_if_not_equals(snapshot.description);
+    todo.summary.set_if_not_equals(snapshot.summary);
+    todo.status.set_if_not_equals(snapshot.status);
+    todo.priority.set_if_not_equals(snapshot.priority);
+    todo.due_date.set_if_not_equals(snapshot.due_date);
+    todo.completed_at.set_if_not_equals(snapshot.completed_at);
+    todo.is_important.set_if_not_equals(snapshot.is_important);
+    todo.is_urgent.set_if_not_equals(snapshot.is_urgent);
+    todo.tags.set_if_not_equals(snapshot.tags);
+    todo.estimated_time
+        .set_if_not_equals(snapshot.estimated_time);
+    if !todo.is_changed() {
+        return Ok(ApiResponse::ok("内容与该版本相同，无需回滚！", Some(model)));
+    }
+    let todo = todo.update(&txn).await?;
+    if needs_reindex(&model, &todo) {
+        refresh_todo_index(&txn, &todo).await?;
+    }
+    txn.commit().await?;
+    tracing::info!(
+        "ID为: {} 的用户把待办事项 {} 回滚到了版本 {}",
+        principal.id,
+        path.id,
+        path.revision
+    );
+    Ok(ApiResponse::ok("回滚成功！", Some(todo)))
+}
@@ -0,0 +1,290 @@
+use crate::calendar::vtodo::{VTodo, parse_vtodos, write_calendar};
+use crate::common::valid::ValidQuery;
+use crate::domain::todo_status::{TodoStatus, completed_at_for};
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{IcsImportQuery, TodoListQuery, TodoPriority};
+use crate::handlers::todo::project::follow_parent_project;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::time_tracking::rollup_actual_time;
+use crate::handlers::todo::tree::{
+    ensure_child_depth, load_ancestor_ids, load_user_subtree, lock_user_tree,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::refresh_todo_index;
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, TryIntoModel,
+};
+use std::collections::HashMap;
+
+/// 单个 VTODO 的导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IcsImportAction {
+    Created,
+    Updated,
+    Failed,
+}
+
+/// 单个 VTODO 的导入明细
+#[derive(Debug, serde::Serialize)]
+pub struct IcsImportItem {
+    pub uid: Option<String>,
+    pub action: IcsImportAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 导入报告，dry_run 为 true 时数据没有写入
+#[derive(Debug, serde::Serialize)]
+pub struct IcsImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub items: Vec<IcsImportItem>,
+}
+
+/// 查询父任务的 UID，父任务不在导出范围内时也能生成 RELATED-TO
+pub async fn load_parent_uids<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todos: &[todo_list::Model],
+) -> ApiResult<HashMap<i32, String>> {
+    let parent_ids: Vec<i32> = todos.iter().filter_map(|t| t.parent_id).collect();
+    if parent_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(TodoList::find()
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::Id.is_in(parent_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t.ical_uid))
+        .collect())
+}
+
+/// 按 UID 查询当前用户的待办事项
+pub async fn find_by_uid<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    uid: &str,
+) -> ApiResult<Option<todo_list::Model>> {
+    Ok(TodoList::find()
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::IcalUid.eq(uid))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .one(db)
+        .await?)
+}
+
+/// 导出为 iCalendar 文件，筛选条件与列表接口相同（忽略排序和分页参数）
+#[debug_handler]
+#[tracing::instrument(name = "export ics", skip_all, fields(user_id = %principal.id))]
+pub async fn export_ics_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<TodoListQuery>,
+) -> ApiResult<Response> {
+    let user_id = principal.id as i32;
+    let todos = params
+        .to_filter_select(user_id)?
+        .order_by_asc(todo_list::Column::SortOrder)
+        .order_by_asc(todo_list::Column::Id)
+        .all(db_pool)
+        .await?;
+    let parent_uids = load_parent_uids(db_pool, user_id, &todos).await?;
+    let body = write_calendar(&principal.name, &todos, &parent_uids);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"todos.ics\"",
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// 按 UID 新增或更新一条待办事项，父子关系在全部导入后再处理
+pub async fn upsert_vtodo<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    vtodo: &VTodo,
+) -> ApiResult<(IcsImportAction, todo_list::Model)> {
+    let existing = find_by_uid(db, user_id, &vtodo.uid).await?;
+    let current_status = existing
+        .as_ref()
+        .map(|t| TodoStatus::from_db(t.status.as_deref()));
+    let status = vtodo
+        .status
+        .or(current_status)
+        .unwrap_or(TodoStatus::Pending);
+    // 状态来自外部日历，直接同步，不经过状态机校验
+    let completed_at = match status {
+        TodoStatus::Completed => vtodo
+            .completed
+            .or(existing.as_ref().and_then(|t| t.completed_at))
+            .or_else(|| completed_at_for(status)),
+        _ => None,
+    };
+    let tags = (!vtodo.categories.is_empty()).then(|| vtodo.categories.clone());
+    let (action, mut todo) = match existing {
+        Some(model) => (IcsImportAction::Updated, model.into_active_model()),
+        None => (
+            IcsImportAction::Created,
+            todo_list::ActiveModel {
+                user_id: Set(user_id),
+                ical_uid: Set(vtodo.uid.clone()),
+                sort_order: Set(Some(append_rank(db, user_id, None).await?)),
+                priority: Set(Some(TodoPriority::Medium.as_str().into())),
+                ..Default::default()
+            },
+        ),
+    };
+    todo.title = Set(vtodo.summary.clone());
+    todo.description = Set(vtodo.description.clone());
+    todo.status = Set(Some(status.as_str().into()));
+    todo.completed_at = Set(completed_at);
+    if let Some(priority) = vtodo.priority {
+        todo.priority = Set(Some(priority.as_str().into()));
+    }
+    todo.due_date = Set(vtodo.due);
+    todo.tags = Set(tags);
+    let todo = todo.save(db).await?.try_into_model()?;
+    refresh_todo_index(db, &todo).await?;
+    Ok((action, todo))
+}
+
+/// 把待办事项挂到 RELATED-TO 指向的父任务下，无法挂载时返回原因
+///
+/// 挂载后整棵子树跟随父任务所在的项目。
+pub async fn link_parent<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    todo_id: i32,
+    parent_uid: &str,
+    imported: &HashMap<String, i32>,
+) -> ApiResult<Option<String>> {
+    let parent_id = match imported.get(parent_uid) {
+        Some(id) => *id,
+        None => match find_by_uid(db, user_id, parent_uid).await? {
+            Some(parent) => parent.id,
+            None => return Ok(Some(format!("父任务 {parent_uid} 不存在，未设置父任务"))),
+        },
+    };
+    let tree = load_user_subtree(db, user_id, todo_id).await?;
+    if tree.todo.parent_id == Some(parent_id) {
+        return Ok(None);
+    }
+    if parent_id == todo_id || load_ancestor_ids(db, parent_id).await?.contains(&todo_id) {
+        return Ok(Some(format!(
+            "父任务 {parent_uid} 会形成循环，未设置父任务"
+        )));
+    }
+    if let Err(e) = ensure_child_depth(db, parent_id, tree.height()).await {
+        return Ok(Some(format!("{e}，未设置父任务")));
+    }
+    let old_parent_id = tree.todo.parent_id;
+    let mut todo = tree.todo.into_active_model();
+    todo.parent_id = Set(Some(parent_id));
+    todo.sort_order = Set(Some(append_rank(db, user_id, Some(parent_id)).await?));
+    todo.update(db).await?;
+    follow_parent_project(db, todo_id, parent_id).await?;
+    if let Some(old_parent_id) = old_parent_id {
+        rollup_actual_time(db, old_parent_id).await?;
+    }
+    rollup_actual_time(db, parent_id).await?;
+    Ok(None)
+}
+
+/// 导入 iCalendar 文件（请求体为 .ics 文本），按 UID 新增或更新待办事项
+///
+/// 整个导入在一个事务中完成；dry_run 时执行全部步骤后回滚，只返回导入报告。
+#[debug_handler]
+#[tracing::instrument(name = "import ics", skip_all, fields(user_id = %principal.id))]
+pub async fn import_ics_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidQuery(params): ValidQuery<IcsImportQuery>,
+    body: String,
+) -> ApiResult<ApiResponse<IcsImportReport>> {
+    let user_id = principal.id as i32;
+    let vtodos =
+        parse_vtodos(&body).map_err(|e| ApiError::Biz(format!("无法解析日历文件：{e}")))?;
+    let txn = history.begin(db_pool).await?;
+    lock_user_tree(&txn, user_id).await?;
+    let mut items = Vec::with_capacity(vtodos.len());
+    let mut imported: HashMap<String, i32> = HashMap::new();
+    let mut parents = vec![];
+    for vtodo in vtodos {
+        match vtodo {
+            Ok(vtodo) => {
+                let (action, todo) = upsert_vtodo(&txn, user_id, &vtodo).await?;
+                imported.insert(vtodo.uid.clone(), todo.id);
+                if let Some(parent_uid) = vtodo.parent_uid {
+                    parents.push((items.len(), todo.id, parent_uid));
+                }
+                items.push(IcsImportItem {
+                    uid: Some(vtodo.uid),
+                    action,
+                    todo_id: Some(todo.id),
+                    message: None,
+                });
+            }
+            Err((uid, message)) => items.push(IcsImportItem {
+                uid,
+                action: IcsImportAction::Failed,
+                todo_id: None,
+                message: Some(message),
+            }),
+        }
+    }
+    for (index, todo_id, parent_uid) in parents {
+        items[index].message = link_parent(&txn, user_id, todo_id, &parent_uid, &imported).await?;
+    }
+    let count = |action| items.iter().filter(|i| i.action == action).count();
+    let report = IcsImportReport {
+        dry_run: params.dry_run,
+        created: count(IcsImportAction::Created),
+        updated: count(IcsImportAction::Updated),
+        failed: count(IcsImportAction::Failed),
+        items,
+    };
+    if params.dry_run {
+        txn.rollback().await?;
+    } else {
+        txn.commit().await?;
+        tracing::info!(
+            "用户 {} 导入日历：新增 {}，更新 {}，失败 {}",
+            user_id,
+            report.created,
+            report.updated,
+            report.failed
+        );
+    }
+    Ok(ApiResponse::ok(
+        if params.dry_run {
+            "预检完成！"
+        } else {
+            "导入完成！"
+        },
+        Some(report),
+    ))
+}
@@ -0,0 +1,353 @@
+use crate::common::valid::ValidQuery;
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::project::crud::visible_project_condition;
+use crate::handlers::todo::access::{accessible_todo_condition, shared_todo_condition};
+use crate::handlers::todo::model::{
+    SortDirection, TodoListQuery, TodoPriority, TodoScope, TodoSortField,
+};
+use crate::handlers::todo::tags::tags_condition;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::page::Page;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::segment::{segment, to_tsquery_literal};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::{Expr, ExprTrait, NullOrdering, Order};
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    Select,
+};
+use std::str::FromStr;
+
+/// 默认每页数量
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+/// 分页游标，记录上一页最后一条数据的排序值和 id
+///
+/// 序列化为 JSON 后再做 URL 安全的 base64 编码，对客户端是不透明的字符串。
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TodoCursor {
+    sort: TodoSortField,
+    order: SortDirection,
+    value: serde_json::Value,
+    id: i32,
+}
+
+impl TodoCursor {
+    /// 根据当前页最后一条数据生成游标
+    pub fn from_last(sort: TodoSortField, order: SortDirection, todo: &todo_list::Model) -> Self {
+        Self {
+            sort,
+            order,
+            value: sort.cursor_value(todo),
+            id: todo.id,
+        }
+    }
+
+    /// 编码为不透明字符串
+    pub fn encode(&self) -> ApiResult<String> {
+        let json = serde_json::to_vec(self).map_err(anyhow::Error::from)?;
+        Ok(URL_SAFE_NO_PAD.encode(json))
+    }
+
+    /// 从不透明字符串解码
+    pub fn decode(cursor: &str) -> ApiResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| ApiError::ValidationError(String::from("分页游标无效")))
+    }
+}
+
+impl TodoSortField {
+    /// 排序使用的表达式，优先级按权重排序而不是按字符串排序
+    fn key_expr(&self) -> Expr {
+        let column = match self {
+            TodoSortField::Id => todo_list::Column::Id,
+            TodoSortField::SortOrder => todo_list::Column::SortOrder,
+            TodoSortField::CreatedAt => todo_list::Column::CreatedAt,
+            TodoSortField::UpdatedAt => todo_list::Column::UpdatedAt,
+            TodoSortField::DueDate => todo_list::Column::DueDate,
+            TodoSortField::CompletedAt => todo_list::Column::CompletedAt,
+            TodoSortField::Status => todo_list::Column::Status,
+            TodoSortField::Priority => {
+                return [
+                    TodoPriority::Low,
+                    TodoPriority::Medium,
+                    TodoPriority::High,
+                    TodoPriority::Urgent,
+                ]
+                .into_iter()
+                .fold(
+                    sea_orm::sea_query::CaseStatement::new(),
+                    |case, priority| {
+                        case.case(
+                            todo_list::Column::Priority.eq(priority.as_str()),
+                            priority.rank(),
+                        )
+                    },
+                )
+                .finally(Expr::null())
+                .into();
+            }
+        };
+        Expr::col((todo_list::Entity, column))
+    }
+
+    /// 取出某条数据在该排序字段上的值，用于生成游标
+    fn cursor_value(&self, todo: &todo_list::Model) -> serde_json::Value {
+        let time = |value: Option<DateTimeWithTimeZone>| {
+            value.map_or(serde_json::Value::Null, |t| t.to_rfc3339().into())
+        };
+        match self {
+            TodoSortField::Id => todo.id.into(),
+            TodoSortField::SortOrder => todo.sort_order.into(),
+            TodoSortField::CreatedAt => time(todo.created_at),
+            TodoSortField::UpdatedAt => time(todo.updated_at),
+            TodoSortField::DueDate => time(todo.due_date),
+            TodoSortField::CompletedAt => time(todo.completed_at),
+            TodoSortField::Status => todo.status.clone().into(),
+            TodoSortField::Priority => todo
+                .priority
+                .as_deref()
+                .and_then(|p| TodoPriority::from_str(p).ok())
+                .map(|p| p.rank())
+                .into(),
+        }
+    }
+
+    /// 把游标中的值还原成 SQL 表达式，空值返回 `None`
+    fn value_expr(&self, value: &serde_json::Value) -> ApiResult<Option<Expr>> {
+        if value.is_null() {
+            return Ok(None);
+        }
+        let invalid = || ApiError::ValidationError(String::from("分页游标无效"));
+        let expr = match self {
+            TodoSortField::Id | TodoSortField::SortOrder | TodoSortField::Priority => {
+                let number = value.as_i64().ok_or_else(invalid)?;
+                Expr::val(i32::try_from(number).map_err(|_| invalid())?)
+            }
+            TodoSortField::CreatedAt
+            | TodoSortField::UpdatedAt
+            | TodoSortField::DueDate
+            | TodoSortField::CompletedAt => {
+                let time = value
+                    .as_str()
+                    .and_then(|s| DateTimeWithTimeZone::parse_from_rfc3339(s).ok())
+                    .ok_or_else(invalid)?;
+                Expr::val(time)
+            }
+            TodoSortField::Status => Expr::val(value.as_str().ok_or_else(invalid)?),
+        };
+        Ok(Some(expr))
+    }
+}
+
+/// 解析逗号分隔的枚举列表
+fn parse_list<T: FromStr>(value: &str, name: &str) -> ApiResult<Vec<T>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse().map_err(|_| {
+                ApiError::ValidationError(format!("{name} 参数中包含无效的值：{item}"))
+            })
+        })
+        .collect()
+}
+
+impl TodoListQuery {
+    /// 排序字段，默认按 sort_order
+    pub fn sort_field(&self) -> TodoSortField {
+        self.sort.unwrap_or_default()
+    }
+
+    /// 排序方向，默认升序
+    pub fn sort_direction(&self) -> SortDirection {
+        self.order.unwrap_or_default()
+    }
+
+    /// 每页数量
+    pub fn page_size(&self) -> u64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    /// 把筛选条件编译为 SeaORM 查询，只包含 WHERE 条件，不含排序和分页
+    pub fn to_filter_select(&self, user_id: i32) -> ApiResult<Select<todo_list::Entity>> {
+        let scope = match self.scope.unwrap_or_default() {
+            TodoScope::All => accessible_todo_condition(user_id),
+            TodoScope::Own => Condition::all().add(todo_list::Column::UserId.eq(user_id)),
+            TodoScope::Shared => shared_todo_condition(user_id),
+        };
+        let mut select = TodoList::find()
+            .filter(scope)
+            .filter(todo_list::Column::DeletedAt.is_null());
+        if let Some(status) = &self.status {
+            let statuses: Vec<TodoStatus> = parse_list(status, "status")?;
+            select =
+                select.filter(todo_list::Column::Status.is_in(statuses.iter().map(|s| s.as_str())));
+        }
+        if let Some(priority) = &self.priority {
+            let priorities: Vec<TodoPriority> = parse_list(priority, "priority")?;
+            select = select
+                .filter(todo_list::Column::Priority.is_in(priorities.iter().map(|p| p.as_str())));
+        }
+        if let Some(tags) = &self.tags {
+            let tags: Vec<String> = parse_list(tags, "tags")?;
+            if !tags.is_empty() {
+                select = select.filter(tags_condition(tags, self.tag_mode.unwrap_or_default()));
+            }
+        }
+        if let Some(due_from) = self.due_from {
+            select = select.filter(todo_list::Column::DueDate.gte(due_from));
+        }
+        if let Some(due_to) = self.due_to {
+            select = select.filter(todo_list::Column::DueDate.lte(due_to));
+        }
+        if let Some(is_important) = self.is_important {
+            select = select.filter(todo_list::Column::IsImportant.eq(is_important));
+        }
+        if let Some(is_urgent) = self.is_urgent {
+            select = select.filter(todo_list::Column::IsUrgent.eq(is_urgent));
+        }
+        if let Some(parent_id) = self.parent_id {
+            select = select.filter(todo_list::Column::ParentId.eq(parent_id));
+        } else if self.top_level == Some(true) {
+            select = select.filter(todo_list::Column::ParentId.is_null());
+        }
+        // 按项目查询时可以看到已归档项目中的待办事项，默认视图不显示
+        if let Some(project_id) = self.project_id {
+            select = select.filter(todo_list::Column::ProjectId.eq(project_id));
+        } else if self.inbox == Some(true) {
+            select = select.filter(todo_list::Column::ProjectId.is_null());
+        } else if self.include_archived != Some(true) {
+            select = select.filter(visible_project_condition());
+        }
+        if let Some(q) = &self.q {
+            let tokens = segment(q);
+            if tokens.is_empty() {
+                return Err(ApiError::ValidationError(String::from(
+                    "搜索关键字中没有可以检索的内容",
+                )));
+            }
+            select = select.filter(Expr::cust_with_values(
+                "search_vector @@ CAST($1 AS TSQUERY)",
+                [to_tsquery_literal(&tokens)],
+            ));
+        }
+        Ok(select)
+    }
+
+    /// 在筛选条件的基础上加上游标条件和排序
+    ///
+    /// 升序时空值排在最后，降序时空值排在最前，始终以 id 作为第二排序键保证顺序稳定。
+    pub fn apply_keyset(
+        &self,
+        select: Select<todo_list::Entity>,
+        cursor: Option<&TodoCursor>,
+    ) -> ApiResult<Select<todo_list::Entity>> {
+        let sort = self.sort_field();
+        let order = self.sort_direction();
+        let id = Expr::col((todo_list::Entity, todo_list::Column::Id));
+        let mut select = select;
+        if let Some(cursor) = cursor {
+            if cursor.sort != sort || cursor.order != order {
+                return Err(ApiError::ValidationError(String::from(
+                    "分页游标与当前排序方式不一致",
+                )));
+            }
+            let key = sort.key_expr();
+            let value = sort.value_expr(&cursor.value)?;
+            let condition = match (sort, order, value) {
+                (TodoSortField::Id, SortDirection::Asc, _) => {
+                    Condition::all().add(id.gt(cursor.id))
+                }
+                (TodoSortField::Id, SortDirection::Desc, _) => {
+                    Condition::all().add(id.lt(cursor.id))
+                }
+                (_, SortDirection::Asc, Some(value)) => Condition::any()
+                    .add(key.clone().gt(value.clone()))
+                    .add(
+                        Condition::all()
+                            .add(key.clone().eq(value))
+                            .add(id.gt(cursor.id)),
+                    )
+                    .add(key.is_null()),
+                (_, SortDirection::Asc, None) => {
+                    Condition::all().add(key.is_null()).add(id.gt(cursor.id))
+                }
+                (_, SortDirection::Desc, Some(value)) => Condition::any()
+                    .add(key.clone().lt(value.clone()))
+                    .add(Condition::all().add(key.eq(value)).add(id.lt(cursor.id))),
+                (_, SortDirection::Desc, None) => Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(key.clone().is_null())
+                            .add(id.lt(cursor.id)),
+                    )
+                    .add(key.is_not_null()),
+            };
+            select = select.filter(condition);
+        }
+        let (ord, nulls) = match order {
+            SortDirection::Asc => (Order::Asc, NullOrdering::Last),
+            SortDirection::Desc => (Order::Desc, NullOrdering::First),
+        };
+        if sort != TodoSortField::Id {
+            select = select.order_by_with_nulls(sort.key_expr(), ord.clone(), nulls);
+        }
+        Ok(select.order_by(todo_list::Column::Id, ord))
+    }
+}
+
+/// 按条件筛选、排序并分页查询当前用户的待办事项
+///
+/// 使用游标（keyset）分页，翻页时不需要 OFFSET，页数很深时依然能利用索引。
+#[debug_handler]
+pub async fn list_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<TodoListQuery>,
+) -> ApiResult<ApiResponse<Page<todo_list::Model>>> {
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(TodoCursor::decode)
+        .transpose()?;
+    let select = params.to_filter_select(principal.id as i32)?;
+    let total = select.clone().count(db_pool).await?;
+    let limit = params.page_size();
+    // 多取一条用来判断是否还有下一页
+    let mut items = params
+        .apply_keyset(select, cursor.as_ref())?
+        .limit(limit + 1)
+        .all(db_pool)
+        .await?;
+    let next_cursor = if items.len() as u64 > limit {
+        items.truncate(limit as usize);
+        items
+            .last()
+            .map(|last| {
+                TodoCursor::from_last(params.sort_field(), params.sort_direction(), last).encode()
+            })
+            .transpose()?
+    } else {
+        None
+    };
+    Ok(ApiResponse::success(Page::new(
+        items,
+        total,
+        limit,
+        next_cursor,
+    )))
+}
@@ -0,0 +1,118 @@
+use crate::common::valid::ValidJson;
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::TodoStatus;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::project::crud::visible_project_condition;
+use crate::handlers::todo::access::{accessible_todo_condition, authorize_todo};
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{MoveQuadrantParam, Quadrant};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::{Expr, NullOrdering, Order};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use std::collections::BTreeSet;
+
+/// 单个象限中的待办事项
+#[derive(Debug, serde::Serialize)]
+pub struct QuadrantBucket {
+    pub quadrant: Quadrant,
+    pub count: usize,
+    pub todos: Vec<todo_list::Model>,
+}
+
+/// 四象限视图
+#[derive(Debug, serde::Serialize)]
+pub struct EisenhowerMatrix {
+    pub total: usize,
+    pub quadrants: Vec<QuadrantBucket>,
+}
+
+/// 批量移动象限的结果
+#[derive(Debug, serde::Serialize)]
+pub struct MoveQuadrantResult {
+    pub quadrant: Quadrant,
+    pub updated: u64,
+}
+
+/// 查询当前用户可以访问的未完成待办事项（包括共享的），并按重要/紧急划分到四个象限
+///
+/// 每个象限内按截止时间（空值在后）、sort_order、id 排序。
+#[debug_handler]
+pub async fn get_matrix_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<EisenhowerMatrix>> {
+    let todos = TodoList::find()
+        .filter(accessible_todo_condition(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(visible_project_condition())
+        .filter(todo_list::Column::Status.is_in([
+            TodoStatus::Pending.as_str(),
+            TodoStatus::InProgress.as_str(),
+        ]))
+        .order_by_with_nulls(todo_list::Column::DueDate, Order::Asc, NullOrdering::Last)
+        .order_by_asc(todo_list::Column::SortOrder)
+        .order_by_asc(todo_list::Column::Id)
+        .all(db_pool)
+        .await?;
+    let total = todos.len();
+    let mut quadrants: Vec<QuadrantBucket> = Quadrant::ALL
+        .into_iter()
+        .map(|quadrant| QuadrantBucket {
+            quadrant,
+            count: 0,
+            todos: Vec::new(),
+        })
+        .collect();
+    // 查询结果已经有序，按顺序放入对应象限即可保持组内顺序
+    for todo in todos {
+        let quadrant = Quadrant::from_flags(
+            todo.is_important.unwrap_or(false),
+            todo.is_urgent.unwrap_or(false),
+        );
+        let bucket = &mut quadrants[quadrant as usize];
+        bucket.count += 1;
+        bucket.todos.push(todo);
+    }
+    Ok(ApiResponse::success(EisenhowerMatrix { total, quadrants }))
+}
+
+/// 批量把待办事项拖动到指定象限
+///
+/// 每个 id 都需要编辑者及以上角色，只要有一个无权修改就取消整个操作；
+/// 鉴权通过后在一条 UPDATE 中同时修改 is_important 和 is_urgent。
+#[debug_handler]
+#[tracing::instrument(name = "move quadrant", skip_all, fields(user_id = %principal.id, quadrant = ?params.quadrant))]
+pub async fn move_quadrant_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidJson(params): ValidJson<MoveQuadrantParam>,
+) -> ApiResult<ApiResponse<MoveQuadrantResult>> {
+    let ids: BTreeSet<i32> = params.ids.into_iter().collect();
+    let (is_important, is_urgent) = params.quadrant.flags();
+    let txn = history.begin(db_pool).await?;
+    for id in &ids {
+        authorize_todo(&txn, principal.id as i32, *id, TodoRole::Editor).await?;
+    }
+    let result = TodoList::update_many()
+        .col_expr(todo_list::Column::IsImportant, Expr::value(is_important))
+        .col_expr(todo_list::Column::IsUrgent, Expr::value(is_urgent))
+        .filter(todo_list::Column::Id.is_in(ids.iter().copied()))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "移动成功！",
+        Some(MoveQuadrantResult {
+            quadrant: params.quadrant,
+            updated: result.rows_affected,
+        }),
+    ))
+}
@@ -0,0 +1,25 @@
+pub mod access;
+pub mod analytics;
+pub mod attachments;
+pub mod batch;
+pub mod comments;
+pub mod crud;
+pub mod feed;
+pub mod history;
+pub mod ical;
+pub mod listing;
+pub mod matrix;
+pub mod model;
+pub mod project;
+pub mod recurrence;
+pub mod reminders;
+pub mod reorder;
+pub mod search;
+pub mod status;
+pub mod tags;
+pub mod time_tracking;
+pub mod todo_txt;
+pub mod transfer;
+pub mod trash;
+pub mod tree;
+pub mod undo;
@@ -0,0 +1,596 @@
+use crate::common::serde::deserialize_optional_field;
+use crate::domain::todo_status::TodoStatus;
+use crate::response::errors::ApiError;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+/// 待办事项优先级，与数据库中 priority 字段的 CHECK 约束保持一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoPriority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl TodoPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoPriority::Low => "low",
+            TodoPriority::Medium => "medium",
+            TodoPriority::High => "high",
+            TodoPriority::Urgent => "urgent",
+        }
+    }
+
+    /// 优先级的排序权重，数值越大优先级越高
+    pub fn rank(&self) -> i32 {
+        match self {
+            TodoPriority::Low => 1,
+            TodoPriority::Medium => 2,
+            TodoPriority::High => 3,
+            TodoPriority::Urgent => 4,
+        }
+    }
+}
+
+impl std::str::FromStr for TodoPriority {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(TodoPriority::Low),
+            "medium" => Ok(TodoPriority::Medium),
+            "high" => Ok(TodoPriority::High),
+            "urgent" => Ok(TodoPriority::Urgent),
+            _ => Err(ApiError::Biz(format!("未知的优先级：{s}"))),
+        }
+    }
+}
+
+/// 按 id 操作待办事项时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TodoIdParam {
+    #[validate(range(min = 1, message = "待办事项的 id 必须大于 0"))]
+    pub id: i32,
+}
+
+/// 创建待办事项的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateTodoParam {
+    #[validate(length(min = 1, max = 200, message = "标题长度必须在 1 到 200 之间"))]
+    pub title: String,
+    pub description: Option<String>,
+    pub summary: Option<Vec<String>>,
+    pub status: Option<TodoStatus>,
+    pub priority: Option<TodoPriority>,
+    pub due_date: Option<DateTimeWithTimeZone>,
+    pub is_important: Option<bool>,
+    pub is_urgent: Option<bool>,
+    #[validate(length(max = 20, message = "标签数量不能超过 20 个"))]
+    pub tags: Option<Vec<String>>,
+    #[validate(range(min = 0, message = "预估时间不能小于 0"))]
+    pub estimated_time: Option<i32>,
+    #[validate(range(min = 1, message = "父任务的 id 必须大于 0"))]
+    pub parent_id: Option<i32>,
+    pub sort_order: Option<i32>,
+    /// 所属项目，子任务跟随父任务所在的项目，可以省略
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub project_id: Option<i32>,
+}
+
+/// 全量更新待办事项的参数（PUT），未传的可选字段会被清空
+///
+/// 实际用时由计时记录汇总得出，不能通过更新接口直接修改。
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct UpdateTodoParam {
+    #[validate(length(min = 1, max = 200, message = "标题长度必须在 1 到 200 之间"))]
+    pub title: String,
+    pub description: Option<String>,
+    pub summary: Option<Vec<String>>,
+    pub status: TodoStatus,
+    pub priority: TodoPriority,
+    pub due_date: Option<DateTimeWithTimeZone>,
+    pub is_important: bool,
+    pub is_urgent: bool,
+    #[validate(length(max = 20, message = "标签数量不能超过 20 个"))]
+    pub tags: Option<Vec<String>>,
+    #[validate(range(min = 0, message = "预估时间不能小于 0"))]
+    pub estimated_time: Option<i32>,
+    pub sort_order: i32,
+}
+
+/// 部分更新待办事项的参数（PATCH），只修改传入的字段
+///
+/// 可为空的字段使用 `Option<Option<T>>`：缺失表示不修改，null 表示清空。
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct PatchTodoParam {
+    #[validate(length(min = 1, max = 200, message = "标题长度必须在 1 到 200 之间"))]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub summary: Option<Option<Vec<String>>>,
+    pub status: Option<TodoStatus>,
+    pub priority: Option<TodoPriority>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub due_date: Option<Option<DateTimeWithTimeZone>>,
+    pub is_important: Option<bool>,
+    pub is_urgent: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    #[validate(length(max = 20, message = "标签数量不能超过 20 个"))]
+    pub tags: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    #[validate(range(min = 0, message = "预估时间不能小于 0"))]
+    pub estimated_time: Option<Option<i32>>,
+    pub sort_order: Option<i32>,
+}
+
+/// 移动子树时的参数，移动到顶层请使用 promote 接口
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct MoveTodoParam {
+    #[validate(range(min = 1, message = "父任务的 id 必须大于 0"))]
+    pub parent_id: i32,
+}
+
+/// 变更任务状态的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TransitionStatusParam {
+    pub status: TodoStatus,
+    /// 是否重新打开已完成或已取消的任务
+    #[serde(default)]
+    pub reopen: bool,
+}
+
+/// 四象限（艾森豪威尔矩阵）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quadrant {
+    /// 重要且紧急：立即去做
+    Do,
+    /// 重要不紧急：计划去做
+    Schedule,
+    /// 紧急不重要：委托他人
+    Delegate,
+    /// 不重要不紧急：舍弃
+    Eliminate,
+}
+
+impl Quadrant {
+    /// 按固定顺序列出全部象限
+    pub const ALL: [Quadrant; 4] = [
+        Quadrant::Do,
+        Quadrant::Schedule,
+        Quadrant::Delegate,
+        Quadrant::Eliminate,
+    ];
+
+    /// 根据重要、紧急标记计算所在象限
+    pub fn from_flags(is_important: bool, is_urgent: bool) -> Self {
+        match (is_important, is_urgent) {
+            (true, true) => Quadrant::Do,
+            (true, false) => Quadrant::Schedule,
+            (false, true) => Quadrant::Delegate,
+            (false, false) => Quadrant::Eliminate,
+        }
+    }
+
+    /// 象限对应的 (is_important, is_urgent) 标记
+    pub fn flags(&self) -> (bool, bool) {
+        match self {
+            Quadrant::Do => (true, true),
+            Quadrant::Schedule => (true, false),
+            Quadrant::Delegate => (false, true),
+            Quadrant::Eliminate => (false, false),
+        }
+    }
+}
+
+/// 批量拖动待办事项到指定象限的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct MoveQuadrantParam {
+    #[validate(length(min = 1, max = 200, message = "一次只能移动 1 到 200 个待办事项"))]
+    pub ids: Vec<i32>,
+    pub quadrant: Quadrant,
+}
+
+/// 重命名标签的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct RenameTagParam {
+    #[validate(length(min = 1, max = 50, message = "标签长度必须在 1 到 50 之间"))]
+    pub from: String,
+    #[validate(length(min = 1, max = 50, message = "标签长度必须在 1 到 50 之间"))]
+    pub to: String,
+}
+
+/// 合并标签的参数，把 source 合并到 target 中
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct MergeTagParam {
+    #[validate(length(min = 1, max = 50, message = "标签长度必须在 1 到 50 之间"))]
+    pub source: String,
+    #[validate(length(min = 1, max = 50, message = "标签长度必须在 1 到 50 之间"))]
+    pub target: String,
+}
+
+/// 删除标签时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TagPathParam {
+    #[validate(length(min = 1, max = 50, message = "标签长度必须在 1 到 50 之间"))]
+    pub tag: String,
+}
+
+/// 按标签筛选时的匹配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatchMode {
+    /// 包含任意一个标签
+    #[default]
+    Any,
+    /// 同时包含全部标签
+    All,
+}
+
+/// 列表查询的范围
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoScope {
+    /// 自己创建的和共享给自己的
+    #[default]
+    All,
+    /// 只查询自己创建的
+    Own,
+    /// 只查询其他用户共享给自己的
+    Shared,
+}
+
+/// 按标签筛选待办事项的查询参数，多个标签用英文逗号分隔
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TagFilterQuery {
+    #[validate(length(min = 1, max = 500, message = "标签参数长度必须在 1 到 500 之间"))]
+    pub tags: String,
+    #[serde(default)]
+    pub mode: TagMatchMode,
+}
+
+impl TagFilterQuery {
+    /// 拆分逗号分隔的标签，去掉空白和空项
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// 全文检索的查询参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct SearchTodoQuery {
+    #[validate(length(min = 1, max = 100, message = "搜索关键字长度必须在 1 到 100 之间"))]
+    pub q: String,
+    #[validate(range(min = 1, max = 100, message = "每次最多返回 100 条结果"))]
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// 待办事项列表支持的排序字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSortField {
+    Id,
+    #[default]
+    SortOrder,
+    CreatedAt,
+    UpdatedAt,
+    DueDate,
+    CompletedAt,
+    Priority,
+    Status,
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// 待办事项列表的筛选、排序与分页参数
+///
+/// 多值参数（status、priority、tags）使用英文逗号分隔，
+/// 分页使用上一页返回的 `next_cursor`，翻页时排序字段和方向必须保持不变。
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct TodoListQuery {
+    #[validate(length(max = 100, message = "状态参数过长"))]
+    pub status: Option<String>,
+    #[validate(length(max = 100, message = "优先级参数过长"))]
+    pub priority: Option<String>,
+    #[validate(length(max = 500, message = "标签参数长度不能超过 500"))]
+    pub tags: Option<String>,
+    pub tag_mode: Option<TagMatchMode>,
+    pub due_from: Option<DateTimeWithTimeZone>,
+    pub due_to: Option<DateTimeWithTimeZone>,
+    pub is_important: Option<bool>,
+    pub is_urgent: Option<bool>,
+    #[validate(range(min = 1, message = "父任务的 id 必须大于 0"))]
+    pub parent_id: Option<i32>,
+    /// 只查询顶层任务
+    pub top_level: Option<bool>,
+    /// 只查询该项目中的待办事项，项目已归档时也可以查询
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub project_id: Option<i32>,
+    /// 只查询不属于任何项目的待办事项（收件箱）
+    pub inbox: Option<bool>,
+    /// 是否包含已归档项目中的待办事项，默认不包含
+    pub include_archived: Option<bool>,
+    /// 查询范围，默认包括共享给自己的待办事项
+    pub scope: Option<TodoScope>,
+    #[validate(length(min = 1, max = 100, message = "搜索关键字长度必须在 1 到 100 之间"))]
+    pub q: Option<String>,
+    pub sort: Option<TodoSortField>,
+    pub order: Option<SortDirection>,
+    #[validate(length(max = 512, message = "分页游标无效"))]
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "每页数量必须在 1 到 100 之间"))]
+    pub limit: Option<u64>,
+}
+
+/// 移动待办事项所属项目的参数，project_id 为 null 时移出项目（回到收件箱）
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct MoveTodoProjectParam {
+    #[validate(range(min = 1, message = "项目的 id 必须大于 0"))]
+    pub project_id: Option<i32>,
+}
+
+/// 拖动排序的参数，把任务放到 prev_id 和 next_id 之间
+///
+/// 两者至少传一个，且必须与被移动的任务位于同一层级；
+/// 只传 prev_id 表示放在它后面，只传 next_id 表示放在它前面。
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct ReorderTodoParam {
+    #[validate(range(min = 1, message = "前一个任务的 id 必须大于 0"))]
+    pub prev_id: Option<i32>,
+    #[validate(range(min = 1, message = "后一个任务的 id 必须大于 0"))]
+    pub next_id: Option<i32>,
+}
+
+/// 按 id 操作计时记录时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TimeEntryIdParam {
+    #[validate(range(min = 1, message = "计时记录的 id 必须大于 0"))]
+    pub entry_id: i32,
+}
+
+/// 手动补录计时记录的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateTimeEntryParam {
+    pub started_at: DateTimeWithTimeZone,
+    pub ended_at: DateTimeWithTimeZone,
+    #[validate(length(max = 500, message = "备注最多 500 个字符"))]
+    pub note: Option<String>,
+}
+
+/// 修改计时记录的参数，只修改请求中出现的字段
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct PatchTimeEntryParam {
+    pub started_at: Option<DateTimeWithTimeZone>,
+    pub ended_at: Option<DateTimeWithTimeZone>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    #[validate(length(max = 500, message = "备注最多 500 个字符"))]
+    pub note: Option<Option<String>>,
+}
+
+/// 估时分析的分组维度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateGroupBy {
+    Tag,
+    #[default]
+    Priority,
+    Week,
+}
+
+/// 估时分析的查询参数，时间范围按完成时间筛选（左闭右开）
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct EstimateReportQuery {
+    pub group_by: Option<EstimateGroupBy>,
+    pub from: Option<DateTimeWithTimeZone>,
+    pub to: Option<DateTimeWithTimeZone>,
+}
+
+/// 设置重复规则的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct SetRecurrenceParam {
+    /// RFC 5545 RRULE，例如 `FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10`
+    #[validate(length(min = 1, max = 255, message = "重复规则长度必须在 1 到 255 之间"))]
+    pub rrule: String,
+}
+
+/// 设置提醒的参数，整体替换待办事项的全部提醒
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct SetRemindersParam {
+    /// 在截止时间之前多少分钟提醒，0 表示到期时提醒，最多提前 30 天
+    #[validate(length(max = 10, message = "每个待办事项最多设置 10 个提醒"))]
+    pub offsets: Vec<i32>,
+}
+
+/// 导入 iCalendar 的查询参数
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct IcsImportQuery {
+    /// 只生成导入报告，不写入数据
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 创建日历订阅的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateCalendarFeedParam {
+    #[validate(length(min = 1, max = 50, message = "订阅名称长度必须在 1 到 50 之间"))]
+    pub name: String,
+}
+
+/// 日历订阅 id 的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CalendarFeedIdParam {
+    #[validate(range(min = 1, message = "日历订阅的 id 必须大于 0"))]
+    pub feed_id: i32,
+}
+
+/// 日历订阅地址的路径参数，格式为 `{token}.ics`
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CalendarFeedFileParam {
+    #[validate(length(min = 5, max = 100, message = "订阅地址无效"))]
+    pub file: String,
+}
+
+/// 批量导入的查询参数
+#[derive(Debug, serde::Deserialize, Clone, Default, validator::Validate)]
+pub struct TodoImportQuery {
+    /// 只校验并生成导入报告，不写入数据
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 批量导入导出（CSV / NDJSON）中的一行待办事项
+///
+/// 使用外部 id 标识待办事项，导入时按外部 id 新增或更新，父任务也通过外部 id 引用。
+/// 导出时外部 id 就是待办事项的 UID，所以导出的文件可以原样导入到其他账号。
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, validator::Validate)]
+pub struct TodoTransferRow {
+    #[validate(length(min = 1, max = 255, message = "外部 id 长度必须在 1 到 255 之间"))]
+    pub external_id: String,
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "父任务的外部 id 长度必须在 1 到 255 之间"
+    ))]
+    pub parent_external_id: Option<String>,
+    #[validate(length(min = 1, max = 200, message = "标题长度必须在 1 到 200 之间"))]
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub summary: Vec<String>,
+    pub status: Option<TodoStatus>,
+    pub priority: Option<TodoPriority>,
+    pub due_date: Option<DateTimeWithTimeZone>,
+    pub completed_at: Option<DateTimeWithTimeZone>,
+    #[serde(default)]
+    pub is_important: bool,
+    #[serde(default)]
+    pub is_urgent: bool,
+    #[serde(default)]
+    #[validate(length(max = 20, message = "标签数量不能超过 20 个"))]
+    pub tags: Vec<String>,
+    #[validate(range(min = 0, message = "预估时间不能小于 0"))]
+    pub estimated_time: Option<i32>,
+    /// 实际用时由计时记录汇总得出，只用于导出，导入时忽略
+    #[validate(range(min = 0, message = "实际用时不能小于 0"))]
+    pub actual_time: Option<i32>,
+    /// 只在新增时使用，更新已有的待办事项时忽略
+    pub created_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 批量操作中的一项操作，按 `op` 区分类型
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    /// 变更状态，规则与单个变更状态相同
+    SetStatus {
+        status: TodoStatus,
+        #[serde(default)]
+        reopen: bool,
+    },
+    SetPriority {
+        priority: TodoPriority,
+    },
+    AddTags {
+        tags: Vec<String>,
+    },
+    RemoveTags {
+        tags: Vec<String>,
+    },
+    /// 移动到新的父任务下，parent_id 为 null 时提升为顶层任务
+    Move {
+        parent_id: Option<i32>,
+    },
+    /// 把待办事项及其子任务移入回收站，只能是最后一项操作
+    Delete,
+}
+
+/// 批量操作的参数，每个待办事项依次执行全部操作
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct BatchTodoParam {
+    #[validate(length(min = 1, message = "待办事项 id 不能为空"))]
+    pub ids: Vec<i32>,
+    #[validate(length(min = 1, max = 10, message = "操作数量必须在 1 到 10 之间"))]
+    pub operations: Vec<BatchOperation>,
+    /// 为 true 时任意一个待办事项失败都会回滚整个批次
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+/// 回收站列表的查询参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TrashQuery {
+    #[validate(range(min = 1, max = 100, message = "每页数量必须在 1 到 100 之间"))]
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// 修改历史的查询参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TodoHistoryQuery {
+    #[validate(range(min = 1, max = 100, message = "每页数量必须在 1 到 100 之间"))]
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// 回滚到指定版本的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct TodoRevisionParam {
+    #[validate(range(min = 1, message = "待办事项的 id 必须大于 0"))]
+    pub id: i32,
+    #[validate(range(min = 1, message = "版本号必须大于 0"))]
+    pub revision: i32,
+}
+
+/// 按 id 操作评论时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CommentIdParam {
+    #[validate(range(min = 1, message = "评论的 id 必须大于 0"))]
+    pub comment_id: i32,
+}
+
+/// 发表评论的参数，正文为 Markdown，parent_id 为回复的评论
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct CreateCommentParam {
+    #[validate(length(min = 1, max = 10000, message = "评论长度必须在 1 到 10000 之间"))]
+    pub body: String,
+    #[validate(range(min = 1, message = "回复的评论 id 必须大于 0"))]
+    pub parent_id: Option<i32>,
+}
+
+/// 编辑评论的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct UpdateCommentParam {
+    #[validate(length(min = 1, max = 10000, message = "评论长度必须在 1 到 10000 之间"))]
+    pub body: String,
+}
+
+/// 查询 @ 到自己的评论的参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct MentionQuery {
+    #[validate(range(min = 1, max = 100, message = "每页数量必须在 1 到 100 之间"))]
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// 按 id 操作附件时的路径参数
+#[derive(Debug, serde::Deserialize, Clone, validator::Validate)]
+pub struct AttachmentIdParam {
+    #[validate(range(min = 1, message = "附件的 id 必须大于 0"))]
+    pub attachment_id: i32,
+}
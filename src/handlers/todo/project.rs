@@ -0,0 +1,90 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoList;
+use crate::handlers::project::crud::find_writable_project;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{MoveTodoProjectParam, TodoIdParam};
+use crate::handlers::todo::tree::{
+    MAX_TODO_DEPTH, TodoTreeNode, load_user_subtree, lock_user_tree,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{ConnectionTrait, DbBackend, EntityTrait, Statement};
+
+/// 把以 `root_id` 为根的整棵子树（包括回收站中的子任务）放入 `project_id`，返回修改的行数
+pub async fn assign_subtree_project<C: ConnectionTrait>(
+    db: &C,
+    root_id: i32,
+    project_id: Option<i32>,
+) -> ApiResult<u64> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"WITH RECURSIVE subtree AS (
+                    SELECT id, 1 AS depth FROM todo_list WHERE id = $1
+                    UNION ALL
+                    SELECT c.id, s.depth + 1 FROM todo_list c
+                    JOIN subtree s ON c.parent_id = s.id
+                    WHERE s.depth <= $3
+                )
+                UPDATE todo_list t SET project_id = $2
+                FROM subtree s
+                WHERE t.id = s.id AND t.project_id IS DISTINCT FROM $2"#,
+            [
+                root_id.into(),
+                project_id.into(),
+                (MAX_TODO_DEPTH as i32).into(),
+            ],
+        ))
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// 子树移动到新的父任务下之后，跟随父任务所在的项目
+pub async fn follow_parent_project<C: ConnectionTrait>(
+    db: &C,
+    root_id: i32,
+    parent_id: i32,
+) -> ApiResult<u64> {
+    let parent = TodoList::find_by_id(parent_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("父任务不存在！")))?;
+    assign_subtree_project(db, root_id, parent.project_id).await
+}
+
+/// 把顶层待办事项连同子任务移动到另一个项目，子任务跟随父任务，不能单独移动
+///
+/// 项目属于待办事项的创建者，因此只有所有者可以移动。
+#[debug_handler]
+#[tracing::instrument(name = "move todo project", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn move_todo_project_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<MoveTodoProjectParam>,
+) -> ApiResult<ApiResponse<TodoTreeNode>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Owner).await?;
+    let user_id = todo.user_id;
+    lock_user_tree(&txn, user_id).await?;
+    if todo.parent_id.is_some() {
+        return Err(ApiError::Biz(String::from(
+            "子任务跟随父任务所在的项目，请移动顶层任务！",
+        )));
+    }
+    if let Some(project_id) = params.project_id {
+        find_writable_project(&txn, user_id, project_id).await?;
+    }
+    assign_subtree_project(&txn, todo.id, params.project_id).await?;
+    let tree = load_user_subtree(&txn, user_id, todo.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("移动成功！", Some(tree)))
+}
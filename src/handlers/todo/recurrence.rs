@@ -0,0 +1,365 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::recurrence::RecurrenceRule;
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::{TodoStatus, apply_status_transition};
+use crate::entities::prelude::{TodoList, TodoRecurrences};
+use crate::entities::{todo_list, todo_recurrences};
+use crate::handlers::project::crud::find_writable_project;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{SetRecurrenceParam, TodoIdParam};
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::tree::load_subtree;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::refresh_todo_index;
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, IntoActiveModel,
+    QueryFilter, QuerySelect, Set, Statement,
+};
+use std::collections::HashMap;
+
+/// 预览接下来多少次重复
+const PREVIEW_OCCURRENCES: usize = 10;
+
+/// 重复系列及接下来的重复时间
+#[derive(Debug, serde::Serialize)]
+pub struct RecurrenceInfo {
+    #[serde(flatten)]
+    pub recurrence: todo_recurrences::Model,
+    pub upcoming: Vec<DateTimeWithTimeZone>,
+}
+
+impl RecurrenceInfo {
+    fn new(recurrence: todo_recurrences::Model) -> ApiResult<Self> {
+        let rule: RecurrenceRule = recurrence.rrule.parse()?;
+        let upcoming = if recurrence.ended_at.is_some() {
+            vec![]
+        } else {
+            let now = get_local_datetime_with_timezone();
+            rule.occurrences(recurrence.dtstart)
+                .filter(|dt| *dt > now)
+                .take(PREVIEW_OCCURRENCES)
+                .collect()
+        };
+        Ok(Self {
+            recurrence,
+            upcoming,
+        })
+    }
+}
+
+/// 查询待办事项所属的重复系列并加锁
+async fn find_series<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<todo_recurrences::Model> {
+    let recurrence_id = todo
+        .recurrence_id
+        .ok_or_else(|| ApiError::Biz(String::from("该任务没有设置重复规则！")))?;
+    TodoRecurrences::find_by_id(recurrence_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("该任务没有设置重复规则！")))
+}
+
+/// 查询以 `todo` 为当前实例、且尚未结束的重复系列
+async fn find_active_series<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<todo_recurrences::Model> {
+    let series = find_series(db, todo).await?;
+    if series.ended_at.is_some() || series.current_todo_id != Some(todo.id) {
+        return Err(ApiError::Biz(String::from(
+            "该任务不是重复系列中正在进行的实例！",
+        )));
+    }
+    Ok(series)
+}
+
+/// 把 `root_id` 整棵子树的截止时间平移 `seconds` 秒
+async fn shift_subtree_due_dates<C: ConnectionTrait>(
+    db: &C,
+    root_id: i32,
+    seconds: i64,
+) -> ApiResult<()> {
+    let ids: Vec<i32> = load_subtree(db, root_id)
+        .await?
+        .into_iter()
+        .map(|todo| todo.id)
+        .collect();
+    TodoList::update_many()
+        .col_expr(
+            todo_list::Column::DueDate,
+            Expr::cust_with_values("due_date + make_interval(secs => $1)", [seconds as f64]),
+        )
+        .filter(todo_list::Column::Id.is_in(ids))
+        .filter(todo_list::Column::DueDate.is_not_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 下一次重复所在的项目：沿用原来的项目，项目已归档时放入收集箱
+async fn next_project_id<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<Option<i32>> {
+    let Some(project_id) = todo.project_id else {
+        return Ok(None);
+    };
+    match find_writable_project(db, todo.user_id, project_id).await {
+        Ok(project) => Ok(Some(project.id)),
+        Err(ApiError::Biz(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 以 `todo` 为模板生成下一次重复的实例，连同子任务一起复制
+///
+/// 复制标题、描述、标签、优先级、项目等字段，状态重置为 pending，
+/// 子任务的截止时间与根任务平移相同的时长。
+/// 项目已归档时新实例放入收集箱，原来是子任务的改为顶层任务，保证父子任务位于同一个项目。
+async fn clone_instance<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+    recurrence_id: i32,
+    next_due: DateTimeWithTimeZone,
+) -> ApiResult<todo_list::Model> {
+    let delta = next_due - todo.due_date.unwrap_or(next_due);
+    let project_id = next_project_id(db, todo).await?;
+    let detached = project_id != todo.project_id;
+    // load_subtree 按层级排序，父任务总是先于子任务插入
+    let rows = load_subtree(db, todo.id).await?;
+    let mut id_map: HashMap<i32, i32> = HashMap::new();
+    let mut root = None;
+    for row in rows {
+        let is_root = row.id == todo.id;
+        let parent_id = if is_root {
+            row.parent_id.filter(|_| !detached)
+        } else {
+            match row.parent_id.and_then(|p| id_map.get(&p)) {
+                Some(parent_id) => Some(*parent_id),
+                None => continue,
+            }
+        };
+        let sort_order = if is_root {
+            Some(append_rank(db, row.user_id, parent_id).await?)
+        } else {
+            row.sort_order
+        };
+        let copy = todo_list::ActiveModel {
+            user_id: Set(row.user_id),
+            title: Set(row.title),
+            description: Set(row.description),
+            summary: Set(row.summary),
+            status: Set(Some(TodoStatus::Pending.as_str().into())),
+            priority: Set(row.priority),
+            due_date: Set(if is_root {
+                Some(next_due)
+            } else {
+                row.due_date.map(|due| due + delta)
+            }),
+            is_important: Set(row.is_important),
+            is_urgent: Set(row.is_urgent),
+            tags: Set(row.tags),
+            estimated_time: Set(row.estimated_time),
+            parent_id: Set(parent_id),
+            sort_order: Set(sort_order),
+            recurrence_id: Set(is_root.then_some(recurrence_id)),
+            project_id: Set(project_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        refresh_todo_index(db, &copy).await?;
+        id_map.insert(row.id, copy.id);
+        if is_root {
+            root = Some(copy);
+        }
+    }
+    root.ok_or_else(|| ApiError::Biz(String::from("待办事项不存在！")))
+}
+
+/// 重复系列的实例完成后生成下一次重复，状态变更的各个入口在任务变为 completed 后调用
+///
+/// 只有系列中正在进行的实例才会触发，重新打开后再次完成不会重复生成；
+/// 规则已经没有下一次重复时结束整个系列。
+pub async fn spawn_next_occurrence<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<Option<todo_list::Model>> {
+    if todo.recurrence_id.is_none() {
+        return Ok(None);
+    }
+    let series = find_series(db, todo).await?;
+    if series.ended_at.is_some() || series.current_todo_id != Some(todo.id) {
+        return Ok(None);
+    }
+    let rule: RecurrenceRule = series.rrule.parse()?;
+    let base = todo.due_date.unwrap_or(series.dtstart);
+    let mut series = series.into_active_model();
+    let Some(next_due) = rule.next_after(*series.dtstart.as_ref(), base) else {
+        series.ended_at = Set(Some(get_local_datetime_with_timezone()));
+        series.update(db).await?;
+        tracing::info!("重复系列 {} 已没有下一次重复，系列结束", todo.id);
+        return Ok(None);
+    };
+    let next = clone_instance(db, todo, *series.id.as_ref(), next_due).await?;
+    // 下一次重复沿用同样的提醒设置
+    db.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"INSERT INTO todo_reminders (user_id, todo_id, offset_minutes)
+            SELECT user_id, $2, offset_minutes FROM todo_reminders WHERE todo_id = $1"#,
+        [todo.id.into(), next.id.into()],
+    ))
+    .await?;
+    series.current_todo_id = Set(Some(next.id));
+    series.update(db).await?;
+    tracing::info!("待办事项 {} 完成，生成下一次重复 {}", todo.id, next.id);
+    Ok(Some(next))
+}
+
+/// 查询待办事项的重复规则及接下来的重复时间
+#[debug_handler]
+pub async fn get_recurrence_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<RecurrenceInfo>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    let series = find_series(db_pool, &todo).await?;
+    Ok(ApiResponse::success(RecurrenceInfo::new(series)?))
+}
+
+/// 为待办事项设置重复规则，已有规则时替换为新规则并从当前截止时间重新开始
+#[debug_handler]
+#[tracing::instrument(name = "set recurrence", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn set_recurrence_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<SetRecurrenceParam>,
+) -> ApiResult<ApiResponse<RecurrenceInfo>> {
+    let user_id = principal.id as i32;
+    let rule: RecurrenceRule = params.rrule.parse()?;
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, user_id, path.id, TodoRole::Editor).await?;
+    let due_date = todo
+        .due_date
+        .ok_or_else(|| ApiError::Biz(String::from("设置重复规则前请先设置截止时间！")))?;
+    if TodoStatus::from_db(todo.status.as_deref()).is_terminal() {
+        return Err(ApiError::Biz(String::from(
+            "已完成或已取消的任务不能设置重复规则！",
+        )));
+    }
+    let existing = match todo.recurrence_id {
+        Some(_) => Some(find_series(&txn, &todo).await?)
+            .filter(|series| series.current_todo_id == Some(todo.id)),
+        None => None,
+    };
+    let series = match existing {
+        Some(series) => {
+            let mut series = series.into_active_model();
+            series.rrule = Set(rule.to_string());
+            series.dtstart = Set(due_date);
+            series.ended_at = Set(None);
+            series.update(&txn).await?
+        }
+        None => {
+            let series = todo_recurrences::ActiveModel {
+                user_id: Set(todo.user_id),
+                rrule: Set(rule.to_string()),
+                dtstart: Set(due_date),
+                current_todo_id: Set(Some(todo.id)),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            let mut todo = todo.into_active_model();
+            todo.recurrence_id = Set(Some(series.id));
+            todo.update(&txn).await?;
+            series
+        }
+    };
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "重复规则已设置！",
+        Some(RecurrenceInfo::new(series)?),
+    ))
+}
+
+/// 跳过本次重复：把当前实例（连同子任务）的截止时间顺延到下一次重复
+///
+/// 没有下一次重复时取消当前实例并结束整个系列。
+#[debug_handler]
+#[tracing::instrument(name = "skip occurrence", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn skip_occurrence_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, params.id, TodoRole::Editor).await?;
+    let series = find_active_series(&txn, &todo).await?;
+    let status = TodoStatus::from_db(todo.status.as_deref());
+    if status.is_terminal() {
+        return Err(ApiError::Biz(String::from("该任务已经结束，无法跳过！")));
+    }
+    let rule: RecurrenceRule = series.rrule.parse()?;
+    let base = todo.due_date.unwrap_or(series.dtstart);
+    let (todo, msg) = match rule.next_after(series.dtstart, base) {
+        Some(next_due) => {
+            shift_subtree_due_dates(&txn, todo.id, (next_due - base).num_seconds()).await?;
+            let todo =
+                authorize_todo(&txn, principal.id as i32, params.id, TodoRole::Editor).await?;
+            (todo, "已跳过本次重复！")
+        }
+        None => {
+            let mut series = series.into_active_model();
+            series.ended_at = Set(Some(get_local_datetime_with_timezone()));
+            series.update(&txn).await?;
+            let mut todo = todo.into_active_model();
+            apply_status_transition(&mut todo, status, TodoStatus::Cancelled, false)?;
+            (todo.update(&txn).await?, "已经是最后一次重复，系列已结束！")
+        }
+    };
+    txn.commit().await?;
+    Ok(ApiResponse::ok(msg, Some(todo)))
+}
+
+/// 结束重复系列，当前实例保留，完成后不再生成新的实例
+#[debug_handler]
+#[tracing::instrument(name = "end recurrence", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn end_recurrence_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<RecurrenceInfo>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, params.id, TodoRole::Editor).await?;
+    let series = find_series(&txn, &todo).await?;
+    if series.ended_at.is_some() {
+        return Err(ApiError::Biz(String::from("该重复系列已经结束！")));
+    }
+    let mut series = series.into_active_model();
+    series.ended_at = Set(Some(get_local_datetime_with_timezone()));
+    let series = series.update(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "重复系列已结束！",
+        Some(RecurrenceInfo::new(series)?),
+    ))
+}
@@ -0,0 +1,102 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoReminders;
+use crate::entities::todo_reminders;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::model::{SetRemindersParam, TodoIdParam};
+use crate::jobs::reminder::MAX_REMINDER_OFFSET_MINUTES;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+
+/// 查询待办事项的提醒，按提前时间从大到小排列
+async fn load_reminders<C: ConnectionTrait>(
+    db: &C,
+    todo_id: i32,
+) -> ApiResult<Vec<todo_reminders::Model>> {
+    Ok(TodoReminders::find()
+        .filter(todo_reminders::Column::TodoId.eq(todo_id))
+        .order_by_desc(todo_reminders::Column::OffsetMinutes)
+        .all(db)
+        .await?)
+}
+
+/// 查询待办事项的全部提醒
+#[debug_handler]
+pub async fn list_reminders_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<Vec<todo_reminders::Model>>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    Ok(ApiResponse::success(
+        load_reminders(db_pool, todo.id).await?,
+    ))
+}
+
+/// 设置待办事项的提醒，例如 `[1440, 15]` 表示提前 1 天和 15 分钟提醒
+///
+/// 保留的提醒不会丢失发送记录，已经发送过的提醒不会因为重新设置而再次发送。
+#[debug_handler]
+#[tracing::instrument(name = "set reminders", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn set_reminders_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<SetRemindersParam>,
+) -> ApiResult<ApiResponse<Vec<todo_reminders::Model>>> {
+    let user_id = principal.id as i32;
+    if params
+        .offsets
+        .iter()
+        .any(|offset| !(0..=MAX_REMINDER_OFFSET_MINUTES).contains(offset))
+    {
+        return Err(ApiError::Biz(format!(
+            "提醒时间必须在 0 到 {MAX_REMINDER_OFFSET_MINUTES} 分钟之间"
+        )));
+    }
+    let mut offsets = params.offsets;
+    offsets.sort_unstable();
+    offsets.dedup();
+    let txn = db_pool.begin().await?;
+    let todo = authorize_todo(&txn, user_id, path.id, TodoRole::Editor).await?;
+    TodoReminders::delete_many()
+        .filter(todo_reminders::Column::TodoId.eq(todo.id))
+        .filter(todo_reminders::Column::OffsetMinutes.is_not_in(offsets.clone()))
+        .exec(&txn)
+        .await?;
+    if !offsets.is_empty() {
+        TodoReminders::insert_many(
+            offsets
+                .into_iter()
+                .map(|offset| todo_reminders::ActiveModel {
+                    user_id: Set(user_id),
+                    todo_id: Set(todo.id),
+                    offset_minutes: Set(offset),
+                    ..Default::default()
+                }),
+        )
+        .on_conflict(
+            OnConflict::columns([
+                todo_reminders::Column::TodoId,
+                todo_reminders::Column::OffsetMinutes,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(&txn)
+        .await?;
+    }
+    let reminders = load_reminders(&txn, todo.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("提醒已设置！", Some(reminders)))
+}
@@ -0,0 +1,239 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::rank::{RANK_GAP, rank_between};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::{authorize_todo, find_owned_todo};
+use crate::handlers::todo::model::{ReorderTodoParam, TodoIdParam};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::{Expr, ExprTrait};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set, Statement, TransactionTrait,
+};
+
+/// 同级分组的筛选条件：同一个用户、同一个父任务（顶层任务的父任务为空），不包括回收站中的任务
+fn sibling_condition(user_id: i32, parent_id: Option<i32>) -> Condition {
+    let parent = match parent_id {
+        Some(parent_id) => todo_list::Column::ParentId.eq(parent_id),
+        None => todo_list::Column::ParentId.is_null(),
+    };
+    Condition::all()
+        .add(todo_list::Column::UserId.eq(user_id))
+        .add(todo_list::Column::DeletedAt.is_null())
+        .add(parent)
+}
+
+/// 对同级分组加事务级咨询锁，保证并发拖动时读到的相邻排序值不会被其他事务改掉
+pub async fn lock_sibling_group<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    parent_id: Option<i32>,
+) -> ApiResult<()> {
+    db.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1, $2)",
+        [user_id.into(), parent_id.unwrap_or(0).into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 按当前顺序（sort_order, id）对同级分组重新编号，间隔恢复为 `RANK_GAP`
+///
+/// 调用前需要先通过 `lock_sibling_group` 加锁。
+pub async fn rebalance_group<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    parent_id: Option<i32>,
+) -> ApiResult<u64> {
+    let result = db
+        .execute_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"UPDATE todo_list t SET sort_order = r.rn * $3
+                FROM (
+                    SELECT id, ROW_NUMBER() OVER (ORDER BY sort_order, id) AS rn
+                    FROM todo_list
+                    WHERE user_id = $1 AND parent_id IS NOT DISTINCT FROM $2
+                        AND deleted_at IS NULL
+                ) r
+                WHERE t.id = r.id AND t.sort_order IS DISTINCT FROM r.rn * $3"#,
+            [user_id.into(), parent_id.into(), RANK_GAP.into()],
+        ))
+        .await?;
+    tracing::info!(
+        "用户 {} 的分组 {:?} 已重新编号，更新了 {} 条数据",
+        user_id,
+        parent_id,
+        result.rows_affected()
+    );
+    Ok(result.rows_affected())
+}
+
+/// 计算追加到同级分组末尾时的排序值，必要时先重排分组
+pub async fn append_rank<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    parent_id: Option<i32>,
+) -> ApiResult<i32> {
+    lock_sibling_group(db, user_id, parent_id).await?;
+    let last = max_rank(db, user_id, parent_id).await?;
+    if let Some(rank) = rank_between(last, None) {
+        return Ok(rank);
+    }
+    rebalance_group(db, user_id, parent_id).await?;
+    let last = max_rank(db, user_id, parent_id).await?;
+    rank_between(last, None)
+        .ok_or_else(|| ApiError::Biz(String::from("同一层级的任务数量过多，无法继续添加！")))
+}
+
+/// 同级分组中最大的排序值
+async fn max_rank<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    parent_id: Option<i32>,
+) -> ApiResult<Option<i32>> {
+    let last: Option<Option<i32>> = TodoList::find()
+        .select_only()
+        .expr(Expr::col(todo_list::Column::SortOrder).max())
+        .filter(sibling_condition(user_id, parent_id))
+        .into_tuple()
+        .one(db)
+        .await?;
+    Ok(last.flatten())
+}
+
+/// 找出 `anchor` 相邻的同级任务的排序值（跳过被移动的任务自身）
+///
+/// - after 为 true：anchor 之后的第一个任务
+/// - after 为 false：anchor 之前的最后一个任务
+async fn neighbour_rank<C: ConnectionTrait>(
+    db: &C,
+    moving: &todo_list::Model,
+    anchor: &todo_list::Model,
+    after: bool,
+) -> ApiResult<Option<i32>> {
+    let rank = anchor.sort_order.unwrap_or(0);
+    let (order_condition, query) = if after {
+        (
+            Condition::any()
+                .add(todo_list::Column::SortOrder.gt(rank))
+                .add(
+                    Condition::all()
+                        .add(todo_list::Column::SortOrder.eq(rank))
+                        .add(todo_list::Column::Id.gt(anchor.id)),
+                ),
+            TodoList::find()
+                .order_by_asc(todo_list::Column::SortOrder)
+                .order_by_asc(todo_list::Column::Id),
+        )
+    } else {
+        (
+            Condition::any()
+                .add(todo_list::Column::SortOrder.lt(rank))
+                .add(
+                    Condition::all()
+                        .add(todo_list::Column::SortOrder.eq(rank))
+                        .add(todo_list::Column::Id.lt(anchor.id)),
+                ),
+            TodoList::find()
+                .order_by_desc(todo_list::Column::SortOrder)
+                .order_by_desc(todo_list::Column::Id),
+        )
+    };
+    let neighbour = query
+        .filter(sibling_condition(moving.user_id, moving.parent_id))
+        .filter(todo_list::Column::Id.ne(moving.id))
+        .filter(order_condition)
+        .one(db)
+        .await?;
+    Ok(neighbour.and_then(|todo| todo.sort_order))
+}
+
+/// 计算被移动任务的新排序值，返回 `None` 表示间隔已耗尽
+async fn target_rank<C: ConnectionTrait>(
+    db: &C,
+    moving: &todo_list::Model,
+    prev: Option<&todo_list::Model>,
+    next: Option<&todo_list::Model>,
+) -> ApiResult<Option<i32>> {
+    let (lower, upper) = match (prev, next) {
+        (Some(prev), Some(next)) => (prev.sort_order, next.sort_order),
+        (Some(prev), None) => (
+            prev.sort_order,
+            neighbour_rank(db, moving, prev, true).await?,
+        ),
+        (None, Some(next)) => (
+            neighbour_rank(db, moving, next, false).await?,
+            next.sort_order,
+        ),
+        (None, None) => return Err(ApiError::Biz(String::from("请指定目标位置！"))),
+    };
+    Ok(rank_between(lower, upper))
+}
+
+/// 加载相邻的锚点任务，必须与被移动的任务在同一层级
+async fn load_anchor<C: ConnectionTrait>(
+    db: &C,
+    moving: &todo_list::Model,
+    anchor_id: Option<i32>,
+) -> ApiResult<Option<todo_list::Model>> {
+    let Some(anchor_id) = anchor_id else {
+        return Ok(None);
+    };
+    if anchor_id == moving.id {
+        return Err(ApiError::Biz(String::from("不能以任务自身作为参照位置！")));
+    }
+    let anchor = find_owned_todo(db, moving.user_id, anchor_id).await?;
+    if anchor.parent_id != moving.parent_id {
+        return Err(ApiError::Biz(String::from("只能在同一层级内拖动排序！")));
+    }
+    Ok(Some(anchor))
+}
+
+/// 拖动排序：把任务放到同一层级的两个任务之间
+///
+/// 一般情况下只修改被移动任务自身的 sort_order；
+/// 相邻间隔耗尽时先对该层级重新编号，再重新计算位置。
+#[debug_handler]
+#[tracing::instrument(name = "reorder todo", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn reorder_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<ReorderTodoParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let txn = db_pool.begin().await?;
+    let moving = authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Editor).await?;
+    // 同一层级的任务都属于被移动任务的创建者
+    let user_id = moving.user_id;
+    lock_sibling_group(&txn, user_id, moving.parent_id).await?;
+    // 加锁之后再读取相邻任务，保证排序值是最新的
+    let mut prev = load_anchor(&txn, &moving, params.prev_id).await?;
+    let mut next = load_anchor(&txn, &moving, params.next_id).await?;
+    let rank = match target_rank(&txn, &moving, prev.as_ref(), next.as_ref()).await? {
+        Some(rank) => rank,
+        None => {
+            rebalance_group(&txn, user_id, moving.parent_id).await?;
+            prev = load_anchor(&txn, &moving, params.prev_id).await?;
+            next = load_anchor(&txn, &moving, params.next_id).await?;
+            target_rank(&txn, &moving, prev.as_ref(), next.as_ref())
+                .await?
+                .ok_or_else(|| ApiError::Biz(String::from("目标位置无效，请刷新后重试！")))?
+        }
+    };
+    // 重新编号后被移动的任务的 sort_order 也可能变化，这里重新读取
+    let mut todo = find_owned_todo(&txn, user_id, path.id)
+        .await?
+        .into_active_model();
+    todo.sort_order = Set(Some(rank));
+    let todo = todo.update(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("排序成功！", Some(todo)))
+}
@@ -0,0 +1,130 @@
+use crate::common::valid::ValidQuery;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::project::crud::visible_project_condition;
+use crate::handlers::todo::access::{accessible_todo_condition, editable_todo_condition};
+use crate::handlers::todo::model::SearchTodoQuery;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::refresh_todo_index;
+use crate::search::segment::{highlight, segment, to_tsquery_literal};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, TransactionTrait,
+};
+
+/// 命中字段的高亮片段
+#[derive(Debug, serde::Serialize)]
+pub struct SearchHighlight {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub summary: Vec<String>,
+}
+
+/// 单条检索结果
+#[derive(Debug, serde::Serialize)]
+pub struct SearchHit {
+    pub todo: todo_list::Model,
+    pub rank: f32,
+    pub highlight: SearchHighlight,
+}
+
+/// 重建索引的结果
+#[derive(Debug, serde::Serialize)]
+pub struct ReindexResult {
+    pub indexed: usize,
+}
+
+/// 在当前用户可以访问的待办事项（包括共享的）中全文检索标题、描述和摘要
+///
+/// 关键字先用 jieba 分词，所有词都命中才会返回，按 `ts_rank_cd` 相关度倒序排列。
+#[debug_handler]
+#[tracing::instrument(name = "search todo", skip_all, fields(user_id = %principal.id, q = %params.q))]
+pub async fn search_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<SearchTodoQuery>,
+) -> ApiResult<ApiResponse<Vec<SearchHit>>> {
+    let tokens = segment(&params.q);
+    if tokens.is_empty() {
+        return Err(ApiError::ValidationError(String::from(
+            "搜索关键字中没有可以检索的内容",
+        )));
+    }
+    let query = to_tsquery_literal(&tokens);
+    let rank = Expr::cust_with_values(
+        "ts_rank_cd(todo_list.search_vector, CAST($1 AS TSQUERY))",
+        [query.clone()],
+    );
+    let statement = TodoList::find()
+        .column_as(rank.clone(), "rank")
+        .filter(accessible_todo_condition(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(visible_project_condition())
+        .filter(Expr::cust_with_values(
+            "todo_list.search_vector @@ CAST($1 AS TSQUERY)",
+            [query],
+        ))
+        .order_by_desc(rank)
+        .order_by_desc(todo_list::Column::Id)
+        .limit(params.limit.unwrap_or(20))
+        .offset(params.offset.unwrap_or(0))
+        .build(DbBackend::Postgres);
+    let rows = db_pool.query_all_raw(statement).await?;
+    let mut hits = Vec::with_capacity(rows.len());
+    for row in rows {
+        let todo = todo_list::Model::from_query_result(&row, "")?;
+        let rank: f32 = row.try_get("", "rank")?;
+        let highlight = SearchHighlight {
+            title: highlight(&todo.title, &tokens),
+            description: todo
+                .description
+                .as_deref()
+                .and_then(|description| highlight(description, &tokens)),
+            summary: todo
+                .summary
+                .iter()
+                .flatten()
+                .filter_map(|line| highlight(line, &tokens))
+                .collect(),
+        };
+        hits.push(SearchHit {
+            todo,
+            rank,
+            highlight,
+        });
+    }
+    Ok(ApiResponse::success(hits))
+}
+
+/// 重建当前用户可以编辑的全部待办事项（包括以编辑者身份共享的）的全文检索索引，用于历史数据或分词词典更新之后
+///
+/// 重建会写入 search_vector，只有查看权限的待办事项不在范围内。
+#[debug_handler]
+#[tracing::instrument(name = "reindex todo", skip_all, fields(user_id = %principal.id))]
+pub async fn reindex_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<ReindexResult>> {
+    let todos = TodoList::find()
+        .filter(editable_todo_condition(principal.id as i32))
+        .all(db_pool)
+        .await?;
+    let txn = db_pool.begin().await?;
+    for todo in &todos {
+        refresh_todo_index(&txn, todo).await?;
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "索引重建完成！",
+        Some(ReindexResult {
+            indexed: todos.len(),
+        }),
+    ))
+}
@@ -0,0 +1,58 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::domain::todo_status::{TodoStatus, apply_status_transition};
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{TodoIdParam, TransitionStatusParam};
+use crate::handlers::todo::recurrence::spawn_next_occurrence;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QuerySelect,
+};
+
+/// 变更待办事项状态
+///
+/// 状态流转由 `TodoStatus::check_transition` 校验，非法流转返回 `ApiError::IllegalStatusTransition`，
+/// 并同步维护 completed_at。读取时对行加排他锁，避免并发变更互相覆盖。
+#[debug_handler]
+#[tracing::instrument(name = "transition todo status", skip_all, fields(user_id = %principal.id, id = %path.id, to = %params.status))]
+pub async fn transition_status_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<TransitionStatusParam>,
+) -> ApiResult<ApiResponse<todo_list::Model>> {
+    let txn = history.begin(db_pool).await?;
+    authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Editor).await?;
+    let model = TodoList::find_by_id(path.id)
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待办事项不存在或无权访问！")))?;
+    let current_status = TodoStatus::from_db(model.status.as_deref());
+    let mut todo = model.into_active_model();
+    apply_status_transition(&mut todo, current_status, params.status, params.reopen)?;
+    let todo = todo.update(&txn).await?;
+    // 重复任务完成后生成下一次重复
+    if params.status == TodoStatus::Completed {
+        spawn_next_occurrence(&txn, &todo).await?;
+    }
+    txn.commit().await?;
+    tracing::info!(
+        "待办事项 {} 的状态由 {} 变更为 {}",
+        todo.id,
+        current_status,
+        params.status
+    );
+    Ok(ApiResponse::ok("状态已更新！", Some(todo)))
+}
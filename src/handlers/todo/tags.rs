@@ -0,0 +1,208 @@
+use crate::common::valid::{ValidJson, ValidPath, ValidQuery};
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::accessible_todo_condition;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{
+    MergeTagParam, RenameTagParam, TagFilterQuery, TagMatchMode, TagPathParam,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::sea_query::extension::postgres::PgBinOper;
+use sea_orm::sea_query::{Expr, ExprTrait};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::collections::HashMap;
+
+/// 标签及其使用次数
+#[derive(Debug, serde::Serialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// 标签批量操作的结果
+#[derive(Debug, serde::Serialize)]
+pub struct TagChangeResult {
+    pub affected: u64,
+}
+
+/// 构造标签数组的筛选条件，可以直接用于 `QueryFilter::filter`
+///
+/// - any: `tags && $1`，命中任意一个标签
+/// - all: `tags @> $1`，包含全部标签
+///
+/// 两个操作符都可以使用 `idx_todo_tags` GIN 索引。
+pub fn tags_condition(tags: Vec<String>, mode: TagMatchMode) -> Expr {
+    let op = match mode {
+        TagMatchMode::Any => PgBinOper::Overlap,
+        TagMatchMode::All => PgBinOper::Contains,
+    };
+    Expr::col(todo_list::Column::Tags).binary(op, Expr::val(tags))
+}
+
+/// 在一个事务中把当前用户自己的待办事项（不包括回收站中的）里的 `from` 标签替换为 `to`，并去重保持原有顺序
+///
+/// 标签的批量修改只作用于自己创建的待办事项，不会改动其他用户共享过来的待办事项。
+async fn replace_tag<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    from: &str,
+    to: &str,
+) -> ApiResult<u64> {
+    let result = TodoList::update_many()
+        .col_expr(
+            todo_list::Column::Tags,
+            Expr::cust_with_values(
+                r#"ARRAY(
+                    SELECT t FROM unnest(array_replace(todo_list.tags, $1, $2)) WITH ORDINALITY AS u(t, ord)
+                    GROUP BY t ORDER BY MIN(ord)
+                )"#,
+                [from, to],
+            ),
+        )
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(tags_condition(vec![from.to_string()], TagMatchMode::All))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// 查询当前用户可以访问的待办事项（包括共享的）中的全部标签及使用次数，按使用次数倒序
+#[debug_handler]
+pub async fn list_tags_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Vec<TagUsage>>> {
+    let rows: Vec<Option<Vec<String>>> = TodoList::find()
+        .select_only()
+        .column(todo_list::Column::Tags)
+        .filter(accessible_todo_condition(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(todo_list::Column::Tags.is_not_null())
+        .into_tuple()
+        .all(db_pool)
+        .await?;
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for tag in rows.into_iter().flatten().flatten() {
+        *counts.entry(tag).or_default() += 1;
+    }
+    let mut tags: Vec<TagUsage> = counts
+        .into_iter()
+        .map(|(tag, count)| TagUsage { tag, count })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(ApiResponse::success(tags))
+}
+
+/// 重命名标签，目标标签已存在时请使用合并接口
+#[debug_handler]
+#[tracing::instrument(name = "rename tag", skip_all, fields(user_id = %principal.id, from = %params.from, to = %params.to))]
+pub async fn rename_tag_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidJson(params): ValidJson<RenameTagParam>,
+) -> ApiResult<ApiResponse<TagChangeResult>> {
+    let user_id = principal.id as i32;
+    if params.from == params.to {
+        return Err(ApiError::Biz(String::from("新旧标签名称相同！")));
+    }
+    let txn = history.begin(db_pool).await?;
+    let target_exists = TodoList::find()
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(tags_condition(vec![params.to.clone()], TagMatchMode::Any))
+        .one(&txn)
+        .await?
+        .is_some();
+    if target_exists {
+        return Err(ApiError::Biz(format!(
+            "标签 {} 已存在，请使用合并功能！",
+            params.to
+        )));
+    }
+    let affected = replace_tag(&txn, user_id, &params.from, &params.to).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "重命名成功！",
+        Some(TagChangeResult { affected }),
+    ))
+}
+
+/// 合并标签，把 source 标签合并到 target 标签中，同一个待办事项中的重复标签会被去重
+#[debug_handler]
+#[tracing::instrument(name = "merge tag", skip_all, fields(user_id = %principal.id, source = %params.source, target = %params.target))]
+pub async fn merge_tag_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidJson(params): ValidJson<MergeTagParam>,
+) -> ApiResult<ApiResponse<TagChangeResult>> {
+    if params.source == params.target {
+        return Err(ApiError::Biz(String::from("不能把标签合并到自己！")));
+    }
+    let txn = history.begin(db_pool).await?;
+    let affected = replace_tag(&txn, principal.id as i32, &params.source, &params.target).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "合并成功！",
+        Some(TagChangeResult { affected }),
+    ))
+}
+
+/// 从当前用户自己的全部待办事项（不包括回收站中的）中删除某个标签
+#[debug_handler]
+#[tracing::instrument(name = "delete tag", skip_all, fields(user_id = %principal.id, tag = %params.tag))]
+pub async fn delete_tag_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TagPathParam>,
+) -> ApiResult<ApiResponse<TagChangeResult>> {
+    let txn = history.begin(db_pool).await?;
+    let result = TodoList::update_many()
+        .col_expr(
+            todo_list::Column::Tags,
+            Expr::cust_with_values("array_remove(todo_list.tags, $1)", [params.tag.clone()]),
+        )
+        .filter(todo_list::Column::UserId.eq(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(tags_condition(vec![params.tag], TagMatchMode::All))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok(
+        "删除成功！",
+        Some(TagChangeResult {
+            affected: result.rows_affected,
+        }),
+    ))
+}
+
+/// 按标签筛选当前用户可以访问的待办事项（包括共享的），mode 为 any 时命中任意标签，为 all 时需包含全部标签
+#[debug_handler]
+pub async fn filter_by_tags_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<TagFilterQuery>,
+) -> ApiResult<ApiResponse<Vec<todo_list::Model>>> {
+    let tags = params.tag_list();
+    if tags.is_empty() {
+        return Err(ApiError::ValidationError(String::from("至少需要一个标签")));
+    }
+    let todos = TodoList::find()
+        .filter(accessible_todo_condition(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(tags_condition(tags, params.mode))
+        .order_by_asc(todo_list::Column::SortOrder)
+        .order_by_asc(todo_list::Column::Id)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(todos))
+}
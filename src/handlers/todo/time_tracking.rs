@@ -0,0 +1,372 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TimeEntries;
+use crate::entities::time_entries;
+use crate::handlers::todo::access::authorize_todo;
+use crate::handlers::todo::model::{
+    CreateTimeEntryParam, PatchTimeEntryParam, TimeEntryIdParam, TodoIdParam,
+};
+use crate::handlers::todo::tree::load_ancestor_ids;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, IntoActiveModel,
+    ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, Statement, TransactionTrait,
+};
+
+/// 计时器记录的来源
+const SOURCE_TIMER: &str = "timer";
+/// 手动补录的来源
+const SOURCE_MANUAL: &str = "manual";
+
+/// 当前计时器的状态
+#[derive(Debug, serde::Serialize)]
+pub struct CurrentTimer {
+    pub entry: time_entries::Model,
+    /// true 表示正在计时，false 表示已暂停
+    pub running: bool,
+    /// 当前这段记录已经累计的秒数
+    pub elapsed_seconds: i64,
+}
+
+/// 重新汇总 `todo_id` 及其全部祖先的 actual_time
+///
+/// 每个任务的 actual_time = 自身已结束的计时记录总时长（分钟，四舍五入）+ 直接子任务的 actual_time，
+/// 从自身开始逐层向上计算，因此子任务的时间会一直累加到顶层任务。
+pub async fn rollup_actual_time<C: ConnectionTrait>(db: &C, todo_id: i32) -> ApiResult<()> {
+    for id in load_ancestor_ids(db, todo_id).await? {
+        db.execute_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"UPDATE todo_list t SET actual_time = (
+                    SELECT COALESCE(ROUND(SUM(EXTRACT(EPOCH FROM (e.ended_at - e.started_at))) / 60), 0)::INTEGER
+                    FROM time_entries e
+                    WHERE e.todo_id = t.id AND e.ended_at IS NOT NULL
+                ) + (
+                    SELECT COALESCE(SUM(c.actual_time), 0)::INTEGER
+                    FROM todo_list c
+                    WHERE c.parent_id = t.id AND c.deleted_at IS NULL
+                )
+                WHERE t.id = $1"#,
+            [id.into()],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// 查询当前用户正在计时的记录
+async fn find_running_entry<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> ApiResult<Option<time_entries::Model>> {
+    Ok(TimeEntries::find()
+        .filter(time_entries::Column::UserId.eq(user_id))
+        .filter(time_entries::Column::EndedAt.is_null())
+        .lock_exclusive()
+        .one(db)
+        .await?)
+}
+
+/// 查询当前用户最近一次暂停的记录
+async fn find_paused_entry<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> ApiResult<Option<time_entries::Model>> {
+    Ok(TimeEntries::find()
+        .filter(time_entries::Column::UserId.eq(user_id))
+        .filter(time_entries::Column::Paused.eq(true))
+        .order_by_desc(time_entries::Column::EndedAt)
+        .lock_exclusive()
+        .one(db)
+        .await?)
+}
+
+/// 清除当前用户所有的暂停标记，开始新的计时或停止计时后暂停的记录不能再继续
+async fn clear_paused<C: ConnectionTrait>(db: &C, user_id: i32) -> ApiResult<()> {
+    TimeEntries::update_many()
+        .col_expr(time_entries::Column::Paused, Expr::value(false))
+        .filter(time_entries::Column::UserId.eq(user_id))
+        .filter(time_entries::Column::Paused.eq(true))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 查询属于当前用户的计时记录
+async fn find_user_entry<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    entry_id: i32,
+) -> ApiResult<time_entries::Model> {
+    TimeEntries::find_by_id(entry_id)
+        .filter(time_entries::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("计时记录不存在或无权访问！")))
+}
+
+/// 结束正在计时的记录并汇总用时
+async fn close_entry<C: ConnectionTrait>(
+    db: &C,
+    entry: time_entries::Model,
+    paused: bool,
+) -> ApiResult<time_entries::Model> {
+    let mut active = entry.into_active_model();
+    active.ended_at = Set(Some(get_local_datetime_with_timezone()));
+    active.paused = Set(paused);
+    let entry = active.update(db).await?;
+    rollup_actual_time(db, entry.todo_id).await?;
+    Ok(entry)
+}
+
+/// 校验计时区间：结束时间不能早于开始时间，也不能晚于当前时间
+fn check_entry_range(
+    started_at: DateTimeWithTimeZone,
+    ended_at: Option<DateTimeWithTimeZone>,
+) -> ApiResult<()> {
+    let now = get_local_datetime_with_timezone();
+    if started_at > now || ended_at.is_some_and(|ended_at| ended_at > now) {
+        return Err(ApiError::Biz(String::from("计时时间不能晚于当前时间！")));
+    }
+    if ended_at.is_some_and(|ended_at| ended_at < started_at) {
+        return Err(ApiError::Biz(String::from("结束时间不能早于开始时间！")));
+    }
+    Ok(())
+}
+
+/// 查询当前计时器，没有正在计时或暂停的记录时返回空
+#[debug_handler]
+pub async fn current_timer_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Option<CurrentTimer>>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let current = match find_running_entry(&txn, user_id).await? {
+        Some(entry) => Some((entry, true)),
+        None => find_paused_entry(&txn, user_id)
+            .await?
+            .map(|entry| (entry, false)),
+    };
+    txn.commit().await?;
+    let timer = current.map(|(entry, running)| {
+        let ended_at = entry
+            .ended_at
+            .unwrap_or_else(get_local_datetime_with_timezone);
+        CurrentTimer {
+            elapsed_seconds: (ended_at - entry.started_at).num_seconds(),
+            entry,
+            running,
+        }
+    });
+    Ok(ApiResponse::success(timer))
+}
+
+/// 为待办事项开始计时，每个用户同一时间只能有一个正在计时的任务
+#[debug_handler]
+#[tracing::instrument(name = "start timer", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn start_timer_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    authorize_todo(&txn, user_id, params.id, TodoRole::Editor).await?;
+    if find_running_entry(&txn, user_id).await?.is_some() {
+        return Err(ApiError::Biz(String::from(
+            "已有正在计时的任务，请先暂停或停止！",
+        )));
+    }
+    clear_paused(&txn, user_id).await?;
+    // 并发开始计时由唯一索引 uq_time_entries_running 兜底
+    let entry = time_entries::ActiveModel {
+        user_id: Set(user_id),
+        todo_id: Set(params.id),
+        started_at: Set(get_local_datetime_with_timezone()),
+        source: Set(SOURCE_TIMER.into()),
+        paused: Set(false),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("开始计时！", Some(entry)))
+}
+
+/// 暂停当前计时，暂停后可以继续计时
+#[debug_handler]
+#[tracing::instrument(name = "pause timer", skip_all, fields(user_id = %principal.id))]
+pub async fn pause_timer_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let entry = find_running_entry(&txn, user_id)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("当前没有正在计时的任务！")))?;
+    let entry = close_entry(&txn, entry, true).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("已暂停计时！", Some(entry)))
+}
+
+/// 继续最近一次暂停的计时，会为同一个任务新开一段计时记录
+#[debug_handler]
+#[tracing::instrument(name = "resume timer", skip_all, fields(user_id = %principal.id))]
+pub async fn resume_timer_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    if find_running_entry(&txn, user_id).await?.is_some() {
+        return Err(ApiError::Biz(String::from("已有正在计时的任务！")));
+    }
+    let paused = find_paused_entry(&txn, user_id)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("没有可以继续的计时！")))?;
+    clear_paused(&txn, user_id).await?;
+    let entry = time_entries::ActiveModel {
+        user_id: Set(user_id),
+        todo_id: Set(paused.todo_id),
+        started_at: Set(get_local_datetime_with_timezone()),
+        source: Set(SOURCE_TIMER.into()),
+        paused: Set(false),
+        note: Set(paused.note),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("继续计时！", Some(entry)))
+}
+
+/// 停止计时，正在计时的记录会被结束，暂停中的计时不能再继续
+#[debug_handler]
+#[tracing::instrument(name = "stop timer", skip_all, fields(user_id = %principal.id))]
+pub async fn stop_timer_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let user_id = principal.id as i32;
+    let txn = db_pool.begin().await?;
+    let entry = match find_running_entry(&txn, user_id).await? {
+        Some(entry) => close_entry(&txn, entry, false).await?,
+        None => {
+            let mut paused = find_paused_entry(&txn, user_id)
+                .await?
+                .ok_or_else(|| ApiError::Biz(String::from("当前没有正在计时的任务！")))?;
+            paused.paused = false;
+            paused
+        }
+    };
+    clear_paused(&txn, user_id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("已停止计时！", Some(entry)))
+}
+
+/// 查询待办事项的全部计时记录
+#[debug_handler]
+pub async fn list_time_entries_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<Vec<time_entries::Model>>> {
+    let todo = authorize_todo(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    let entries = todo
+        .find_related(TimeEntries)
+        .order_by_desc(time_entries::Column::StartedAt)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(entries))
+}
+
+/// 手动补录一段计时记录
+#[debug_handler]
+#[tracing::instrument(name = "create time entry", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn create_time_entry_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<CreateTimeEntryParam>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let user_id = principal.id as i32;
+    check_entry_range(params.started_at, Some(params.ended_at))?;
+    let txn = db_pool.begin().await?;
+    authorize_todo(&txn, user_id, path.id, TodoRole::Editor).await?;
+    let entry = time_entries::ActiveModel {
+        user_id: Set(user_id),
+        todo_id: Set(path.id),
+        started_at: Set(params.started_at),
+        ended_at: Set(Some(params.ended_at)),
+        source: Set(SOURCE_MANUAL.into()),
+        paused: Set(false),
+        note: Set(params.note),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    rollup_actual_time(&txn, entry.todo_id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("补录成功！", Some(entry)))
+}
+
+/// 修改计时记录的时间或备注
+#[debug_handler]
+#[tracing::instrument(name = "patch time entry", skip_all, fields(user_id = %principal.id, entry_id = %path.entry_id))]
+pub async fn patch_time_entry_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(path): ValidPath<TimeEntryIdParam>,
+    ValidJson(params): ValidJson<PatchTimeEntryParam>,
+) -> ApiResult<ApiResponse<time_entries::Model>> {
+    let txn = db_pool.begin().await?;
+    let model = find_user_entry(&txn, principal.id as i32, path.entry_id).await?;
+    if model.ended_at.is_none() && params.ended_at.is_some() {
+        return Err(ApiError::Biz(String::from(
+            "正在计时的记录请通过暂停或停止结束！",
+        )));
+    }
+    let started_at = params.started_at.unwrap_or(model.started_at);
+    let ended_at = params.ended_at.or(model.ended_at);
+    check_entry_range(started_at, ended_at)?;
+    let mut entry = model.clone().into_active_model();
+    entry.started_at = Set(started_at);
+    entry.ended_at = Set(ended_at);
+    if let Some(note) = params.note {
+        entry.note = Set(note);
+    }
+    if !entry.is_changed() {
+        return Ok(ApiResponse::ok("没有需要更新的内容", Some(model)));
+    }
+    let entry = entry.update(&txn).await?;
+    rollup_actual_time(&txn, entry.todo_id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("更新成功！", Some(entry)))
+}
+
+/// 删除计时记录
+#[debug_handler]
+#[tracing::instrument(name = "delete time entry", skip_all, fields(user_id = %principal.id, entry_id = %params.entry_id))]
+pub async fn delete_time_entry_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TimeEntryIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let txn = db_pool.begin().await?;
+    let entry = find_user_entry(&txn, principal.id as i32, params.entry_id).await?;
+    let todo_id = entry.todo_id;
+    entry.delete(&txn).await?;
+    rollup_actual_time(&txn, todo_id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success_with_msg("删除成功！"))
+}
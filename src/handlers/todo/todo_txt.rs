@@ -0,0 +1,154 @@
+use crate::common::valid::ValidQuery;
+use crate::domain::todo_status::TodoStatus;
+use crate::domain::todo_txt::{TodoTxtTask, parse_todo_txt};
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{TodoImportQuery, TodoTransferRow};
+use crate::handlers::todo::transfer::{ParsedRow, TodoImportReport, export_rows, import_rows};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::east8;
+use axum::Extension;
+use axum::body::Body;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDate;
+use futures_util::StreamExt;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+
+/// 东八区的日期
+fn local_date(datetime: DateTimeWithTimeZone) -> NaiveDate {
+    datetime.with_timezone(&east8().unwrap()).date_naive()
+}
+
+/// 日期转换为东八区当天零点；与原来的时间是同一天时保留原来的时间
+fn date_to_datetime(
+    date: Option<NaiveDate>,
+    current: Option<DateTimeWithTimeZone>,
+) -> Option<DateTimeWithTimeZone> {
+    let date = date?;
+    if let Some(current) = current.filter(|c| local_date(*c) == date) {
+        return Some(current);
+    }
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(east8().unwrap())
+        .single()
+}
+
+impl From<TodoTransferRow> for TodoTxtTask {
+    fn from(row: TodoTransferRow) -> Self {
+        Self {
+            status: row.status.unwrap_or(TodoStatus::Pending),
+            priority: row.priority,
+            completion_date: row.completed_at.map(local_date),
+            creation_date: row.created_at.map(local_date),
+            title: row.title,
+            tags: row.tags,
+            due: row.due_date.map(local_date),
+            uid: Some(row.external_id),
+        }
+    }
+}
+
+/// 转换为导入行，todo.txt 中没有的字段（描述、摘要、重要紧急、用时）沿用已有待办事项的值
+fn to_transfer_row(task: TodoTxtTask, existing: Option<&todo_list::Model>) -> TodoTransferRow {
+    TodoTransferRow {
+        external_id: task.uid.unwrap_or_else(|| xid::new().to_string()),
+        parent_external_id: None,
+        title: task.title,
+        description: existing.and_then(|t| t.description.clone()),
+        summary: existing.and_then(|t| t.summary.clone()).unwrap_or_default(),
+        status: Some(task.status),
+        priority: task.priority,
+        due_date: date_to_datetime(task.due, existing.and_then(|t| t.due_date)),
+        completed_at: date_to_datetime(task.completion_date, existing.and_then(|t| t.completed_at)),
+        is_important: existing.and_then(|t| t.is_important).unwrap_or(false),
+        is_urgent: existing.and_then(|t| t.is_urgent).unwrap_or(false),
+        tags: task.tags,
+        estimated_time: existing.and_then(|t| t.estimated_time),
+        actual_time: existing.and_then(|t| t.actual_time),
+        created_at: date_to_datetime(task.creation_date, None),
+    }
+}
+
+/// 查询文件中带 uid 的已有待办事项
+async fn load_existing<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    tasks: &[(u64, Result<TodoTxtTask, String>)],
+) -> ApiResult<HashMap<String, todo_list::Model>> {
+    let uids: Vec<&str> = tasks
+        .iter()
+        .filter_map(|(_, task)| task.as_ref().ok()?.uid.as_deref())
+        .collect();
+    if uids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(TodoList::find()
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::IcalUid.is_in(uids))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|t| (t.ical_uid.clone(), t))
+        .collect())
+}
+
+/// 导出为 todo.txt，每个待办事项一行，描述和摘要不会导出
+#[debug_handler]
+#[tracing::instrument(name = "export todo.txt", skip_all, fields(user_id = %principal.id))]
+pub async fn export_todo_txt_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<Response> {
+    let rows = export_rows(db_pool, principal.id as i32).await?;
+    let body = rows.map(|row| Ok::<_, std::io::Error>(format!("{}\n", TodoTxtTask::from(row?))));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"todo.txt\"",
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// 导入 todo.txt（请求体为文件内容），带 `uid:` 的行更新已有的待办事项，其余的新增
+///
+/// 与 CSV 导入一样在一个事务中完成，任意一行无效时不会写入任何数据。
+#[debug_handler]
+#[tracing::instrument(name = "import todo.txt", skip_all, fields(user_id = %principal.id))]
+pub async fn import_todo_txt_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidQuery(params): ValidQuery<TodoImportQuery>,
+    body: String,
+) -> ApiResult<ApiResponse<TodoImportReport>> {
+    let user_id = principal.id as i32;
+    let tasks = parse_todo_txt(&body);
+    let existing = load_existing(db_pool, user_id, &tasks).await?;
+    let rows: Vec<ParsedRow> = tasks
+        .into_iter()
+        .map(|(line, task)| {
+            let row = task
+                .map(|task| {
+                    let current = task.uid.as_ref().and_then(|uid| existing.get(uid));
+                    to_transfer_row(task, current)
+                })
+                .map_err(|e| (None, e));
+            (line, row)
+        })
+        .collect();
+    import_rows(db_pool, &history, user_id, rows, params.dry_run).await
+}
@@ -0,0 +1,525 @@
+use crate::common::valid::ValidQuery;
+use crate::domain::todo_status::{TodoStatus, completed_at_for};
+use crate::entities::todo_list;
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::ical::{find_by_uid, link_parent};
+use crate::handlers::todo::model::{TodoImportQuery, TodoPriority, TodoTransferRow};
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::tree::lock_user_tree;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::refresh_todo_index;
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::body::Body;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DbBackend, FromQueryResult, IntoActiveModel, Set, Statement,
+    TryIntoModel,
+};
+use std::collections::HashMap;
+use validator::Validate;
+
+/// 单次导入的最大行数
+const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// CSV 的表头，与 CsvTodoRow 的字段顺序一致
+const CSV_COLUMNS: [&str; 15] = [
+    "external_id",
+    "parent_external_id",
+    "title",
+    "description",
+    "summary",
+    "status",
+    "priority",
+    "due_date",
+    "completed_at",
+    "is_important",
+    "is_urgent",
+    "tags",
+    "estimated_time",
+    "actual_time",
+    "created_at",
+];
+
+/// 导出时查询的一行数据，带上父任务的 UID
+#[derive(Debug, FromQueryResult)]
+struct TodoExportRow {
+    ical_uid: String,
+    parent_uid: Option<String>,
+    title: String,
+    description: Option<String>,
+    summary: Option<Vec<String>>,
+    status: Option<String>,
+    priority: Option<String>,
+    due_date: Option<DateTimeWithTimeZone>,
+    completed_at: Option<DateTimeWithTimeZone>,
+    is_important: Option<bool>,
+    is_urgent: Option<bool>,
+    tags: Option<Vec<String>>,
+    estimated_time: Option<i32>,
+    actual_time: Option<i32>,
+    created_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<TodoExportRow> for TodoTransferRow {
+    fn from(row: TodoExportRow) -> Self {
+        Self {
+            external_id: row.ical_uid,
+            parent_external_id: row.parent_uid,
+            title: row.title,
+            description: row.description,
+            summary: row.summary.unwrap_or_default(),
+            status: Some(TodoStatus::from_db(row.status.as_deref())),
+            priority: row.priority.and_then(|p| p.parse().ok()),
+            due_date: row.due_date,
+            completed_at: row.completed_at,
+            is_important: row.is_important.unwrap_or(false),
+            is_urgent: row.is_urgent.unwrap_or(false),
+            tags: row.tags.unwrap_or_default(),
+            estimated_time: row.estimated_time,
+            actual_time: row.actual_time,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// CSV 中的一行，数组字段（summary、tags）以 JSON 数组的形式放在单元格中
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CsvTodoRow {
+    external_id: String,
+    parent_external_id: Option<String>,
+    title: String,
+    description: Option<String>,
+    summary: Option<String>,
+    status: Option<TodoStatus>,
+    priority: Option<TodoPriority>,
+    due_date: Option<DateTimeWithTimeZone>,
+    completed_at: Option<DateTimeWithTimeZone>,
+    is_important: Option<bool>,
+    is_urgent: Option<bool>,
+    tags: Option<String>,
+    estimated_time: Option<i32>,
+    actual_time: Option<i32>,
+    created_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 数组写成 JSON，空数组写成空单元格
+fn array_to_cell(values: &[String]) -> Option<String> {
+    (!values.is_empty()).then(|| serde_json::to_string(values).unwrap_or_default())
+}
+
+/// 解析 JSON 数组单元格
+fn cell_to_array(field: &str, cell: Option<String>) -> Result<Vec<String>, String> {
+    match cell.as_deref().map(str::trim) {
+        None | Some("") => Ok(vec![]),
+        Some(cell) => serde_json::from_str(cell)
+            .map_err(|_| format!("{field} 必须是 JSON 字符串数组，例如 [\"a\",\"b\"]")),
+    }
+}
+
+impl CsvTodoRow {
+    fn from_transfer(row: TodoTransferRow) -> Self {
+        Self {
+            summary: array_to_cell(&row.summary),
+            tags: array_to_cell(&row.tags),
+            external_id: row.external_id,
+            parent_external_id: row.parent_external_id,
+            title: row.title,
+            description: row.description,
+            status: row.status,
+            priority: row.priority,
+            due_date: row.due_date,
+            completed_at: row.completed_at,
+            is_important: Some(row.is_important),
+            is_urgent: Some(row.is_urgent),
+            estimated_time: row.estimated_time,
+            actual_time: row.actual_time,
+            created_at: row.created_at,
+        }
+    }
+
+    fn into_transfer(self) -> Result<TodoTransferRow, String> {
+        Ok(TodoTransferRow {
+            summary: cell_to_array("summary", self.summary)?,
+            tags: cell_to_array("tags", self.tags)?,
+            external_id: self.external_id,
+            parent_external_id: self.parent_external_id,
+            title: self.title,
+            description: self.description,
+            status: self.status,
+            priority: self.priority,
+            due_date: self.due_date,
+            completed_at: self.completed_at,
+            is_important: self.is_important.unwrap_or(false),
+            is_urgent: self.is_urgent.unwrap_or(false),
+            estimated_time: self.estimated_time,
+            actual_time: self.actual_time,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// 按 id 顺序流式读取当前用户的全部待办事项，不会一次性加载到内存
+pub async fn export_rows(
+    db: &'static sea_orm::DatabaseConnection,
+    user_id: i32,
+) -> ApiResult<impl Stream<Item = Result<TodoTransferRow, std::io::Error>> + Send + 'static> {
+    let stream = TodoExportRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT t.ical_uid, p.ical_uid AS parent_uid, t.title, t.description, t.summary,
+                  t.status, t.priority, t.due_date, t.completed_at, t.is_important, t.is_urgent,
+                  t.tags, t.estimated_time, t.actual_time, t.created_at
+           FROM todo_list t
+           LEFT JOIN todo_list p ON p.id = t.parent_id
+           WHERE t.user_id = $1 AND t.deleted_at IS NULL
+           ORDER BY t.id"#,
+        [user_id.into()],
+    ))
+    .stream(db)
+    .await?;
+    Ok(stream
+        .map_ok(TodoTransferRow::from)
+        .map_err(std::io::Error::other))
+}
+
+/// 下载文件的响应头
+fn attachment(content_type: &'static str, filename: &'static str, body: Body) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// 把一行写成 CSV 文本
+fn write_csv_row(row: CsvTodoRow) -> Result<Vec<u8>, std::io::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+    writer.serialize(row).map_err(std::io::Error::other)?;
+    writer.into_inner().map_err(|e| e.into_error())
+}
+
+/// 导出为 CSV，数组字段以 JSON 数组的形式放在单元格中
+#[debug_handler]
+#[tracing::instrument(name = "export csv", skip_all, fields(user_id = %principal.id))]
+pub async fn export_csv_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<Response> {
+    let rows = export_rows(db_pool, principal.id as i32).await?;
+    let header = format!("{}\r\n", CSV_COLUMNS.join(",")).into_bytes();
+    let body = stream::once(async move { Ok(header) })
+        .chain(rows.map(|row| write_csv_row(CsvTodoRow::from_transfer(row?))));
+    Ok(attachment(
+        "text/csv; charset=utf-8",
+        "attachment; filename=\"todos.csv\"",
+        Body::from_stream(body),
+    ))
+}
+
+/// 导出为 NDJSON，每行一个 JSON 对象
+#[debug_handler]
+#[tracing::instrument(name = "export ndjson", skip_all, fields(user_id = %principal.id))]
+pub async fn export_ndjson_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<Response> {
+    let rows = export_rows(db_pool, principal.id as i32).await?;
+    let body = rows.map(|row| {
+        let mut line = serde_json::to_vec(&row?)?;
+        line.push(b'\n');
+        Ok::<_, std::io::Error>(line)
+    });
+    Ok(attachment(
+        "application/x-ndjson; charset=utf-8",
+        "attachment; filename=\"todos.ndjson\"",
+        Body::from_stream(body),
+    ))
+}
+
+/// 单行的导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoImportAction {
+    Created,
+    Updated,
+    Failed,
+}
+
+/// 单行的导入明细，line 是数据在文件中的行号（CSV 的表头是第 1 行）
+#[derive(Debug, serde::Serialize)]
+pub struct TodoImportItem {
+    pub line: u64,
+    pub external_id: Option<String>,
+    pub action: TodoImportAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 导入报告，只要有一行失败，整个导入都不会写入
+#[derive(Debug, serde::Serialize)]
+pub struct TodoImportReport {
+    pub dry_run: bool,
+    pub committed: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub items: Vec<TodoImportItem>,
+}
+
+/// 解析后的一行：行号和解析结果，解析失败时尽量带上外部 id
+pub type ParsedRow = (u64, Result<TodoTransferRow, (Option<String>, String)>);
+
+/// 解析 CSV，按表头匹配列，列的顺序不限
+fn parse_csv(body: &str) -> ApiResult<Vec<ParsedRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(body.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ApiError::Biz(format!("无法解析 CSV 表头：{e}")))?
+        .clone();
+    for required in ["external_id", "title"] {
+        if !headers.iter().any(|h| h == required) {
+            return Err(ApiError::Biz(format!("CSV 缺少 {required} 列")));
+        }
+    }
+    let external_id_index = headers.iter().position(|h| h == "external_id");
+    let mut rows = vec![];
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| ApiError::Biz(format!("无法解析 CSV：{e}")))?;
+        // 表头是第 1 行
+        let line = index as u64 + 2;
+        let external_id = external_id_index
+            .and_then(|i| record.get(i))
+            .filter(|id| !id.is_empty())
+            .map(String::from);
+        let row = record
+            .deserialize::<CsvTodoRow>(Some(&headers))
+            .map_err(|e| e.to_string())
+            .and_then(CsvTodoRow::into_transfer)
+            .map_err(|e| (external_id, e));
+        rows.push((line, row));
+    }
+    Ok(rows)
+}
+
+/// 解析 NDJSON，跳过空行
+fn parse_ndjson(body: &str) -> Vec<ParsedRow> {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let row = serde_json::from_str::<TodoTransferRow>(line).map_err(|e| {
+                let external_id = serde_json::from_str::<serde_json::Value>(line)
+                    .ok()
+                    .and_then(|v| v.get("external_id")?.as_str().map(String::from));
+                (external_id, e.to_string())
+            });
+            (index as u64 + 1, row)
+        })
+        .collect()
+}
+
+/// 按外部 id 新增或更新一条待办事项，父子关系在全部导入后再处理
+///
+/// 实际用时由计时记录汇总得出，导入的 actual_time 会被忽略。
+async fn upsert_row<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    row: &TodoTransferRow,
+) -> ApiResult<(TodoImportAction, todo_list::Model)> {
+    let existing = find_by_uid(db, user_id, &row.external_id).await?;
+    let current_status = existing
+        .as_ref()
+        .map(|t| TodoStatus::from_db(t.status.as_deref()));
+    let status = row.status.or(current_status).unwrap_or(TodoStatus::Pending);
+    // 状态来自外部数据，直接写入，不经过状态机校验
+    let completed_at = match status {
+        TodoStatus::Completed => row
+            .completed_at
+            .or(existing.as_ref().and_then(|t| t.completed_at))
+            .or_else(|| completed_at_for(status)),
+        _ => None,
+    };
+    let (action, mut todo) = match existing {
+        Some(model) => (TodoImportAction::Updated, model.into_active_model()),
+        None => (
+            TodoImportAction::Created,
+            todo_list::ActiveModel {
+                user_id: Set(user_id),
+                ical_uid: Set(row.external_id.clone()),
+                sort_order: Set(Some(append_rank(db, user_id, None).await?)),
+                priority: Set(Some(TodoPriority::Medium.as_str().into())),
+                ..Default::default()
+            },
+        ),
+    };
+    todo.title = Set(row.title.clone());
+    todo.description = Set(row.description.clone());
+    todo.summary = Set((!row.summary.is_empty()).then(|| row.summary.clone()));
+    todo.status = Set(Some(status.as_str().into()));
+    todo.completed_at = Set(completed_at);
+    if let Some(priority) = row.priority {
+        todo.priority = Set(Some(priority.as_str().into()));
+    }
+    todo.due_date = Set(row.due_date);
+    todo.is_important = Set(Some(row.is_important));
+    todo.is_urgent = Set(Some(row.is_urgent));
+    todo.tags = Set((!row.tags.is_empty()).then(|| row.tags.clone()));
+    todo.estimated_time = Set(row.estimated_time);
+    // 创建时间只在新增时使用，保留原系统中的创建时间
+    if action == TodoImportAction::Created && row.created_at.is_some() {
+        todo.created_at = Set(row.created_at);
+    }
+    let todo = todo.save(db).await?.try_into_model()?;
+    refresh_todo_index(db, &todo).await?;
+    Ok((action, todo))
+}
+
+/// 在一个事务中导入全部行，任意一行失败或 dry_run 时回滚
+///
+/// 没有父任务外部 id 的行保留原来的父任务，父任务外部 id 可以指向文件中的其他行或已有的待办事项。
+pub async fn import_rows(
+    db_pool: &sea_orm::DatabaseConnection,
+    history: &HistoryContext,
+    user_id: i32,
+    rows: Vec<ParsedRow>,
+    dry_run: bool,
+) -> ApiResult<ApiResponse<TodoImportReport>> {
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(ApiError::Biz(format!("单次最多导入 {MAX_IMPORT_ROWS} 行")));
+    }
+    let mut items = Vec::with_capacity(rows.len());
+    let mut valid = vec![];
+    let mut lines: HashMap<String, u64> = HashMap::new();
+    for (line, row) in rows {
+        let checked = row.and_then(|row| match row.validate() {
+            Ok(()) => match lines.insert(row.external_id.clone(), line) {
+                Some(first) => Err((
+                    Some(row.external_id),
+                    format!("外部 id 与第 {first} 行重复"),
+                )),
+                None => Ok(row),
+            },
+            Err(e) => Err((Some(row.external_id), e.to_string())),
+        });
+        match checked {
+            Ok(row) => {
+                valid.push((items.len(), row));
+                items.push(TodoImportItem {
+                    line,
+                    external_id: None,
+                    action: TodoImportAction::Failed,
+                    todo_id: None,
+                    message: None,
+                });
+            }
+            Err((external_id, message)) => items.push(TodoImportItem {
+                line,
+                external_id,
+                action: TodoImportAction::Failed,
+                todo_id: None,
+                message: Some(message),
+            }),
+        }
+    }
+    let txn = history.begin(db_pool).await?;
+    lock_user_tree(&txn, user_id).await?;
+    let mut imported: HashMap<String, i32> = HashMap::new();
+    let mut parents = vec![];
+    for (index, row) in valid {
+        let (action, todo) = upsert_row(&txn, user_id, &row).await?;
+        imported.insert(row.external_id.clone(), todo.id);
+        if let Some(parent_external_id) = row.parent_external_id {
+            parents.push((index, todo.id, parent_external_id));
+        }
+        let item = &mut items[index];
+        item.external_id = Some(row.external_id);
+        item.action = action;
+        item.todo_id = Some(todo.id);
+    }
+    for (index, todo_id, parent_external_id) in parents {
+        if let Some(message) =
+            link_parent(&txn, user_id, todo_id, &parent_external_id, &imported).await?
+        {
+            let item = &mut items[index];
+            item.action = TodoImportAction::Failed;
+            item.message = Some(message);
+        }
+    }
+    let count = |action| items.iter().filter(|i| i.action == action).count();
+    let failed = count(TodoImportAction::Failed);
+    let committed = failed == 0 && !dry_run;
+    let report = TodoImportReport {
+        dry_run,
+        committed,
+        created: count(TodoImportAction::Created),
+        updated: count(TodoImportAction::Updated),
+        failed,
+        items,
+    };
+    if !committed {
+        txn.rollback().await?;
+    } else {
+        txn.commit().await?;
+        tracing::info!(
+            "用户 {} 批量导入：新增 {}，更新 {}",
+            user_id,
+            report.created,
+            report.updated
+        );
+    }
+    Ok(if failed > 0 {
+        ApiResponse::new(
+            -1,
+            format!("有 {failed} 行数据无效，没有写入任何数据！"),
+            Some(report),
+        )
+    } else if dry_run {
+        ApiResponse::ok("预检完成！", Some(report))
+    } else {
+        ApiResponse::ok("导入完成！", Some(report))
+    })
+}
+
+/// 导入 CSV（请求体为 CSV 文本，第一行是表头），按外部 id 新增或更新待办事项
+#[debug_handler]
+#[tracing::instrument(name = "import csv", skip_all, fields(user_id = %principal.id))]
+pub async fn import_csv_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidQuery(params): ValidQuery<TodoImportQuery>,
+    body: String,
+) -> ApiResult<ApiResponse<TodoImportReport>> {
+    let rows = parse_csv(&body)?;
+    import_rows(db_pool, &history, principal.id as i32, rows, params.dry_run).await
+}
+
+/// 导入 NDJSON（每行一个 JSON 对象），按外部 id 新增或更新待办事项
+#[debug_handler]
+#[tracing::instrument(name = "import ndjson", skip_all, fields(user_id = %principal.id))]
+pub async fn import_ndjson_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidQuery(params): ValidQuery<TodoImportQuery>,
+    body: String,
+) -> ApiResult<ApiResponse<TodoImportReport>> {
+    let rows = parse_ndjson(&body);
+    import_rows(db_pool, &history, principal.id as i32, rows, params.dry_run).await
+}
@@ -0,0 +1,319 @@
+use crate::common::valid::{ValidPath, ValidQuery};
+use crate::conf::get_app_config;
+use crate::entities::prelude::{TimeEntries, TodoList};
+use crate::entities::{time_entries, todo_list};
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{TodoIdParam, TrashQuery};
+use crate::handlers::todo::project::follow_parent_project;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::time_tracking::rollup_actual_time;
+use crate::handlers::todo::tree::{
+    MAX_TODO_DEPTH, TodoTreeNode, build_tree, ensure_child_depth, load_subtree, load_user_subtree,
+    lock_user_tree,
+};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait, ModelTrait, QueryFilter,
+    Statement,
+};
+use std::collections::HashSet;
+
+/// 回收站中的一项，子任务随父任务一起显示，不单独列出
+#[derive(Debug, serde::Serialize)]
+pub struct TrashItem {
+    pub todo: todo_list::Model,
+    /// 超过该时间后会被彻底删除
+    pub purge_at: Option<DateTimeWithTimeZone>,
+}
+
+/// 彻底删除的结果
+#[derive(Debug, serde::Serialize)]
+pub struct PurgeResult {
+    pub deleted: u64,
+}
+
+/// 把待办事项及其全部子任务移入回收站，返回移入的 id
+///
+/// 整棵子树使用同一个删除时间，恢复时据此找回一起删除的子任务；
+/// 子树中正在计时的记录会先结束，子树的用时不再计入父任务。
+pub async fn soft_delete_todo<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<Vec<i32>> {
+    let ids: Vec<i32> = load_subtree(db, todo.id)
+        .await?
+        .iter()
+        .map(|t| t.id)
+        .collect();
+    let now = get_local_datetime_with_timezone();
+    let running = TimeEntries::find()
+        .filter(time_entries::Column::TodoId.is_in(ids.iter().copied()))
+        .filter(time_entries::Column::EndedAt.is_null())
+        .all(db)
+        .await?;
+    if !running.is_empty() {
+        TimeEntries::update_many()
+            .col_expr(time_entries::Column::EndedAt, Expr::value(now))
+            .filter(time_entries::Column::Id.is_in(running.iter().map(|e| e.id)))
+            .exec(db)
+            .await?;
+        for entry in &running {
+            rollup_actual_time(db, entry.todo_id).await?;
+        }
+    }
+    // 暂停的计时不能再继续
+    TimeEntries::update_many()
+        .col_expr(time_entries::Column::Paused, Expr::value(false))
+        .filter(time_entries::Column::TodoId.is_in(ids.iter().copied()))
+        .filter(time_entries::Column::Paused.eq(true))
+        .exec(db)
+        .await?;
+    TodoList::update_many()
+        .col_expr(todo_list::Column::DeletedAt, Expr::value(now))
+        .filter(todo_list::Column::Id.is_in(ids.iter().copied()))
+        .exec(db)
+        .await?;
+    if let Some(parent_id) = todo.parent_id {
+        rollup_actual_time(db, parent_id).await?;
+    }
+    Ok(ids)
+}
+
+/// 查询回收站中属于当前用户的待办事项
+async fn find_trashed_todo<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    id: i32,
+) -> ApiResult<todo_list::Model> {
+    TodoList::find_by_id(id)
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::DeletedAt.is_not_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("回收站中没有该待办事项！")))
+}
+
+/// 查出与 `root` 一起被删除的整棵子树（删除时间相同），之前单独删除的子任务不包括在内
+async fn load_trashed_subtree<C: ConnectionTrait>(
+    db: &C,
+    root: &todo_list::Model,
+) -> ApiResult<Vec<todo_list::Model>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"WITH RECURSIVE subtree AS (
+                SELECT t.*, 1 AS depth FROM todo_list t WHERE t.id = $1
+                UNION ALL
+                SELECT c.*, s.depth + 1 FROM todo_list c
+                JOIN subtree s ON c.parent_id = s.id
+                WHERE s.depth <= $3 AND c.deleted_at = $2
+            )
+            SELECT * FROM subtree ORDER BY depth, sort_order, id"#,
+        [
+            root.id.into(),
+            root.deleted_at.into(),
+            (MAX_TODO_DEPTH as i32).into(),
+        ],
+    );
+    Ok(TodoList::find().from_raw_sql(stmt).all(db).await?)
+}
+
+/// 恢复前处理 UID 和资源名冲突：删除之后又导入或同步了相同 UID 的待办事项时，
+/// 为恢复的待办事项重新生成 UID，并清除 CalDAV 资源名
+async fn resolve_uid_conflicts<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    rows: &[todo_list::Model],
+) -> ApiResult<()> {
+    let uids = rows.iter().map(|t| t.ical_uid.as_str());
+    let names = rows.iter().filter_map(|t| t.caldav_name.as_deref());
+    let active = TodoList::find()
+        .filter(todo_list::Column::UserId.eq(user_id))
+        .filter(todo_list::Column::DeletedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(todo_list::Column::IcalUid.is_in(uids))
+                .add(todo_list::Column::CaldavName.is_in(names)),
+        )
+        .all(db)
+        .await?;
+    if active.is_empty() {
+        return Ok(());
+    }
+    let taken_uids: HashSet<&str> = active.iter().map(|t| t.ical_uid.as_str()).collect();
+    let taken_names: HashSet<&str> = active
+        .iter()
+        .filter_map(|t| t.caldav_name.as_deref())
+        .collect();
+    let conflicts: Vec<i32> = rows
+        .iter()
+        .filter(|t| {
+            taken_uids.contains(t.ical_uid.as_str())
+                || t.caldav_name
+                    .as_deref()
+                    .is_some_and(|name| taken_names.contains(name))
+        })
+        .map(|t| t.id)
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    TodoList::update_many()
+        .col_expr(
+            todo_list::Column::IcalUid,
+            Expr::cust("gen_random_uuid()::TEXT"),
+        )
+        .col_expr(
+            todo_list::Column::CaldavName,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(todo_list::Column::Id.is_in(conflicts))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 查询回收站，按删除时间倒序，只列出每次删除的顶层任务
+#[debug_handler]
+pub async fn list_trash_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidQuery(params): ValidQuery<TrashQuery>,
+) -> ApiResult<ApiResponse<Vec<TrashItem>>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT t.* FROM todo_list t
+            LEFT JOIN todo_list p ON p.id = t.parent_id
+            WHERE t.user_id = $1 AND t.deleted_at IS NOT NULL
+                AND (p.id IS NULL OR p.deleted_at IS DISTINCT FROM t.deleted_at)
+            ORDER BY t.deleted_at DESC, t.id DESC
+            LIMIT $2 OFFSET $3"#,
+        [
+            (principal.id as i32).into(),
+            (params.limit.unwrap_or(20) as i64).into(),
+            (params.offset.unwrap_or(0) as i64).into(),
+        ],
+    );
+    let retention = chrono::Duration::days(i64::from(get_app_config().trash().retention_days()));
+    let items = TodoList::find()
+        .from_raw_sql(stmt)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(|todo| TrashItem {
+            purge_at: todo.deleted_at.map(|deleted_at| deleted_at + retention),
+            todo,
+        })
+        .collect();
+    Ok(ApiResponse::success(items))
+}
+
+/// 从回收站恢复待办事项及与它一起删除的子任务，恢复后放到原父任务的末尾
+///
+/// 父任务仍在回收站中时不能单独恢复，需要先恢复父任务。
+#[debug_handler]
+#[tracing::instrument(name = "restore todo", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn restore_trash_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<TodoTreeNode>> {
+    let user_id = principal.id as i32;
+    let txn = history.begin(db_pool).await?;
+    lock_user_tree(&txn, user_id).await?;
+    let root = find_trashed_todo(&txn, user_id, params.id).await?;
+    let rows = load_trashed_subtree(&txn, &root).await?;
+    let ids: Vec<i32> = rows.iter().map(|t| t.id).collect();
+    resolve_uid_conflicts(&txn, user_id, &rows).await?;
+    let height = build_tree(root.id, rows)
+        .ok_or_else(|| ApiError::Biz(String::from("回收站中没有该待办事项！")))?
+        .height();
+    if let Some(parent_id) = root.parent_id {
+        let parent = TodoList::find_by_id(parent_id)
+            .one(&txn)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        if parent.deleted_at.is_some() {
+            return Err(ApiError::Biz(String::from(
+                "父任务仍在回收站中，请先恢复父任务！",
+            )));
+        }
+        // 删除之后父任务可能被移动到了更深的层级
+        ensure_child_depth(&txn, parent_id, height).await?;
+    }
+    let rank = append_rank(&txn, user_id, root.parent_id).await?;
+    TodoList::update_many()
+        .col_expr(
+            todo_list::Column::DeletedAt,
+            Expr::value(Option::<DateTimeWithTimeZone>::None),
+        )
+        .filter(todo_list::Column::Id.is_in(ids))
+        .exec(&txn)
+        .await?;
+    TodoList::update_many()
+        .col_expr(todo_list::Column::SortOrder, Expr::value(rank))
+        .filter(todo_list::Column::Id.eq(root.id))
+        .exec(&txn)
+        .await?;
+    if let Some(parent_id) = root.parent_id {
+        // 删除之后父任务可能被移动到了其他项目
+        follow_parent_project(&txn, root.id, parent_id).await?;
+        rollup_actual_time(&txn, parent_id).await?;
+    }
+    let tree = load_user_subtree(&txn, user_id, root.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("恢复成功！", Some(tree)))
+}
+
+/// 彻底删除回收站中的待办事项，子任务随外键级联删除，修改历史会保留
+#[debug_handler]
+#[tracing::instrument(name = "purge todo", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn purge_trash_item_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = find_trashed_todo(&txn, principal.id as i32, params.id).await?;
+    todo.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success_with_msg("已彻底删除！"))
+}
+
+/// 清空回收站
+#[debug_handler]
+#[tracing::instrument(name = "empty trash", skip_all, fields(user_id = %principal.id))]
+pub async fn empty_trash_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+) -> ApiResult<ApiResponse<PurgeResult>> {
+    let txn = history.begin(db_pool).await?;
+    let result = TodoList::delete_many()
+        .filter(todo_list::Column::UserId.eq(principal.id as i32))
+        .filter(todo_list::Column::DeletedAt.is_not_null())
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    tracing::info!(
+        "ID为: {} 的用户清空了回收站，删除 {} 条",
+        principal.id,
+        result.rows_affected
+    );
+    Ok(ApiResponse::ok(
+        "回收站已清空！",
+        Some(PurgeResult {
+            deleted: result.rows_affected,
+        }),
+    ))
+}
@@ -0,0 +1,244 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::handlers::todo::access::{authorize_subtree, authorize_todo};
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::model::{MoveTodoParam, TodoIdParam};
+use crate::handlers::todo::project::follow_parent_project;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::time_tracking::rollup_actual_time;
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DbBackend, EntityTrait, IntoActiveModel, Set, Statement,
+};
+use std::collections::HashMap;
+
+/// 子任务树允许的最大层级（顶层任务为第 1 层）
+pub const MAX_TODO_DEPTH: usize = 8;
+
+/// 带有子任务的待办事项节点
+#[derive(Debug, serde::Serialize)]
+pub struct TodoTreeNode {
+    #[serde(flatten)]
+    pub todo: todo_list::Model,
+    pub children: Vec<TodoTreeNode>,
+}
+
+impl TodoTreeNode {
+    /// 以当前节点为根的子树高度，叶子节点为 1
+    pub fn height(&self) -> usize {
+        1 + self.children.iter().map(|c| c.height()).max().unwrap_or(0)
+    }
+}
+
+/// 通过递归 CTE 查出以 `root_id` 为根的整棵子树（包含根节点本身），不包括回收站中的任务
+///
+/// 递归层数额外限制在 `MAX_TODO_DEPTH + 1` 以内，避免脏数据中存在环时无限递归。
+pub async fn load_subtree<C: ConnectionTrait>(
+    db: &C,
+    root_id: i32,
+) -> ApiResult<Vec<todo_list::Model>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"WITH RECURSIVE subtree AS (
+                SELECT t.*, 1 AS depth FROM todo_list t
+                WHERE t.id = $1 AND t.deleted_at IS NULL
+                UNION ALL
+                SELECT c.*, s.depth + 1 FROM todo_list c
+                JOIN subtree s ON c.parent_id = s.id
+                WHERE s.depth <= $2 AND c.deleted_at IS NULL
+            )
+            SELECT * FROM subtree ORDER BY depth, sort_order, id"#,
+        [root_id.into(), (MAX_TODO_DEPTH as i32).into()],
+    );
+    Ok(TodoList::find().from_raw_sql(stmt).all(db).await?)
+}
+
+/// 查出 `id` 自身及其全部祖先的 id，顺序为从自身到顶层
+pub async fn load_ancestor_ids<C: ConnectionTrait>(db: &C, id: i32) -> ApiResult<Vec<i32>> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"WITH RECURSIVE ancestors AS (
+                SELECT id, parent_id, 1 AS depth FROM todo_list WHERE id = $1
+                UNION ALL
+                SELECT t.id, t.parent_id, a.depth + 1 FROM todo_list t
+                JOIN ancestors a ON t.id = a.parent_id
+                WHERE a.depth <= $2
+            )
+            SELECT id FROM ancestors ORDER BY depth"#,
+        [id.into(), (MAX_TODO_DEPTH as i32).into()],
+    );
+    db.query_all_raw(stmt)
+        .await?
+        .iter()
+        .map(|row| row.try_get_by_index::<i32>(0).map_err(ApiError::from))
+        .collect()
+}
+
+/// 把平铺的子树数据组装成嵌套结构，`rows` 中必须包含 `root_id`
+pub fn build_tree(root_id: i32, rows: Vec<todo_list::Model>) -> Option<TodoTreeNode> {
+    let mut root = None;
+    let mut children_map: HashMap<i32, Vec<todo_list::Model>> = HashMap::new();
+    for row in rows {
+        if row.id == root_id {
+            root = Some(row);
+        } else if let Some(parent_id) = row.parent_id {
+            children_map.entry(parent_id).or_default().push(row);
+        }
+    }
+    fn attach(
+        todo: todo_list::Model,
+        children_map: &mut HashMap<i32, Vec<todo_list::Model>>,
+    ) -> TodoTreeNode {
+        let children = children_map
+            .remove(&todo.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|child| attach(child, children_map))
+            .collect();
+        TodoTreeNode { todo, children }
+    }
+    root.map(|todo| attach(todo, &mut children_map))
+}
+
+/// 查询子树并校验整棵子树都属于当前用户
+pub async fn load_user_subtree<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    root_id: i32,
+) -> ApiResult<TodoTreeNode> {
+    let rows = load_subtree(db, root_id).await?;
+    if rows.is_empty() || rows.iter().any(|row| row.user_id != user_id) {
+        return Err(ApiError::Biz(String::from("待办事项不存在或无权访问！")));
+    }
+    build_tree(root_id, rows).ok_or_else(|| ApiError::Biz(String::from("待办事项不存在！")))
+}
+
+/// 校验在 `parent_id` 下新增一层子任务后不会超过最大层级
+pub async fn ensure_child_depth<C: ConnectionTrait>(
+    db: &C,
+    parent_id: i32,
+    subtree_height: usize,
+) -> ApiResult<Vec<i32>> {
+    let ancestors = load_ancestor_ids(db, parent_id).await?;
+    if ancestors.len() + subtree_height > MAX_TODO_DEPTH {
+        return Err(ApiError::Biz(format!(
+            "子任务层级不能超过 {MAX_TODO_DEPTH} 层"
+        )));
+    }
+    Ok(ancestors)
+}
+
+/// 对当前用户的任务树结构加事务级咨询锁，防止并发移动时产生环
+pub async fn lock_user_tree<C: ConnectionTrait>(db: &C, user_id: i32) -> ApiResult<()> {
+    db.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1)",
+        [i64::from(user_id).into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 查询待办事项及其全部子任务（嵌套结构）
+#[debug_handler]
+pub async fn get_todo_tree_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<TodoTreeNode>> {
+    let tree = authorize_subtree(db_pool, principal.id as i32, params.id, TodoRole::Viewer).await?;
+    Ok(ApiResponse::success(tree))
+}
+
+/// 把待办事项连同其子树移动到新的父任务下
+#[debug_handler]
+#[tracing::instrument(name = "move todo", skip_all, fields(user_id = %principal.id, id = %path.id))]
+pub async fn move_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(path): ValidPath<TodoIdParam>,
+    ValidJson(params): ValidJson<MoveTodoParam>,
+) -> ApiResult<ApiResponse<TodoTreeNode>> {
+    if path.id == params.parent_id {
+        return Err(ApiError::Biz(String::from("不能把任务移动到自己下面！")));
+    }
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, path.id, TodoRole::Editor).await?;
+    let parent = authorize_todo(
+        &txn,
+        principal.id as i32,
+        params.parent_id,
+        TodoRole::Editor,
+    )
+    .await?;
+    // 任务树按创建者划分，不能把任务移动到其他用户的任务下面
+    if parent.user_id != todo.user_id {
+        return Err(ApiError::Biz(String::from(
+            "不能把任务移动到其他用户创建的任务下面！",
+        )));
+    }
+    let user_id = todo.user_id;
+    lock_user_tree(&txn, user_id).await?;
+    let tree = load_user_subtree(&txn, user_id, path.id).await?;
+    // 新父任务不能位于被移动的子树中，否则会形成环
+    let ancestors = ensure_child_depth(&txn, params.parent_id, tree.height()).await?;
+    if ancestors.contains(&path.id) {
+        return Err(ApiError::Biz(String::from(
+            "不能把任务移动到它自己的子任务下面！",
+        )));
+    }
+    let mut todo = tree.todo.clone().into_active_model();
+    todo.parent_id = Set(Some(params.parent_id));
+    // 移动后排在新父任务的子任务末尾
+    todo.sort_order = Set(Some(
+        append_rank(&txn, user_id, Some(params.parent_id)).await?,
+    ));
+    todo.update(&txn).await?;
+    follow_parent_project(&txn, path.id, params.parent_id).await?;
+    // 原父任务和新父任务链上的用时都需要重新汇总
+    if let Some(old_parent_id) = tree.todo.parent_id {
+        rollup_actual_time(&txn, old_parent_id).await?;
+    }
+    rollup_actual_time(&txn, params.parent_id).await?;
+    let tree = load_user_subtree(&txn, user_id, path.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("移动成功！", Some(tree)))
+}
+
+/// 把子任务提升为顶层任务，其子树随之一起移动
+#[debug_handler]
+#[tracing::instrument(name = "promote todo", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn promote_todo_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+    ValidPath(params): ValidPath<TodoIdParam>,
+) -> ApiResult<ApiResponse<TodoTreeNode>> {
+    let txn = history.begin(db_pool).await?;
+    let todo = authorize_todo(&txn, principal.id as i32, params.id, TodoRole::Owner).await?;
+    let user_id = todo.user_id;
+    lock_user_tree(&txn, user_id).await?;
+    let tree = load_user_subtree(&txn, user_id, params.id).await?;
+    if tree.todo.parent_id.is_none() {
+        return Err(ApiError::Biz(String::from("该任务已经是顶层任务！")));
+    }
+    let mut todo = tree.todo.clone().into_active_model();
+    todo.parent_id = Set(None);
+    todo.sort_order = Set(Some(append_rank(&txn, user_id, None).await?));
+    todo.update(&txn).await?;
+    if let Some(old_parent_id) = tree.todo.parent_id {
+        rollup_actual_time(&txn, old_parent_id).await?;
+    }
+    let tree = load_user_subtree(&txn, user_id, params.id).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::ok("已提升为顶层任务！", Some(tree)))
+}
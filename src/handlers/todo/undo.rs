@@ -0,0 +1,538 @@
+use crate::conf::get_app_config;
+use crate::db::get_global_database_pool;
+use crate::db::get_global_redis_client;
+use crate::domain::todo_role::TodoRole;
+use crate::entities::prelude::{TodoHistory, TodoList};
+use crate::entities::{todo_history, todo_list};
+use crate::handlers::todo::access::{ensure_role, find_owned_todo, todo_role};
+use crate::handlers::todo::history::HistoryContext;
+use crate::handlers::todo::reorder::append_rank;
+use crate::handlers::todo::time_tracking::rollup_actual_time;
+use crate::handlers::todo::tree::{ensure_child_depth, load_user_subtree, lock_user_tree};
+use crate::middlewares::auth::principal::Principal;
+use crate::middlewares::request_id::make_request_id::request_id_of;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::search::index::{needs_reindex, refresh_todo_index};
+use crate::state::app_state::AppState;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::{Expr, ExprTrait};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, QuerySelect, Set, Statement,
+};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use tower_http::request_id::RequestId;
+
+/// 撤销和重做会恢复的字段
+///
+/// 与回滚相比多了父任务、项目和回收站状态；排序值、用时由系统维护，
+/// UID 和重复规则涉及其他数据，都不在撤销范围内。
+pub const UNDOABLE_FIELDS: [&str; 14] = [
+    "title",
+    "description",
+    "summary",
+    "status",
+    "priority",
+    "due_date",
+    "completed_at",
+    "is_important",
+    "is_urgent",
+    "tags",
+    "estimated_time",
+    "parent_id",
+    "project_id",
+    "deleted_at",
+];
+
+/// 按 JSON 修改一行待办事项，JSON 中没有出现的字段保持原值
+const PATCH_TODO_SQL: &str = r#"UPDATE todo_list t SET
+        (title, description, summary, status, priority, due_date, completed_at,
+         is_important, is_urgent, tags, estimated_time, parent_id, project_id, deleted_at) =
+        (SELECT p.title, p.description, p.summary, p.status, p.priority, p.due_date, p.completed_at,
+                p.is_important, p.is_urgent, p.tags, p.estimated_time, p.parent_id, p.project_id,
+                p.deleted_at
+         FROM jsonb_populate_record(t, $2::JSONB) p)
+    WHERE t.id = $1
+    RETURNING t.*"#;
+
+/// 一个待办事项在一次操作中的变化
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UndoChange {
+    pub todo_id: i32,
+    /// 操作完成后的最新版本号，执行前据此判断这之后是否被修改过
+    pub revision: i32,
+    /// 是否由这次操作新建，撤销新建即移入回收站
+    pub created: bool,
+    /// 操作前的字段值
+    pub before: Map<String, Value>,
+    /// 操作后的字段值
+    pub after: Map<String, Value>,
+}
+
+/// 撤销栈中的一步，对应一次写请求
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UndoStep {
+    pub request_id: String,
+    pub changes: Vec<UndoChange>,
+}
+
+impl UndoStep {
+    /// 把一次请求产生的修改历史（按 id 升序）合并成一步，同一个待办事项只保留一条变化
+    ///
+    /// 字段的旧值取第一次修改前的值，新值取最后一次修改后的值，
+    /// 最终没有变化的字段和待办事项会被忽略。
+    pub fn from_history(request_id: String, rows: &[todo_history::Model]) -> Self {
+        let mut changes: Vec<UndoChange> = Vec::new();
+        for row in rows {
+            let index = match changes.iter().position(|c| c.todo_id == row.todo_id) {
+                Some(index) => index,
+                None => {
+                    changes.push(UndoChange {
+                        todo_id: row.todo_id,
+                        revision: row.revision,
+                        created: false,
+                        before: Map::new(),
+                        after: Map::new(),
+                    });
+                    changes.len() - 1
+                }
+            };
+            let change = &mut changes[index];
+            change.revision = std::cmp::max(change.revision, row.revision);
+            change.created |= row.action == "create";
+            let Some(diff) = row.changes.as_object() else {
+                continue;
+            };
+            for (field, value) in diff {
+                if !UNDOABLE_FIELDS.contains(&field.as_str()) {
+                    continue;
+                }
+                let old = value.get("old").cloned().unwrap_or(Value::Null);
+                let new = value.get("new").cloned().unwrap_or(Value::Null);
+                change.before.entry(field.clone()).or_insert(old);
+                change.after.insert(field.clone(), new);
+            }
+        }
+        for change in &mut changes {
+            let unchanged: Vec<String> = change
+                .after
+                .iter()
+                .filter(|(field, value)| change.before.get(*field) == Some(*value))
+                .map(|(field, _)| field.clone())
+                .collect();
+            for field in unchanged {
+                change.before.remove(&field);
+                change.after.remove(&field);
+            }
+        }
+        changes.retain(|c| c.created || !c.after.is_empty());
+        Self {
+            request_id,
+            changes,
+        }
+    }
+
+    fn todo_ids(&self) -> Vec<i32> {
+        self.changes.iter().map(|c| c.todo_id).collect()
+    }
+}
+
+/// 执行方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UndoDirection {
+    Undo,
+    Redo,
+}
+
+impl UndoDirection {
+    fn name(&self) -> &'static str {
+        match self {
+            UndoDirection::Undo => "撤销",
+            UndoDirection::Redo => "重做",
+        }
+    }
+
+    /// 从哪个栈中取出
+    fn source_key(&self, user_id: i32, session_id: &str) -> String {
+        match self {
+            UndoDirection::Undo => undo_key(user_id, session_id),
+            UndoDirection::Redo => redo_key(user_id, session_id),
+        }
+    }
+
+    /// 执行完成后放入哪个栈
+    fn target_key(&self, user_id: i32, session_id: &str) -> String {
+        match self {
+            UndoDirection::Undo => redo_key(user_id, session_id),
+            UndoDirection::Redo => undo_key(user_id, session_id),
+        }
+    }
+
+    /// 需要写回的字段
+    fn patch(&self, change: &UndoChange, now: DateTimeWithTimeZone) -> Map<String, Value> {
+        match (self, change.created) {
+            // 撤销新建即移入回收站，重做新建即从回收站恢复
+            (UndoDirection::Undo, true) => {
+                Map::from_iter([(String::from("deleted_at"), serde_json::json!(now))])
+            }
+            (UndoDirection::Redo, true) => {
+                Map::from_iter([(String::from("deleted_at"), Value::Null)])
+            }
+            (UndoDirection::Undo, false) => change.before.clone(),
+            (UndoDirection::Redo, false) => change.after.clone(),
+        }
+    }
+}
+
+/// 撤销栈在 Redis 中的键，按用户和会话区分：yx_todo_undo_1_xxx
+fn undo_key(user_id: i32, session_id: &str) -> String {
+    format!("yx_todo_undo_{user_id}_{session_id}")
+}
+
+/// 重做栈在 Redis 中的键：yx_todo_redo_1_xxx
+fn redo_key(user_id: i32, session_id: &str) -> String {
+    format!("yx_todo_redo_{user_id}_{session_id}")
+}
+
+fn redis_error(err: impl std::fmt::Display) -> ApiError {
+    ApiError::Internal(anyhow::anyhow!("读写撤销记录失败：{err}"))
+}
+
+/// 查询待办事项最新的版本号
+async fn latest_revisions<C: ConnectionTrait>(db: &C, ids: &[i32]) -> ApiResult<HashMap<i32, i32>> {
+    let rows: Vec<(i32, Option<i32>)> = TodoHistory::find()
+        .select_only()
+        .column(todo_history::Column::TodoId)
+        .expr(Expr::col(todo_history::Column::Revision).max())
+        .filter(todo_history::Column::TodoId.is_in(ids.iter().copied()))
+        .group_by(todo_history::Column::TodoId)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, revision)| revision.map(|r| (id, r)))
+        .collect())
+}
+
+/// 记录撤销步骤的中间件，放在登陆校验之后
+///
+/// 写请求成功后，按请求 id 读出这次请求产生的修改历史，合并成一步压入当前会话的撤销栈，
+/// 同时清空重做栈；没有修改任何待办事项的请求不会记录。撤销和重做本身不记录。
+pub async fn record_undo_step(request: Request, next: Next) -> Response {
+    let skip = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) || request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| path.as_str().ends_with("/undo") || path.as_str().ends_with("/redo"));
+    let principal = request.extensions().get::<Principal>().cloned();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(request_id_of);
+    let (Some(principal), Some(request_id)) = (principal, request_id) else {
+        return next.run(request).await;
+    };
+    // Basic 认证等没有会话的请求不记录
+    if skip || principal.session_id.is_empty() {
+        return next.run(request).await;
+    }
+    // 请求 id 可能由客户端传入并重复使用，只读取这次请求开始之后写入的历史
+    let since = match TodoHistory::find()
+        .select_only()
+        .expr(Expr::col(todo_history::Column::Id).max())
+        .into_tuple::<Option<i64>>()
+        .one(get_global_database_pool())
+        .await
+    {
+        Ok(id) => id.flatten().unwrap_or(0),
+        Err(e) => {
+            tracing::warn!("读取修改历史失败，本次操作不记录撤销：{e}");
+            return next.run(request).await;
+        }
+    };
+    let response = next.run(request).await;
+    if response.status().is_success()
+        && let Err(e) = push_undo_step(&principal, request_id, since).await
+    {
+        tracing::warn!("记录撤销步骤失败：{e}");
+    }
+    response
+}
+
+async fn push_undo_step(principal: &Principal, request_id: String, since: i64) -> ApiResult<()> {
+    let user_id = principal.id as i32;
+    let rows = TodoHistory::find()
+        .filter(todo_history::Column::RequestId.eq(request_id.as_str()))
+        .filter(todo_history::Column::ActorId.eq(user_id))
+        .filter(todo_history::Column::Id.gt(since))
+        .order_by_asc(todo_history::Column::Id)
+        .all(get_global_database_pool())
+        .await?;
+    let step = UndoStep::from_history(request_id, &rows);
+    if step.changes.is_empty() {
+        return Ok(());
+    }
+    let config = get_app_config().undo();
+    let redis_client = get_global_redis_client();
+    let undo_key = undo_key(user_id, &principal.session_id);
+    redis_client
+        .del(&redo_key(user_id, &principal.session_id))
+        .await
+        .map_err(redis_error)?;
+    // 步骤过大时不再记录，之前的步骤也无法按顺序撤销了，一并清空
+    if step.changes.len() > config.max_changes() {
+        tracing::warn!(
+            "ID为: {} 的用户的操作涉及 {} 个待办事项，超过撤销上限，已清空撤销记录",
+            user_id,
+            step.changes.len()
+        );
+        redis_client.del(&undo_key).await.map_err(redis_error)?;
+        return Ok(());
+    }
+    let value = serde_json::to_string(&step).map_err(|e| ApiError::Internal(e.into()))?;
+    redis_client
+        .push_capped(
+            &undo_key,
+            &value,
+            config.max_steps(),
+            config.ttl_secs() as i64,
+        )
+        .await
+        .map_err(redis_error)
+}
+
+/// 在事务中执行一步撤销或重做，返回修改后的待办事项
+///
+/// 任意一个待办事项在这之后被修改过、已被彻底删除，或执行后任务树不再合法
+/// （未删除的子任务挂在回收站中的父任务下、出现环或超过最大层级）时返回业务错误。
+/// 共享的待办事项也可以撤销，但执行时当前用户必须仍然是编辑者。
+async fn apply_step<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    step: &mut UndoStep,
+    direction: UndoDirection,
+) -> ApiResult<Vec<todo_list::Model>> {
+    let conflict = || {
+        ApiError::Biz(format!(
+            "相关的待办事项在这之后已被修改，无法{}！",
+            direction.name()
+        ))
+    };
+    let ids = step.todo_ids();
+    // 按创建者依次加锁，顺序固定避免死锁
+    let owners: BTreeSet<i32> = TodoList::find()
+        .select_only()
+        .column(todo_list::Column::UserId)
+        .filter(todo_list::Column::Id.is_in(ids.iter().copied()))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    for owner_id in owners {
+        lock_user_tree(db, owner_id).await?;
+    }
+    let current: HashMap<i32, todo_list::Model> = TodoList::find()
+        .filter(todo_list::Column::Id.is_in(ids.iter().copied()))
+        .lock_exclusive()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|todo| (todo.id, todo))
+        .collect();
+    let revisions = latest_revisions(db, &ids).await?;
+    if step.changes.iter().any(|change| {
+        !current.contains_key(&change.todo_id)
+            || revisions.get(&change.todo_id) != Some(&change.revision)
+    }) {
+        return Err(conflict());
+    }
+    for todo in current.values() {
+        ensure_role(todo_role(db, user_id, todo).await?, TodoRole::Editor)?;
+    }
+    let now = get_local_datetime_with_timezone();
+    let mut updated = Vec::with_capacity(step.changes.len());
+    for change in &step.changes {
+        let patch = Value::Object(direction.patch(change, now));
+        let todo = TodoList::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                PATCH_TODO_SQL,
+                [change.todo_id.into(), patch.into()],
+            ))
+            .one(db)
+            .await?
+            .ok_or_else(conflict)?;
+        updated.push(todo);
+    }
+    // 未删除的子任务不能挂在回收站中的父任务下
+    let orphan = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT c.id FROM todo_list c
+                JOIN todo_list p ON p.id = c.parent_id
+                WHERE c.deleted_at IS NULL AND p.deleted_at IS NOT NULL
+                  AND (c.id = ANY($1) OR p.id = ANY($1))
+                LIMIT 1"#,
+            [ids.clone().into()],
+        ))
+        .await?;
+    if orphan.is_some() {
+        return Err(conflict());
+    }
+    let mut rollup_ids = BTreeSet::new();
+    let mut todos = Vec::with_capacity(updated.len());
+    for mut todo in updated {
+        let before = &current[&todo.id];
+        if before.parent_id != todo.parent_id {
+            if let (Some(parent_id), None) = (todo.parent_id, todo.deleted_at) {
+                find_owned_todo(db, todo.user_id, parent_id)
+                    .await
+                    .map_err(|_| conflict())?;
+                let height = load_user_subtree(db, todo.user_id, todo.id).await?.height();
+                if ensure_child_depth(db, parent_id, height)
+                    .await?
+                    .contains(&todo.id)
+                {
+                    return Err(conflict());
+                }
+            }
+            // 回到原来的父任务下时排在末尾
+            let sort_order = append_rank(db, todo.user_id, todo.parent_id).await?;
+            let mut active = todo.into_active_model();
+            active.sort_order = Set(Some(sort_order));
+            todo = active.update(db).await?;
+            rollup_ids.extend(before.parent_id);
+            rollup_ids.extend(todo.parent_id);
+        } else if before.deleted_at != todo.deleted_at {
+            rollup_ids.extend(todo.parent_id);
+        }
+        if needs_reindex(before, &todo) {
+            refresh_todo_index(db, &todo).await?;
+        }
+        todos.push(todo);
+    }
+    for id in rollup_ids {
+        rollup_actual_time(db, id).await?;
+    }
+    let revisions = latest_revisions(db, &ids).await?;
+    for change in &mut step.changes {
+        if let Some(revision) = revisions.get(&change.todo_id) {
+            change.revision = *revision;
+        }
+    }
+    Ok(todos)
+}
+
+/// 撤销或重做的结果
+#[derive(Debug, serde::Serialize)]
+pub struct UndoResult {
+    /// 被撤销或重做的操作的请求 id
+    pub request_id: String,
+    pub todos: Vec<todo_list::Model>,
+}
+
+/// 从栈中取出一步并执行，成功后放入另一个栈
+///
+/// 发生冲突时这一步被丢弃；数据库等其他错误时放回原来的栈，可以稍后重试。
+async fn run_step(
+    state: &AppState,
+    principal: &Principal,
+    history: &HistoryContext,
+    direction: UndoDirection,
+) -> ApiResult<ApiResponse<UndoResult>> {
+    if principal.session_id.is_empty() {
+        return Err(ApiError::Biz(format!(
+            "当前登陆方式不支持{}！",
+            direction.name()
+        )));
+    }
+    let user_id = principal.id as i32;
+    let config = get_app_config().undo();
+    let source_key = direction.source_key(user_id, &principal.session_id);
+    let value = state
+        .redis_client
+        .lpop(&source_key)
+        .await
+        .map_err(redis_error)?
+        .ok_or_else(|| ApiError::Biz(format!("没有可以{}的操作！", direction.name())))?;
+    let mut step: UndoStep = serde_json::from_str(&value)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("无法解析撤销记录：{e}")))?;
+    let result = async {
+        let txn = history.begin(state.db_pool).await?;
+        let todos = apply_step(&txn, user_id, &mut step, direction).await?;
+        txn.commit().await?;
+        Ok::<_, ApiError>(todos)
+    }
+    .await;
+    let (key, todos) = match result {
+        Ok(todos) => (direction.target_key(user_id, &principal.session_id), todos),
+        Err(e @ ApiError::Biz(_)) => return Err(e),
+        Err(e) => {
+            state
+                .redis_client
+                .push_capped(
+                    &source_key,
+                    &value,
+                    config.max_steps(),
+                    config.ttl_secs() as i64,
+                )
+                .await
+                .map_err(redis_error)?;
+            return Err(e);
+        }
+    };
+    let value = serde_json::to_string(&step).map_err(|e| ApiError::Internal(e.into()))?;
+    state
+        .redis_client
+        .push_capped(&key, &value, config.max_steps(), config.ttl_secs() as i64)
+        .await
+        .map_err(redis_error)?;
+    tracing::info!(
+        "ID为: {} 的用户{}了请求 {} 的操作",
+        user_id,
+        direction.name(),
+        step.request_id
+    );
+    Ok(ApiResponse::ok(
+        format!("{}成功！", direction.name()),
+        Some(UndoResult {
+            request_id: step.request_id,
+            todos,
+        }),
+    ))
+}
+
+/// 撤销当前会话中最近一次修改待办事项的操作
+#[debug_handler]
+#[tracing::instrument(name = "undo todo", skip_all, fields(user_id = %principal.id))]
+pub async fn undo_todo_handler(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+) -> ApiResult<ApiResponse<UndoResult>> {
+    run_step(&state, &principal, &history, UndoDirection::Undo).await
+}
+
+/// 重做当前会话中最近一次撤销的操作
+#[debug_handler]
+#[tracing::instrument(name = "redo todo", skip_all, fields(user_id = %principal.id))]
+pub async fn redo_todo_handler(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    history: HistoryContext,
+) -> ApiResult<ApiResponse<UndoResult>> {
+    run_step(&state, &principal, &history, UndoDirection::Redo).await
+}
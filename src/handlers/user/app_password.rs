@@ -0,0 +1,94 @@
+use crate::common::valid::{ValidJson, ValidPath};
+use crate::entities::app_passwords;
+use crate::entities::prelude::AppPasswords;
+use crate::handlers::common::model::{AppPasswordIdParam, CreateAppPasswordParam};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::errors::ApiError;
+use crate::response::{ApiResult, resp::ApiResponse};
+use crate::state::app_state::AppState;
+use crate::utils::crypto::{generate_token, sha256_hex};
+use axum::Extension;
+use axum::debug_handler;
+use axum::extract::State;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set};
+
+/// 每个用户最多创建的应用专用密码数量
+const MAX_APP_PASSWORDS_PER_USER: u64 = 20;
+
+/// 新建的应用专用密码，密码只在创建时返回一次
+#[derive(Debug, serde::Serialize)]
+pub struct CreatedAppPassword {
+    #[serde(flatten)]
+    pub app_password: app_passwords::Model,
+    pub password: String,
+}
+
+/// 查询当前用户的应用专用密码（不包含密码本身）
+#[debug_handler]
+pub async fn list_app_passwords_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> ApiResult<ApiResponse<Vec<app_passwords::Model>>> {
+    let app_passwords = AppPasswords::find()
+        .filter(app_passwords::Column::UserId.eq(principal.id as i32))
+        .order_by_asc(app_passwords::Column::Id)
+        .all(db_pool)
+        .await?;
+    Ok(ApiResponse::success(app_passwords))
+}
+
+/// 创建应用专用密码，供 CalDAV 等只支持用户名和密码的客户端使用
+#[debug_handler]
+#[tracing::instrument(name = "create app password", skip_all, fields(user_id = %principal.id))]
+pub async fn create_app_password_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(params): ValidJson<CreateAppPasswordParam>,
+) -> ApiResult<ApiResponse<CreatedAppPassword>> {
+    let user_id = principal.id as i32;
+    let count = AppPasswords::find()
+        .filter(app_passwords::Column::UserId.eq(user_id))
+        .count(db_pool)
+        .await?;
+    if count >= MAX_APP_PASSWORDS_PER_USER {
+        return Err(ApiError::Biz(format!(
+            "每个用户最多创建 {MAX_APP_PASSWORDS_PER_USER} 个应用专用密码"
+        )));
+    }
+    let password = generate_token();
+    let app_password = AppPasswords::insert(app_passwords::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(params.name.trim().to_string()),
+        password_hash: Set(sha256_hex(&password)),
+        ..Default::default()
+    })
+    .exec_with_returning(db_pool)
+    .await?;
+    tracing::info!("用户 {} 创建应用专用密码 {}", user_id, app_password.id);
+    Ok(ApiResponse::ok(
+        "创建成功，密码只显示这一次，请妥善保存！",
+        Some(CreatedAppPassword {
+            app_password,
+            password,
+        }),
+    ))
+}
+
+/// 撤销应用专用密码，使用该密码的客户端需要重新登陆
+#[debug_handler]
+#[tracing::instrument(name = "revoke app password", skip_all, fields(user_id = %principal.id, id = %params.id))]
+pub async fn revoke_app_password_handler(
+    State(AppState { db_pool, .. }): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidPath(params): ValidPath<AppPasswordIdParam>,
+) -> ApiResult<ApiResponse<()>> {
+    let result = AppPasswords::delete_many()
+        .filter(app_passwords::Column::Id.eq(params.id))
+        .filter(app_passwords::Column::UserId.eq(principal.id as i32))
+        .exec(db_pool)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::Biz(String::from("应用专用密码不存在")));
+    }
+    Ok(ApiResponse::ok("已撤销！", None))
+}
@@ -65,6 +65,7 @@ pub async fn user_login_handler(
         name: user_display_name,
         level: 1,
         identity: Identity::role_to_identity(RoleEnum::User),
+        session_id: String::new(),
     };
     // 生成 access_token
     let access_token = get_default_jwt().encode(principal)?;
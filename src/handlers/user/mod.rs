@@ -1,2 +1,3 @@
+pub mod app_password;
 pub mod login;
 pub mod query;
@@ -0,0 +1,68 @@
+use crate::jobs::wait_next_tick;
+use crate::response::ApiResult;
+use crate::response::errors::ApiError;
+use crate::storage::blob::BlobStore;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 只清理一小时之前的文件，正在上传或刚刚复用的文件不会被误删
+const MIN_AGE: Duration = Duration::from_secs(3600);
+
+/// 每次查询引用情况的文件数量
+const CHUNK_SIZE: usize = 500;
+
+/// 定期删除没有任何附件引用的文件
+///
+/// 删除附件、彻底删除待办事项（附件随外键级联删除）后，文件都由这里统一回收。
+pub async fn run(
+    db: &'static DatabaseConnection,
+    store: BlobStore,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("📎 attachment gc started");
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while wait_next_tick(&mut interval, &mut shutdown).await {
+        match collect_garbage(db, &store).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("清理了 {} 个无人引用的附件文件", count),
+            Err(e) => tracing::error!("清理附件文件失败：{}", e),
+        }
+    }
+}
+
+/// 执行一轮清理，返回删除的文件数量（包括残留的临时文件）
+///
+/// 列出文件和查询引用之间可能有上传复用了同一个文件，删除前会重新检查修改时间，
+/// 被 [`BlobStore::persist`] 刷新过的文件留到下一轮再判断。
+async fn collect_garbage(db: &DatabaseConnection, store: &BlobStore) -> ApiResult<usize> {
+    let io_error = |e: std::io::Error| ApiError::Internal(e.into());
+    let mut removed = store.clean_temp(MIN_AGE).await.map_err(io_error)?;
+    let hashes = store.list_older_than(MIN_AGE).await.map_err(io_error)?;
+    for chunk in hashes.chunks(CHUNK_SIZE) {
+        let rows = db
+            .query_all_raw(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT DISTINCT sha256 FROM todo_attachments WHERE sha256 = ANY($1)",
+                [chunk.to_vec().into()],
+            ))
+            .await?;
+        let referenced = rows
+            .iter()
+            .map(|row| row.try_get_by_index::<String>(0))
+            .collect::<Result<HashSet<_>, _>>()?;
+        for sha256 in chunk.iter().filter(|sha256| !referenced.contains(*sha256)) {
+            if store
+                .remove_if_older_than(sha256, MIN_AGE)
+                .await
+                .map_err(io_error)?
+            {
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,95 @@
+use crate::conf::attachment::AttachmentConfig;
+use crate::conf::reminder::ReminderConfig;
+use crate::conf::trash::TrashConfig;
+use crate::notifier::build_notifier;
+use crate::state::app_state::AppState;
+use crate::storage::blob::BlobStore;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+pub mod attachment_gc;
+pub mod purge;
+pub mod rebalance;
+pub mod reminder;
+
+/// 后台任务集合，随服务启动，在服务优雅关闭后统一停止
+///
+/// 每个后台任务都持有一个关闭信号的接收端，收到信号后完成当前一轮工作再退出。
+pub struct BackgroundJobs {
+    shutdown_tx: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundJobs {
+    /// 启动所有后台任务
+    ///
+    /// # 参数
+    /// - state: app 的数据状态
+    /// - reminder_config: 到期提醒的配置
+    /// - trash_config: 回收站的配置
+    /// - attachment_config: 附件的配置
+    pub fn spawn(
+        state: &AppState,
+        reminder_config: &ReminderConfig,
+        trash_config: &TrashConfig,
+        attachment_config: &AttachmentConfig,
+    ) -> Self {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut handles = vec![
+            tokio::spawn(rebalance::run(state.db_pool, shutdown_rx.clone())),
+            tokio::spawn(attachment_gc::run(
+                state.db_pool,
+                BlobStore::new(attachment_config.dir()),
+                Duration::from_secs(attachment_config.gc_interval_secs()),
+                shutdown_rx.clone(),
+            )),
+        ];
+        if reminder_config.enabled() {
+            handles.push(tokio::spawn(reminder::run(
+                state.db_pool,
+                build_notifier(reminder_config.notifier(), state.redis_client),
+                Duration::from_secs(reminder_config.interval_secs()),
+                reminder_config.batch_size(),
+                shutdown_rx.clone(),
+            )));
+        }
+        if trash_config.enabled() {
+            handles.push(tokio::spawn(purge::run(
+                state.db_pool,
+                trash_config.retention_days(),
+                Duration::from_secs(trash_config.interval_secs()),
+                shutdown_rx,
+            )));
+        }
+        Self {
+            shutdown_tx,
+            handles,
+        }
+    }
+
+    /// 通知所有后台任务退出，并等待它们结束
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(true);
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                tracing::error!("后台任务异常退出：{}", e);
+            }
+        }
+        tracing::info!("✅ background jobs stopped");
+    }
+}
+
+/// 等待下一次执行，收到关闭信号时返回 false
+pub(crate) async fn wait_next_tick(
+    interval: &mut tokio::time::Interval,
+    shutdown: &mut watch::Receiver<bool>,
+) -> bool {
+    if *shutdown.borrow() {
+        return false;
+    }
+    tokio::select! {
+        _ = interval.tick() => true,
+        _ = shutdown.changed() => false,
+    }
+}
@@ -0,0 +1,41 @@
+use crate::entities::prelude::TodoList;
+use crate::entities::todo_list;
+use crate::jobs::wait_next_tick;
+use crate::response::ApiResult;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 定期彻底删除在回收站中超过保留天数的待办事项
+///
+/// 同一次删除的子任务与父任务的删除时间相同，会在同一轮中一起清理；
+/// 计时记录、提醒等关联数据随外键级联删除，修改历史中会记录一条操作人为空的 purge。
+pub async fn run(
+    db: &'static DatabaseConnection,
+    retention_days: u32,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("🗑️ trash purge started, retention: {} days", retention_days);
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while wait_next_tick(&mut interval, &mut shutdown).await {
+        match purge_expired(db, retention_days).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("清理了回收站中 {} 条过期的待办事项", count),
+            Err(e) => tracing::error!("清理回收站失败：{}", e),
+        }
+    }
+}
+
+/// 执行一轮清理，返回删除的数量
+async fn purge_expired(db: &DatabaseConnection, retention_days: u32) -> ApiResult<u64> {
+    let cutoff =
+        get_local_datetime_with_timezone() - chrono::Duration::days(i64::from(retention_days));
+    let result = TodoList::delete_many()
+        .filter(todo_list::Column::DeletedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
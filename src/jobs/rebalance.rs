@@ -0,0 +1,64 @@
+use crate::domain::rank::DENSE_RANK_GAP;
+use crate::handlers::todo::reorder::{lock_sibling_group, rebalance_group};
+use crate::jobs::wait_next_tick;
+use crate::response::ApiResult;
+use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, Statement, TransactionTrait};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 检查排序值密度的间隔
+const REBALANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// 每一轮最多重排的分组数量，避免单轮占用过多数据库资源
+const REBALANCE_BATCH: i64 = 100;
+
+/// 排序值过于密集的同级分组
+#[derive(Debug, FromQueryResult)]
+struct DenseGroup {
+    user_id: i32,
+    parent_id: Option<i32>,
+}
+
+/// 定期找出相邻排序值间隔过小（或重复）的同级分组并重新编号
+///
+/// 拖动排序总是取两个相邻值的中点，多次插入同一位置后间隔会越来越小，
+/// 提前在后台重排可以让拖动接口几乎总是只修改一行数据。
+pub async fn run(db: &'static DatabaseConnection, mut shutdown: watch::Receiver<bool>) {
+    let mut interval = tokio::time::interval(REBALANCE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while wait_next_tick(&mut interval, &mut shutdown).await {
+        match rebalance_dense_groups(db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("后台重排了 {} 个排序过密的分组", count),
+            Err(e) => tracing::error!("后台重排排序值失败：{}", e),
+        }
+    }
+}
+
+/// 执行一轮重排，返回处理的分组数量
+async fn rebalance_dense_groups(db: &DatabaseConnection) -> ApiResult<usize> {
+    let groups = DenseGroup::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT user_id, parent_id FROM (
+                SELECT user_id, parent_id,
+                    sort_order - LAG(sort_order) OVER (
+                        PARTITION BY user_id, parent_id ORDER BY sort_order, id
+                    ) AS gap
+                FROM todo_list
+                WHERE deleted_at IS NULL
+            ) g
+            WHERE gap IS NOT NULL AND gap < $1
+            GROUP BY user_id, parent_id
+            LIMIT $2"#,
+        [DENSE_RANK_GAP.into(), REBALANCE_BATCH.into()],
+    ))
+    .all(db)
+    .await?;
+    for group in &groups {
+        // 每个分组单独一个事务，与拖动接口使用同一把锁
+        let txn = db.begin().await?;
+        lock_sibling_group(&txn, group.user_id, group.parent_id).await?;
+        rebalance_group(&txn, group.user_id, group.parent_id).await?;
+        txn.commit().await?;
+    }
+    Ok(groups.len())
+}
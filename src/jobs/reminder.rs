@@ -0,0 +1,140 @@
+use crate::jobs::wait_next_tick;
+use crate::notifier::{DueReminder, Notifier};
+use crate::response::ApiResult;
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, Statement};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 提醒最多可以提前多少分钟（30 天），扫描时据此限定 due_date 的范围
+pub const MAX_REMINDER_OFFSET_MINUTES: i32 = 30 * 24 * 60;
+/// 截止时间已经过去超过该分钟数的任务不再补发提醒（例如服务长时间停机）
+const MISSED_GRACE_MINUTES: i32 = 60;
+
+/// 定期扫描到期的提醒并发送
+///
+/// 每条提醒针对同一个截止时间只会发送一次：发送前先在 reminder_deliveries 中插入记录占位，
+/// 插入成功（提交）后才发送，因此服务重启或多实例部署时都不会重复发送。
+pub async fn run(
+    db: &'static DatabaseConnection,
+    notifier: Box<dyn Notifier>,
+    interval: Duration,
+    batch_size: u64,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!(
+        "⏰ reminder scheduler started, notifier: {}",
+        notifier.name()
+    );
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while wait_next_tick(&mut interval, &mut shutdown).await {
+        match deliver_due_reminders(db, notifier.as_ref(), batch_size, &shutdown).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("发送了 {} 条到期提醒", count),
+            Err(e) => tracing::error!("发送到期提醒失败：{}", e),
+        }
+    }
+}
+
+/// 查询已经到了提醒时间、且尚未发送的提醒
+///
+/// 通过限定 due_date 的范围走 idx_todo_due_date 索引，只处理未完成的任务。
+async fn find_due_reminders<C: ConnectionTrait>(
+    db: &C,
+    batch_size: u64,
+) -> ApiResult<Vec<DueReminder>> {
+    let now = get_local_datetime_with_timezone();
+    Ok(DueReminder::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT r.id AS reminder_id, t.id AS todo_id, t.user_id, t.title, t.due_date, r.offset_minutes
+            FROM todo_list t
+            JOIN todo_reminders r ON r.todo_id = t.id
+            WHERE t.due_date >= $1 - make_interval(mins => $2)
+                AND t.due_date <= $1 + make_interval(mins => $3)
+                AND t.due_date - make_interval(mins => r.offset_minutes) <= $1
+                AND t.status IN ('pending', 'in_progress')
+                AND t.deleted_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM reminder_deliveries d
+                    WHERE d.reminder_id = r.id AND d.due_date = t.due_date
+                )
+            ORDER BY t.due_date - make_interval(mins => r.offset_minutes)
+            LIMIT $4"#,
+        [
+            now.into(),
+            MISSED_GRACE_MINUTES.into(),
+            MAX_REMINDER_OFFSET_MINUTES.into(),
+            (batch_size as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await?)
+}
+
+/// 占用一条提醒的发送记录，返回 None 表示已经被其他实例或之前的运行占用
+async fn claim_delivery<C: ConnectionTrait>(
+    db: &C,
+    reminder: &DueReminder,
+) -> ApiResult<Option<i32>> {
+    let row = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO reminder_deliveries (reminder_id, due_date) VALUES ($1, $2)
+                ON CONFLICT (reminder_id, due_date) DO NOTHING
+                RETURNING id"#,
+            [reminder.reminder_id.into(), reminder.due_date.into()],
+        ))
+        .await?;
+    Ok(match row {
+        Some(row) => Some(row.try_get("", "id")?),
+        None => None,
+    })
+}
+
+/// 更新发送结果
+async fn finish_delivery<C: ConnectionTrait>(
+    db: &C,
+    delivery_id: i32,
+    error: Option<String>,
+) -> ApiResult<()> {
+    let status = if error.is_some() { "failed" } else { "sent" };
+    db.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE reminder_deliveries SET status = $2, error = $3, delivered_at = CURRENT_TIMESTAMP
+            WHERE id = $1"#,
+        [delivery_id.into(), status.into(), error.into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 执行一轮发送，返回成功发送的数量；收到关闭信号时处理完当前提醒即停止
+async fn deliver_due_reminders(
+    db: &DatabaseConnection,
+    notifier: &dyn Notifier,
+    batch_size: u64,
+    shutdown: &watch::Receiver<bool>,
+) -> ApiResult<usize> {
+    let mut delivered = 0;
+    for reminder in find_due_reminders(db, batch_size).await? {
+        if *shutdown.borrow() {
+            break;
+        }
+        let Some(delivery_id) = claim_delivery(db, &reminder).await? else {
+            continue;
+        };
+        let error = match notifier.notify(&reminder).await {
+            Ok(()) => {
+                delivered += 1;
+                None
+            }
+            Err(e) => {
+                tracing::warn!("提醒 {} 发送失败：{}", reminder.reminder_id, e);
+                Some(e.to_string())
+            }
+        };
+        finish_delivery(db, delivery_id, error).await?;
+    }
+    Ok(delivered)
+}
@@ -1,12 +1,18 @@
 pub mod app;
+pub mod calendar;
 pub mod common;
 pub mod conf;
 pub mod db;
+pub mod domain;
 pub mod entities;
 pub mod handlers;
+pub mod jobs;
 pub mod log;
 pub mod middlewares;
+pub mod notifier;
 pub mod response;
 pub mod router;
+pub mod search;
 pub mod state;
+pub mod storage;
 pub mod utils;
@@ -0,0 +1,149 @@
+use crate::db::get_global_database_pool;
+use crate::entities::prelude::{AppPasswords, Users};
+use crate::entities::{app_passwords, users};
+use crate::middlewares::auth::identity::{Identity, RoleEnum};
+use crate::middlewares::auth::principal::Principal;
+use crate::response::ApiResult;
+use crate::utils::crypto::{sha256_hex, verify_password};
+use crate::utils::timezone::get_local_datetime_with_timezone;
+use axum::http::{HeaderMap, Request, Response, StatusCode, header};
+use axum::response::IntoResponse;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::LazyLock;
+use tower_http::auth::{AsyncAuthorizeRequest, AsyncRequireAuthorizationLayer};
+
+/// HTTP Basic 认证的 realm
+const REALM: &str = "todo_list";
+
+static BASIC_AUTH_LAYER: LazyLock<AsyncRequireAuthorizationLayer<BasicAuth>> =
+    LazyLock::new(|| AsyncRequireAuthorizationLayer::new(BasicAuth));
+
+/// HTTP Basic 认证，供 CalDAV 等不支持 Bearer 令牌的客户端使用
+///
+/// 密码可以是登陆密码，也可以是应用专用密码。认证成功后和 JwtAuth 一样在请求中放入 Principal。
+#[derive(Clone)]
+pub struct BasicAuth;
+
+/// 返回 401，并通过 WWW-Authenticate 提示客户端使用 Basic 认证
+fn unauthorized(message: &'static str) -> Response<axum::body::Body> {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(
+            header::WWW_AUTHENTICATE,
+            format!("Basic realm=\"{REALM}\", charset=\"UTF-8\""),
+        )],
+        message,
+    )
+        .into_response()
+}
+
+/// 从 Authorization 请求头中解析用户名和密码
+fn parse_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+/// 校验用户名和密码，先匹配应用专用密码，再校验登陆密码
+async fn authenticate(
+    db: &DatabaseConnection,
+    username: &str,
+    password: &str,
+) -> ApiResult<Option<Principal>> {
+    let Some(user) = Users::find()
+        .filter(users::Column::Username.eq(username))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if user.is_active == Some(false) {
+        return Ok(None);
+    }
+    let app_password = AppPasswords::find()
+        .filter(app_passwords::Column::UserId.eq(user.id))
+        .filter(app_passwords::Column::PasswordHash.eq(sha256_hex(password)))
+        .one(db)
+        .await?;
+    let verified = match app_password {
+        Some(app_password) => {
+            // 使用时间每小时最多更新一次，客户端的每个请求都会带上密码
+            let now = get_local_datetime_with_timezone();
+            AppPasswords::update_many()
+                .col_expr(app_passwords::Column::LastUsedAt, Expr::value(now))
+                .filter(app_passwords::Column::Id.eq(app_password.id))
+                .filter(
+                    Condition::any()
+                        .add(app_passwords::Column::LastUsedAt.is_null())
+                        .add(
+                            app_passwords::Column::LastUsedAt.lt(now - chrono::Duration::hours(1)),
+                        ),
+                )
+                .exec(db)
+                .await?;
+            true
+        }
+        None => {
+            // argon2 校验比较耗时，放到阻塞线程池中执行
+            let password = password.to_string();
+            let password_hash = user.password_hash.clone();
+            tokio::task::spawn_blocking(move || verify_password(&password, &password_hash))
+                .await
+                .map_err(anyhow::Error::from)??
+        }
+    };
+    if !verified {
+        return Ok(None);
+    }
+    Ok(Some(Principal {
+        id: user.id as i64,
+        name: user.display_name.unwrap_or(user.username),
+        level: 1,
+        identity: Identity::role_to_identity(RoleEnum::User),
+        // Basic 认证没有会话
+        session_id: String::new(),
+    }))
+}
+
+/// impl authorize request for BasicAuth
+impl AsyncAuthorizeRequest<axum::body::Body> for BasicAuth {
+    type RequestBody = axum::body::Body;
+    type ResponseBody = axum::body::Body;
+
+    type Future = std::pin::Pin<
+        Box<
+            dyn Future<Output = Result<Request<Self::RequestBody>, Response<Self::ResponseBody>>>
+                + Send
+                + 'static,
+        >,
+    >;
+
+    fn authorize(&mut self, mut request: Request<axum::body::Body>) -> Self::Future {
+        let db = get_global_database_pool();
+        Box::pin(async move {
+            let (username, password) = parse_credentials(request.headers())
+                .ok_or_else(|| unauthorized("需要使用用户名和密码登陆"))?;
+            let principal = authenticate(db, &username, &password)
+                .await?
+                .ok_or_else(|| {
+                    tracing::warn!("用户 {} 的 Basic 认证失败", username);
+                    unauthorized("用户名或密码不正确")
+                })?;
+            request.extensions_mut().insert(principal);
+            Ok(request)
+        })
+    }
+}
+
+/// public method to get the static BASIC_AUTH_LAYER pointer
+pub fn get_basic_auth_layer() -> &'static AsyncRequireAuthorizationLayer<BasicAuth> {
+    &BASIC_AUTH_LAYER
+}
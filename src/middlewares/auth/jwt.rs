@@ -134,6 +134,7 @@ impl Jwt {
             name,
             level,
             identity,
+            session_id: claims.jti,
         };
         Ok(principal)
     }
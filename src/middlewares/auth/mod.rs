@@ -1,4 +1,5 @@
 pub mod auth_layer;
+pub mod basic_auth;
 pub mod identity;
 pub mod jwt;
 pub mod principal;
@@ -6,6 +6,8 @@ pub struct Principal {
     pub name: String,       // name 昵称
     pub level: i32,         // level 等级
     pub identity: Identity, // identity 身份信息
+    #[serde(skip)]
+    pub session_id: String, // session_id 会话 id，登陆令牌的 jti
 }
 
 /// 手动实现 Debug trait
@@ -16,6 +18,7 @@ impl std::fmt::Debug for Principal {
             .field("name", &self.name)
             .field("level", &self.level)
             .field("identity", &self.identity.as_str())
+            .field("session_id", &self.session_id)
             .finish()
     }
 }
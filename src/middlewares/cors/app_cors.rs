@@ -19,6 +19,7 @@ pub fn app_cors() -> tower_http::cors::CorsLayer {
             axum::http::Method::GET,
             axum::http::Method::POST,
             axum::http::Method::PUT,
+            axum::http::Method::PATCH,
             axum::http::Method::DELETE,
             axum::http::Method::OPTIONS,
         ])
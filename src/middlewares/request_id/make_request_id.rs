@@ -0,0 +1,26 @@
+use axum::http::{HeaderValue, Request};
+use tower_http::request_id::{MakeRequestId, RequestId};
+
+/// 请求头中的请求 id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 使用 xid 生成请求 id，客户端已经带了 `x-request-id` 时沿用客户端的值
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MakeRequestXid;
+
+impl MakeRequestId for MakeRequestXid {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        HeaderValue::from_str(&xid::new().to_string())
+            .ok()
+            .map(RequestId::new)
+    }
+}
+
+/// 读取请求 id，过长的值会被截断，保证可以写入数据库
+pub fn request_id_of(request_id: &RequestId) -> Option<String> {
+    request_id
+        .header_value()
+        .to_str()
+        .ok()
+        .map(|id| id.chars().take(64).collect())
+}
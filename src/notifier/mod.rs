@@ -0,0 +1,83 @@
+use crate::conf::reminder::NotifierKind;
+use crate::db::my_redis::RedisClient;
+use sea_orm::FromQueryResult;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::future::Future;
+use std::pin::Pin;
+
+/// 需要发送的到期提醒
+#[derive(Debug, Clone, serde::Serialize, FromQueryResult)]
+pub struct DueReminder {
+    pub reminder_id: i32,
+    pub todo_id: i32,
+    pub user_id: i32,
+    pub title: String,
+    pub due_date: DateTimeWithTimeZone,
+    pub offset_minutes: i32,
+}
+
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+
+/// 提醒的通知方式，新增渠道（邮件、推送等）只需要实现该 trait
+pub trait Notifier: Send + Sync {
+    /// 通知方式的名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 发送一条提醒，返回错误时该提醒会被记录为发送失败
+    fn notify<'a>(&'a self, reminder: &'a DueReminder) -> NotifyFuture<'a>;
+}
+
+/// 只输出日志的通知方式，便于开发调试
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn notify<'a>(&'a self, reminder: &'a DueReminder) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            tracing::info!(
+                "⏰ 提醒用户 {}：待办事项「{}」将于 {} 到期",
+                reminder.user_id,
+                reminder.title,
+                reminder.due_date
+            );
+            Ok(())
+        })
+    }
+}
+
+/// 把提醒以 JSON 发布到 redis 频道 `todo:reminders:{user_id}`，由订阅方推送给用户
+pub struct RedisNotifier {
+    redis_client: &'static RedisClient,
+}
+
+impl RedisNotifier {
+    pub fn new(redis_client: &'static RedisClient) -> Self {
+        Self { redis_client }
+    }
+}
+
+impl Notifier for RedisNotifier {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn notify<'a>(&'a self, reminder: &'a DueReminder) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let channel = format!("todo:reminders:{}", reminder.user_id);
+            let message = serde_json::to_string(reminder)?;
+            self.redis_client.publish(&channel, &message).await?;
+            Ok(())
+        })
+    }
+}
+
+/// 按配置创建通知方式
+pub fn build_notifier(kind: NotifierKind, redis_client: &'static RedisClient) -> Box<dyn Notifier> {
+    match kind {
+        NotifierKind::Log => Box::new(LogNotifier),
+        NotifierKind::Redis => Box::new(RedisNotifier::new(redis_client)),
+    }
+}
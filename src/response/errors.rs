@@ -1,4 +1,5 @@
 use crate::response::resp::ApiResponse;
+use axum::extract::multipart::MultipartRejection;
 use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
 use axum::response::{IntoResponse, Response};
 
@@ -20,6 +21,13 @@ pub enum ApiError {
     PathError(#[from] PathRejection),
     #[error("Body 参数错误: {0}")]
     JsonError(#[from] JsonRejection),
+    #[error("文件上传参数错误: {0}")]
+    MultipartError(#[from] MultipartRejection),
+    #[error("非法的状态变更：{from} -> {to}")]
+    IllegalStatusTransition {
+        from: &'static str,
+        to: &'static str,
+    },
     #[error("参数校验失败：{0}")]
     ValidationError(String),
     #[error("Database error: {0}")]
@@ -37,9 +45,11 @@ impl ApiError {
             ApiError::MethodNotAllowed => axum::http::StatusCode::METHOD_NOT_ALLOWED,
             ApiError::Biz(_) => axum::http::StatusCode::OK,
             ApiError::Unauthenticated(_) => axum::http::StatusCode::UNAUTHORIZED,
+            ApiError::IllegalStatusTransition { .. } => axum::http::StatusCode::CONFLICT,
             ApiError::QueryError(_)
             | ApiError::PathError(_)
             | ApiError::JsonError(_)
+            | ApiError::MultipartError(_)
             | ApiError::ValidationError(_) => axum::http::StatusCode::BAD_REQUEST,
             ApiError::Argon2HashingError(_)
             | ApiError::Internal(_)
@@ -1,4 +1,5 @@
 pub mod errors;
+pub mod page;
 pub mod resp;
 
 /// define response api result type
@@ -0,0 +1,32 @@
+/// 游标分页结果
+///
+/// # 成员
+/// - items：当前页数据
+/// - total：满足筛选条件的总条数
+/// - limit：每页数量
+/// - next_cursor：下一页的游标，没有更多数据时为空
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub limit: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// 构造函数
+    pub fn new(items: Vec<T>, total: u64, limit: u64, next_cursor: Option<String>) -> Self {
+        Self {
+            items,
+            total,
+            limit,
+            next_cursor,
+        }
+    }
+
+    /// 是否还有下一页
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+}
@@ -0,0 +1,39 @@
+use crate::handlers::caldav::collection::{
+    caldav_collection_handler, caldav_home_handler, caldav_principal_handler, caldav_root_handler,
+};
+use crate::handlers::caldav::resource::caldav_resource_handler;
+use crate::middlewares::auth::basic_auth::get_basic_auth_layer;
+use crate::state::app_state::AppState;
+use axum::routing::{MethodRouter, any};
+
+/// 同时注册带和不带结尾 “/” 的路径，CalDAV 客户端访问集合时通常会带上 “/”
+fn dav_route(
+    router: axum::Router<AppState>,
+    path: &str,
+    method_router: MethodRouter<AppState>,
+) -> axum::Router<AppState> {
+    router
+        .route(path, method_router.clone())
+        .route(&format!("{path}/"), method_router)
+}
+
+/// 创建 CalDAV 相关的路由，使用 HTTP Basic 认证（登陆密码或应用专用密码）
+///
+/// PROPFIND、REPORT 等 WebDAV 方法不能用 MethodFilter 表示，由各个处理函数自己按方法分发。
+/// 嵌套路由的根路径不能匹配结尾的 “/”，所以这里直接使用完整路径，由 merge_router 合并。
+pub fn create_caldav_router() -> axum::Router<AppState> {
+    let router = dav_route(axum::Router::new(), "/caldav", any(caldav_root_handler));
+    let router = dav_route(router, "/caldav/principal", any(caldav_principal_handler));
+    let router = dav_route(router, "/caldav/calendars", any(caldav_home_handler));
+    let router = dav_route(
+        router,
+        "/caldav/calendars/todos",
+        any(caldav_collection_handler),
+    );
+    router
+        .route(
+            "/caldav/calendars/todos/{file}",
+            any(caldav_resource_handler),
+        )
+        .route_layer(get_basic_auth_layer())
+}
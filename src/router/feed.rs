@@ -0,0 +1,7 @@
+use crate::handlers::todo::feed::calendar_feed_handler;
+use crate::state::app_state::AppState;
+
+/// 创建日历订阅相关的路由，供日历客户端轮询，凭地址中的令牌访问，不经过登录认证
+pub fn create_feed_router() -> axum::Router<AppState> {
+    axum::Router::new().route("/{file}", axum::routing::get(calendar_feed_handler))
+}
@@ -2,15 +2,29 @@ use crate::response::ApiResult;
 use crate::response::errors::ApiError;
 use crate::state::app_state::AppState;
 
+pub mod caldav;
+pub mod feed;
 pub mod login;
+pub mod project;
+pub mod share;
+pub mod todo;
 pub mod user;
 pub mod version;
+
+/// 接口前缀，所有接口都挂在这个路径下
+pub const API_PREFIX: &str = "/v1/api";
+
 /// combine all the routes into one router
 pub fn merge_router() -> axum::Router<AppState> {
     axum::Router::new()
         .nest("/get/current", version::get_version_router())
         .nest("/auth", login::create_user_login_route())
         .nest("/user", user::create_users_router())
+        .nest("/todo", todo::create_todo_router())
+        .nest("/project", project::create_project_router())
+        .nest("/share", share::create_share_router())
+        .nest("/feed", feed::create_feed_router())
+        .merge(caldav::create_caldav_router())
         .fallback(async || -> ApiResult<()> {
             // 路径找不到
             tracing::warn!("Not Found");
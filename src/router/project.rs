@@ -0,0 +1,30 @@
+use crate::handlers::project::crud::{
+    archive_project_handler, create_project_handler, delete_project_handler, get_project_handler,
+    list_projects_handler, patch_project_handler, unarchive_project_handler,
+};
+use crate::middlewares::auth::auth_layer::get_auth_layer;
+use crate::state::app_state::AppState;
+
+/// 创建项目相关的路由，项目中的待办事项通过 /todo?project_id= 查询
+pub fn create_project_router() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route(
+            "/",
+            axum::routing::get(list_projects_handler).post(create_project_handler),
+        )
+        .route(
+            "/{id}",
+            axum::routing::get(get_project_handler)
+                .patch(patch_project_handler)
+                .delete(delete_project_handler),
+        )
+        .route(
+            "/{id}/archive",
+            axum::routing::post(archive_project_handler),
+        )
+        .route(
+            "/{id}/unarchive",
+            axum::routing::post(unarchive_project_handler),
+        )
+        .route_layer(get_auth_layer())
+}
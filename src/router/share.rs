@@ -0,0 +1,31 @@
+use crate::handlers::share::crud::{
+    accept_invitation_handler, create_share_handler, decline_invitation_handler,
+    delete_share_handler, list_invitations_handler, list_shared_with_me_handler,
+    list_shares_handler, patch_share_handler,
+};
+use crate::middlewares::auth::auth_layer::get_auth_layer;
+use crate::state::app_state::AppState;
+
+/// 创建共享相关的路由，共享给自己的待办事项通过 /todo?scope=shared 查询
+pub fn create_share_router() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route(
+            "/",
+            axum::routing::get(list_shares_handler).post(create_share_handler),
+        )
+        .route(
+            "/{id}",
+            axum::routing::patch(patch_share_handler).delete(delete_share_handler),
+        )
+        .route("/with-me", axum::routing::get(list_shared_with_me_handler))
+        .route("/invitations", axum::routing::get(list_invitations_handler))
+        .route(
+            "/invitations/{id}/accept",
+            axum::routing::post(accept_invitation_handler),
+        )
+        .route(
+            "/invitations/{id}/decline",
+            axum::routing::post(decline_invitation_handler),
+        )
+        .route_layer(get_auth_layer())
+}
@@ -0,0 +1,198 @@
+use crate::handlers::todo::analytics::estimate_report_handler;
+use crate::handlers::todo::attachments::{
+    attachment_usage_handler, delete_attachment_handler, download_attachment_handler,
+    list_attachments_handler, upload_attachments_handler,
+};
+use crate::handlers::todo::batch::batch_todo_handler;
+use crate::handlers::todo::comments::{
+    create_comment_handler, delete_comment_handler, list_comment_edits_handler,
+    list_comments_handler, list_mentions_handler, update_comment_handler,
+};
+use crate::handlers::todo::crud::{
+    create_todo_handler, delete_todo_handler, get_todo_handler, patch_todo_handler,
+    update_todo_handler,
+};
+use crate::handlers::todo::feed::{
+    create_calendar_feed_handler, list_calendar_feeds_handler, revoke_calendar_feed_handler,
+};
+use crate::handlers::todo::history::{list_todo_history_handler, revert_todo_handler};
+use crate::handlers::todo::ical::{export_ics_handler, import_ics_handler};
+use crate::handlers::todo::listing::list_todo_handler;
+use crate::handlers::todo::matrix::{get_matrix_handler, move_quadrant_handler};
+use crate::handlers::todo::project::move_todo_project_handler;
+use crate::handlers::todo::recurrence::{
+    end_recurrence_handler, get_recurrence_handler, set_recurrence_handler, skip_occurrence_handler,
+};
+use crate::handlers::todo::reminders::{list_reminders_handler, set_reminders_handler};
+use crate::handlers::todo::reorder::reorder_todo_handler;
+use crate::handlers::todo::search::{reindex_todo_handler, search_todo_handler};
+use crate::handlers::todo::status::transition_status_handler;
+use crate::handlers::todo::tags::{
+    delete_tag_handler, filter_by_tags_handler, list_tags_handler, merge_tag_handler,
+    rename_tag_handler,
+};
+use crate::handlers::todo::time_tracking::{
+    create_time_entry_handler, current_timer_handler, delete_time_entry_handler,
+    list_time_entries_handler, patch_time_entry_handler, pause_timer_handler, resume_timer_handler,
+    start_timer_handler, stop_timer_handler,
+};
+use crate::handlers::todo::todo_txt::{export_todo_txt_handler, import_todo_txt_handler};
+use crate::handlers::todo::transfer::{
+    export_csv_handler, export_ndjson_handler, import_csv_handler, import_ndjson_handler,
+};
+use crate::handlers::todo::trash::{
+    empty_trash_handler, list_trash_handler, purge_trash_item_handler, restore_trash_handler,
+};
+use crate::handlers::todo::tree::{get_todo_tree_handler, move_todo_handler, promote_todo_handler};
+use crate::handlers::todo::undo::{record_undo_step, redo_todo_handler, undo_todo_handler};
+use crate::middlewares::auth::auth_layer::get_auth_layer;
+use crate::state::app_state::AppState;
+
+/// 创建待办事项相关的路由，所有操作都只针对当前登陆用户自己的数据
+pub fn create_todo_router() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route(
+            "/",
+            axum::routing::post(create_todo_handler).get(list_todo_handler),
+        )
+        .route("/batch", axum::routing::post(batch_todo_handler))
+        .route(
+            "/matrix",
+            axum::routing::get(get_matrix_handler).put(move_quadrant_handler),
+        )
+        .route(
+            "/analytics/estimates",
+            axum::routing::get(estimate_report_handler),
+        )
+        .route("/export/ics", axum::routing::get(export_ics_handler))
+        .route("/import/ics", axum::routing::post(import_ics_handler))
+        .route("/export/csv", axum::routing::get(export_csv_handler))
+        .route("/import/csv", axum::routing::post(import_csv_handler))
+        .route("/export/ndjson", axum::routing::get(export_ndjson_handler))
+        .route("/import/ndjson", axum::routing::post(import_ndjson_handler))
+        .route(
+            "/export/todotxt",
+            axum::routing::get(export_todo_txt_handler),
+        )
+        .route(
+            "/import/todotxt",
+            axum::routing::post(import_todo_txt_handler),
+        )
+        .route(
+            "/feeds",
+            axum::routing::get(list_calendar_feeds_handler).post(create_calendar_feed_handler),
+        )
+        .route(
+            "/feeds/{feed_id}",
+            axum::routing::delete(revoke_calendar_feed_handler),
+        )
+        .route("/search", axum::routing::get(search_todo_handler))
+        .route("/search/reindex", axum::routing::post(reindex_todo_handler))
+        .route("/tags", axum::routing::get(list_tags_handler))
+        .route("/tags/filter", axum::routing::get(filter_by_tags_handler))
+        .route("/tags/rename", axum::routing::put(rename_tag_handler))
+        .route("/tags/merge", axum::routing::put(merge_tag_handler))
+        .route("/tags/{tag}", axum::routing::delete(delete_tag_handler))
+        .route(
+            "/trash",
+            axum::routing::get(list_trash_handler).delete(empty_trash_handler),
+        )
+        .route(
+            "/trash/{id}",
+            axum::routing::delete(purge_trash_item_handler),
+        )
+        .route(
+            "/trash/{id}/restore",
+            axum::routing::post(restore_trash_handler),
+        )
+        .route("/undo", axum::routing::post(undo_todo_handler))
+        .route("/redo", axum::routing::post(redo_todo_handler))
+        .route("/timer", axum::routing::get(current_timer_handler))
+        .route("/timer/pause", axum::routing::post(pause_timer_handler))
+        .route("/timer/resume", axum::routing::post(resume_timer_handler))
+        .route("/timer/stop", axum::routing::post(stop_timer_handler))
+        .route(
+            "/time-entries/{entry_id}",
+            axum::routing::patch(patch_time_entry_handler).delete(delete_time_entry_handler),
+        )
+        .route(
+            "/comments/mentions",
+            axum::routing::get(list_mentions_handler),
+        )
+        .route(
+            "/comments/{comment_id}",
+            axum::routing::patch(update_comment_handler).delete(delete_comment_handler),
+        )
+        .route(
+            "/comments/{comment_id}/edits",
+            axum::routing::get(list_comment_edits_handler),
+        )
+        .route(
+            "/attachments/usage",
+            axum::routing::get(attachment_usage_handler),
+        )
+        .route(
+            "/attachments/{attachment_id}",
+            axum::routing::get(download_attachment_handler).delete(delete_attachment_handler),
+        )
+        .route(
+            "/{id}",
+            axum::routing::get(get_todo_handler)
+                .put(update_todo_handler)
+                .patch(patch_todo_handler)
+                .delete(delete_todo_handler),
+        )
+        .route(
+            "/{id}/status",
+            axum::routing::post(transition_status_handler),
+        )
+        .route(
+            "/{id}/history",
+            axum::routing::get(list_todo_history_handler),
+        )
+        .route(
+            "/{id}/history/{revision}/revert",
+            axum::routing::post(revert_todo_handler),
+        )
+        .route("/{id}/tree", axum::routing::get(get_todo_tree_handler))
+        .route("/{id}/move", axum::routing::post(move_todo_handler))
+        .route("/{id}/promote", axum::routing::post(promote_todo_handler))
+        .route(
+            "/{id}/project",
+            axum::routing::put(move_todo_project_handler),
+        )
+        .route("/{id}/reorder", axum::routing::post(reorder_todo_handler))
+        .route(
+            "/{id}/recurrence",
+            axum::routing::get(get_recurrence_handler)
+                .put(set_recurrence_handler)
+                .delete(end_recurrence_handler),
+        )
+        .route(
+            "/{id}/recurrence/skip",
+            axum::routing::post(skip_occurrence_handler),
+        )
+        .route(
+            "/{id}/reminders",
+            axum::routing::get(list_reminders_handler).put(set_reminders_handler),
+        )
+        .route(
+            "/{id}/timer/start",
+            axum::routing::post(start_timer_handler),
+        )
+        .route(
+            "/{id}/time-entries",
+            axum::routing::get(list_time_entries_handler).post(create_time_entry_handler),
+        )
+        .route(
+            "/{id}/comments",
+            axum::routing::get(list_comments_handler).post(create_comment_handler),
+        )
+        .route(
+            "/{id}/attachments",
+            axum::routing::get(list_attachments_handler).post(upload_attachments_handler),
+        )
+        // 记录撤销步骤需要登陆信息，必须放在登陆校验之后执行
+        .route_layer(axum::middleware::from_fn(record_undo_step))
+        .route_layer(get_auth_layer())
+}
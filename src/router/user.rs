@@ -1,3 +1,6 @@
+use crate::handlers::user::app_password::{
+    create_app_password_handler, list_app_passwords_handler, revoke_app_password_handler,
+};
 use crate::handlers::user::query::query_user_info_by_id_handler;
 use crate::middlewares::auth::auth_layer::get_auth_layer;
 use crate::state::app_state::AppState;
@@ -9,5 +12,13 @@ pub fn create_users_router() -> axum::Router<AppState> {
             "/query/info/id/{id}",
             axum::routing::get(query_user_info_by_id_handler),
         )
+        .route(
+            "/app-passwords",
+            axum::routing::get(list_app_passwords_handler).post(create_app_password_handler),
+        )
+        .route(
+            "/app-passwords/{id}",
+            axum::routing::delete(revoke_app_password_handler),
+        )
         .route_layer(get_auth_layer())
 }
@@ -0,0 +1,44 @@
+use crate::entities::todo_list;
+use crate::response::ApiResult;
+use crate::search::segment::segment;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+/// 更新单个待办事项的全文检索向量
+///
+/// 标题、描述、摘要分别以 A、B、C 权重写入，排序时标题命中的结果靠前。
+/// 每次新增或修改标题、描述、摘要之后都需要调用。
+pub async fn refresh_todo_index<C: ConnectionTrait>(
+    db: &C,
+    todo: &todo_list::Model,
+) -> ApiResult<()> {
+    let title_tokens = segment(&todo.title);
+    let description_tokens = todo.description.as_deref().map(segment).unwrap_or_default();
+    let summary_tokens = todo
+        .summary
+        .as_ref()
+        .map(|summary| segment(&summary.join("\n")))
+        .unwrap_or_default();
+    db.execute_raw(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE todo_list SET search_vector =
+                setweight(array_to_tsvector($2::TEXT[]), 'A')
+                || setweight(array_to_tsvector($3::TEXT[]), 'B')
+                || setweight(array_to_tsvector($4::TEXT[]), 'C')
+            WHERE id = $1"#,
+        [
+            todo.id.into(),
+            title_tokens.into(),
+            description_tokens.into(),
+            summary_tokens.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 判断修改前后的待办事项是否需要重建索引
+pub fn needs_reindex(before: &todo_list::Model, after: &todo_list::Model) -> bool {
+    before.title != after.title
+        || before.description != after.description
+        || before.summary != after.summary
+}
@@ -0,0 +1,7 @@
+//! 待办事项全文检索
+//!
+//! Postgres 自带的分词器无法切分中文，所以分词在 Rust 中用 jieba 完成，
+//! 分词结果通过 `array_to_tsvector` 直接写入 `todo_list.search_vector`，检索时同样先分词再拼成 tsquery。
+
+pub mod index;
+pub mod segment;
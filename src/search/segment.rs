@@ -0,0 +1,113 @@
+use jieba_rs::Jieba;
+use std::sync::LazyLock;
+
+/// 全局 jieba 分词器，加载默认词典较耗时，只初始化一次
+static JIEBA: LazyLock<Jieba> = LazyLock::new(Jieba::new);
+
+/// 高亮片段左右保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 30;
+
+/// 对文本进行搜索引擎模式分词，去掉空白和标点，英文统一转为小写
+///
+/// 搜索引擎模式会在精确分词的基础上，对长词再切分出短词，以提高召回率。
+pub fn segment(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = JIEBA
+        .cut_for_search(text, true)
+        .into_iter()
+        .map(|word| word.trim().to_lowercase())
+        .filter(|word| !word.is_empty() && word.chars().any(char::is_alphanumeric))
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+/// 把分词结果拼成 tsquery 字面量，所有词都必须命中
+///
+/// 每个词都用单引号包裹作为词素，不再经过 Postgres 的解析器。
+pub fn to_tsquery_literal(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("'{}'", token.replace('\\', "\\\\").replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+/// 转义 HTML 特殊字符
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// 查找 `text` 中所有与分词结果匹配的区间（字节下标），优先匹配最长的词，英文不区分大小写
+fn find_matches(text: &str, tokens: &[String]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text.as_bytes()[pos..];
+        let longest = tokens
+            .iter()
+            .filter(|token| {
+                rest.len() >= token.len()
+                    && text.is_char_boundary(pos + token.len())
+                    && rest[..token.len()].eq_ignore_ascii_case(token.as_bytes())
+            })
+            .map(|token| token.len())
+            .max();
+        match longest {
+            Some(len) if len > 0 => {
+                matches.push((pos, pos + len));
+                pos += len;
+            }
+            _ => {
+                pos += text[pos..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    matches
+}
+
+/// 生成带 `<mark>` 高亮的片段，没有命中时返回 `None`
+///
+/// 片段以第一个命中位置为中心，左右各保留若干字符，被截断的一侧用省略号表示。
+/// 非高亮部分会进行 HTML 转义，前端可以直接渲染。
+pub fn highlight(text: &str, tokens: &[String]) -> Option<String> {
+    let matches = find_matches(text, tokens);
+    let (first_start, _) = *matches.first()?;
+    // 以字符为单位计算片段范围
+    let char_starts: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let first_char = char_starts.partition_point(|&i| i < first_start);
+    let begin_char = first_char.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end_char = (first_char + SNIPPET_CONTEXT_CHARS * 2).min(char_starts.len());
+    let begin = char_starts.get(begin_char).copied().unwrap_or(text.len());
+    let end = char_starts.get(end_char).copied().unwrap_or(text.len());
+
+    let mut snippet = String::new();
+    if begin > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = begin;
+    for (start, stop) in matches {
+        if start < begin || stop > end {
+            continue;
+        }
+        escape_html(&text[cursor..start], &mut snippet);
+        snippet.push_str("<mark>");
+        escape_html(&text[start..stop], &mut snippet);
+        snippet.push_str("</mark>");
+        cursor = stop;
+    }
+    escape_html(&text[cursor..end], &mut snippet);
+    if end < text.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
@@ -0,0 +1,200 @@
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+
+/// 上传过程中使用的临时文件所在的子目录
+const TEMP_DIR: &str = "tmp";
+
+/// 按内容寻址的文件存储
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+/// 正在写入的临时文件，写入的同时计算 SHA-256
+///
+/// 没有通过 `BlobStore::persist` 保存时，临时文件在释放时删除。
+#[derive(Debug)]
+pub struct TempBlob {
+    path: PathBuf,
+    file: Option<File>,
+    hasher: Sha256,
+    size: u64,
+    sha256: Option<String>,
+}
+
+impl TempBlob {
+    /// 已经写入的字节数
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 写入完成后的 SHA-256（小写十六进制）
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// 追加一段内容
+    pub async fn write(&mut self, chunk: &[u8]) -> io::Result<()> {
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("临时文件已经写入完成"))?;
+        file.write_all(chunk).await?;
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// 结束写入，返回 SHA-256
+    pub async fn finish(&mut self) -> io::Result<String> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+            file.sync_all().await?;
+            self.sha256 = Some(format!("{:x}", self.hasher.finalize_reset()));
+        }
+        self.sha256
+            .clone()
+            .ok_or_else(|| io::Error::other("临时文件没有写入完成"))
+    }
+}
+
+impl Drop for TempBlob {
+    fn drop(&mut self) {
+        // 保存后临时文件已被移走，这里只清理没有保存的文件
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl BlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 内容为 `sha256` 的文件的存储路径
+    pub fn path_of(&self, sha256: &str) -> PathBuf {
+        self.root
+            .join(sha256.get(0..2).unwrap_or("00"))
+            .join(sha256.get(2..4).unwrap_or("00"))
+            .join(sha256)
+    }
+
+    /// 创建一个临时文件，用于边接收边写入
+    pub async fn create_temp(&self) -> io::Result<TempBlob> {
+        let dir = self.root.join(TEMP_DIR);
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join(format!("{}.part", xid::new()));
+        let file = File::create(&path).await?;
+        Ok(TempBlob {
+            path,
+            file: Some(file),
+            hasher: Sha256::new(),
+            size: 0,
+            sha256: None,
+        })
+    }
+
+    /// 把写入完成的临时文件保存为正式文件，内容相同的文件已经存在时直接复用
+    ///
+    /// 复用时刷新已有文件的修改时间，避免被后台清理任务当作无人引用的旧文件删除。
+    pub async fn persist(&self, temp: &TempBlob) -> io::Result<PathBuf> {
+        let sha256 = temp
+            .sha256()
+            .ok_or_else(|| io::Error::other("临时文件没有写入完成"))?;
+        let target = self.path_of(sha256);
+        if fs::try_exists(&target).await? {
+            let file = File::options().append(true).open(&target).await?;
+            file.into_std().await.set_modified(SystemTime::now())?;
+            fs::remove_file(&temp.path).await?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::rename(&temp.path, &target).await?;
+        }
+        Ok(target)
+    }
+
+    /// 文件的修改时间仍然早于 `min_age` 之前时删除文件，返回是否删除
+    ///
+    /// 删除前重新读取修改时间：列出文件之后被上传复用的文件已经由 [`BlobStore::persist`] 刷新了修改时间，
+    /// 这时保留文件，避免删掉即将被新附件引用的内容。
+    pub async fn remove_if_older_than(&self, sha256: &str, min_age: Duration) -> io::Result<bool> {
+        let path = self.path_of(sha256);
+        match fs::metadata(&path).await {
+            Ok(metadata) if is_older_than(&metadata, min_age) => {}
+            Ok(_) => return Ok(false),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 列出修改时间早于 `min_age` 之前的全部文件的 SHA-256
+    pub async fn list_older_than(&self, min_age: Duration) -> io::Result<Vec<String>> {
+        let mut hashes = Vec::new();
+        for first in read_dirs(&self.root).await? {
+            if first.file_name().is_some_and(|name| name == TEMP_DIR) {
+                continue;
+            }
+            for second in read_dirs(&first).await? {
+                let mut entries = fs::read_dir(&second).await?;
+                while let Some(entry) = entries.next_entry().await? {
+                    let metadata = entry.metadata().await?;
+                    if metadata.is_file() && is_older_than(&metadata, min_age) {
+                        hashes.push(entry.file_name().to_string_lossy().into_owned());
+                    }
+                }
+            }
+        }
+        Ok(hashes)
+    }
+
+    /// 删除修改时间早于 `min_age` 之前的临时文件（例如上传过程中服务被终止），返回删除的数量
+    pub async fn clean_temp(&self, min_age: Duration) -> io::Result<usize> {
+        let mut entries = match fs::read_dir(self.root.join(TEMP_DIR)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            if is_older_than(&entry.metadata().await?, min_age) {
+                fs::remove_file(entry.path()).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// 列出目录下的子目录，目录不存在时返回空
+async fn read_dirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut dirs = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+fn is_older_than(metadata: &std::fs::Metadata, min_age: Duration) -> bool {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= min_age)
+}
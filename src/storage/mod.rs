@@ -0,0 +1,7 @@
+//! 附件文件的本地存储
+//!
+//! 文件按内容的 SHA-256 存放（`<dir>/ab/cd/abcd...`），相同内容只保存一份，
+//! 元数据保存在 todo_attachments 表中。删除附件只删除元数据，
+//! 没有任何附件引用的文件由后台任务统一清理。
+
+pub mod blob;
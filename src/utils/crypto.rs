@@ -2,6 +2,10 @@ use crate::response::ApiResult;
 use argon2::{
     Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::phc::SaltString,
 };
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
 
 /// 使用 argon2 对密码进行加密。
 ///
@@ -69,3 +73,19 @@ pub fn verify_password(password: &str, password_hash: &str) -> ApiResult<bool> {
     // 返回验证的结果
     Ok(verify_result)
 }
+
+/// 生成随机令牌（32 个随机字节，URL 安全的 base64 编码，43 个字符）
+///
+/// 用于日历订阅地址、应用专用密码等需要长期保存在客户端的凭证。
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// 计算 SHA-256 摘要（十六进制小写）
+///
+/// 随机令牌本身有足够的熵，数据库中只保存摘要即可，不需要 argon2 这样的慢哈希。
+pub fn sha256_hex(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
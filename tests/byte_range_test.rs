@@ -0,0 +1,56 @@
+use todo_list_v1::domain::byte_range::{ByteRange, RangeNotSatisfiable, parse_range};
+
+#[test]
+fn parses_single_ranges() {
+    assert_eq!(
+        parse_range("bytes=0-99", 1000),
+        Ok(Some(ByteRange { start: 0, end: 99 }))
+    );
+    assert_eq!(
+        parse_range("bytes=900-", 1000),
+        Ok(Some(ByteRange {
+            start: 900,
+            end: 999
+        }))
+    );
+    assert_eq!(
+        parse_range("bytes=-100", 1000),
+        Ok(Some(ByteRange {
+            start: 900,
+            end: 999
+        }))
+    );
+}
+
+#[test]
+fn clamps_ranges_to_file_size() {
+    let range = parse_range("bytes=500-5000", 1000).unwrap().unwrap();
+    assert_eq!(
+        range,
+        ByteRange {
+            start: 500,
+            end: 999
+        }
+    );
+    assert_eq!(range.length(), 500);
+    assert_eq!(range.content_range(1000), "bytes 500-999/1000");
+    assert_eq!(
+        parse_range("bytes=-5000", 1000),
+        Ok(Some(ByteRange { start: 0, end: 999 }))
+    );
+}
+
+#[test]
+fn ignores_unsupported_ranges() {
+    assert_eq!(parse_range("items=0-1", 1000), Ok(None));
+    assert_eq!(parse_range("bytes=0-1,5-9", 1000), Ok(None));
+    assert_eq!(parse_range("bytes=9-1", 1000), Ok(None));
+    assert_eq!(parse_range("bytes=abc", 1000), Ok(None));
+}
+
+#[test]
+fn rejects_unsatisfiable_ranges() {
+    assert_eq!(parse_range("bytes=1000-", 1000), Err(RangeNotSatisfiable));
+    assert_eq!(parse_range("bytes=-0", 1000), Err(RangeNotSatisfiable));
+    assert_eq!(parse_range("bytes=0-", 0), Err(RangeNotSatisfiable));
+}
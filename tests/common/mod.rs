@@ -0,0 +1,31 @@
+//! 需要数据库的测试共用的辅助函数
+//!
+//! 设置 TEST_DATABASE_URL（已执行全部迁移的库）后运行，未设置时测试直接跳过；
+//! 每个测试都在事务中执行并回滚，不会留下数据。
+
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, Database, DatabaseTransaction, Set, TransactionTrait,
+};
+use todo_list_v1::entities::users;
+
+pub async fn begin() -> Option<DatabaseTransaction> {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("未设置 TEST_DATABASE_URL，跳过");
+        return None;
+    };
+    let db = Database::connect(url).await.expect("连接测试数据库失败");
+    Some(db.begin().await.expect("开启事务失败"))
+}
+
+pub async fn create_user<C: ConnectionTrait>(db: &C, name: &str) -> i32 {
+    users::ActiveModel {
+        username: Set(name.to_string()),
+        email: Set(format!("{name}@example.com")),
+        password_hash: Set(String::from("x")),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+    .id
+}
@@ -0,0 +1,65 @@
+//! 需要数据库的导入测试，运行方式见 `common` 模块
+
+mod common;
+
+use common::{begin, create_user};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use std::collections::HashMap;
+use todo_list_v1::calendar::vtodo::parse_vtodos;
+use todo_list_v1::entities::prelude::TodoList;
+use todo_list_v1::entities::{projects, todo_list};
+use todo_list_v1::handlers::todo::ical::{link_parent, upsert_vtodo};
+
+#[tokio::test]
+async fn imported_child_follows_parent_project() {
+    let Some(txn) = begin().await else {
+        return;
+    };
+    let user_id = create_user(&txn, "ical_import_project").await;
+    let project = projects::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(String::from("工作")),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let parent = todo_list::ActiveModel {
+        user_id: Set(user_id),
+        title: Set(String::from("父任务")),
+        ical_uid: Set(String::from("parent@example.com")),
+        project_id: Set(Some(project.id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+
+    let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:child@example.com\r\n\
+               SUMMARY:子任务\r\nRELATED-TO:parent@example.com\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    let vtodos = parse_vtodos(ics).unwrap();
+    let [Ok(vtodo)] = vtodos.as_slice() else {
+        panic!("unexpected vtodos: {vtodos:?}");
+    };
+    let (_, child) = upsert_vtodo(&txn, user_id, vtodo).await.unwrap();
+    assert_eq!(child.project_id, None);
+    let message = link_parent(
+        &txn,
+        user_id,
+        child.id,
+        vtodo.parent_uid.as_deref().unwrap(),
+        &HashMap::new(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(message, None);
+
+    let child = TodoList::find_by_id(child.id)
+        .one(&txn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(child.parent_id, Some(parent.id));
+    assert_eq!(child.project_id, Some(project.id));
+    txn.rollback().await.unwrap();
+}
@@ -0,0 +1,191 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
+use std::collections::HashMap;
+use todo_list_v1::calendar::ics::{
+    ContentLine, IcsWriter, escape_text, parse_datetime, split_text_list, unescape_text, unfold,
+};
+use todo_list_v1::calendar::vtodo::{parse_vtodos, write_calendar};
+use todo_list_v1::domain::todo_status::TodoStatus;
+use todo_list_v1::entities::todo_list;
+use todo_list_v1::handlers::todo::model::TodoPriority;
+
+fn dt(s: &str) -> DateTimeWithTimeZone {
+    DateTimeWithTimeZone::parse_from_rfc3339(s).unwrap()
+}
+
+fn todo(id: i32, uid: &str, title: &str) -> todo_list::Model {
+    todo_list::Model {
+        id,
+        user_id: 1,
+        title: title.to_string(),
+        description: None,
+        summary: None,
+        status: Some(TodoStatus::Pending.as_str().into()),
+        priority: Some(TodoPriority::Medium.as_str().into()),
+        due_date: None,
+        completed_at: None,
+        is_important: Some(false),
+        is_urgent: Some(false),
+        tags: None,
+        estimated_time: None,
+        actual_time: None,
+        parent_id: None,
+        sort_order: Some(1024),
+        created_at: None,
+        updated_at: None,
+        recurrence_id: None,
+        ical_uid: uid.to_string(),
+        caldav_name: None,
+        deleted_at: None,
+        project_id: None,
+    }
+}
+
+fn calendar(body: &str) -> String {
+    format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{body}END:VCALENDAR\r\n")
+}
+
+#[test]
+fn long_lines_are_folded_at_char_boundaries() {
+    let value = "待办".repeat(40) + &"x".repeat(100);
+    let mut writer = IcsWriter::new();
+    writer.property("SUMMARY", &value);
+    let out = writer.finish();
+    assert!(out.ends_with("\r\n"));
+    let physical: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+    assert!(physical.len() > 1);
+    assert!(physical.iter().all(|line| line.len() <= 75));
+    assert!(physical[1..].iter().all(|line| line.starts_with(' ')));
+    assert_eq!(unfold(&out)[0], format!("SUMMARY:{value}"));
+}
+
+#[test]
+fn unfold_accepts_lf_and_tab_continuations() {
+    assert_eq!(
+        unfold("DESCRIPTION:ab\n c\r\n\td\r\nUID:1"),
+        ["DESCRIPTION:abcd", "UID:1"]
+    );
+}
+
+#[test]
+fn text_escaping_round_trips() {
+    let text = "a;b,c\\d\n第二行";
+    let escaped = escape_text(text);
+    assert_eq!(escaped, r"a\;b\,c\\d\n第二行");
+    assert_eq!(unescape_text(&escaped), text);
+    assert_eq!(unescape_text("line\\Nbreak"), "line\nbreak");
+    assert_eq!(escape_text("windows\r\nline"), "windows\\nline");
+}
+
+#[test]
+fn splits_escaped_text_lists() {
+    assert_eq!(
+        split_text_list(r"work,a\,b, home ,,c\;d"),
+        ["work", "a,b", "home", "c;d"]
+    );
+}
+
+#[test]
+fn parses_quoted_params() {
+    let line = ContentLine::parse("related-to;reltype=PARENT;X-NOTE=\"a:b;c\":uid-1").unwrap();
+    assert_eq!(line.name, "RELATED-TO");
+    assert_eq!(line.param("RELTYPE"), Some("PARENT"));
+    assert_eq!(line.param("x-note"), Some("a:b;c"));
+    assert_eq!(line.value, "uid-1");
+}
+
+#[test]
+fn parses_due_as_date_or_date_time() {
+    let parse = |line: &str| parse_datetime(&ContentLine::parse(line).unwrap());
+    assert_eq!(
+        parse("DUE;VALUE=DATE:20250101"),
+        Ok(dt("2025-01-01T00:00:00+08:00"))
+    );
+    assert_eq!(
+        parse("DUE:20250101T010000Z"),
+        Ok(dt("2025-01-01T09:00:00+08:00"))
+    );
+    // 浮动时间和带 TZID 的时间都按东八区解析
+    assert_eq!(
+        parse("DUE:20250101T090000"),
+        Ok(dt("2025-01-01T09:00:00+08:00"))
+    );
+    assert_eq!(
+        parse("DUE;TZID=Asia/Shanghai:20250101T090000"),
+        Ok(dt("2025-01-01T09:00:00+08:00"))
+    );
+    assert!(parse("DUE:2025-01-01").is_err());
+}
+
+#[test]
+fn written_calendar_parses_back() {
+    let mut parent = todo(1, "parent@example.com", "父任务");
+    parent.description = Some(String::from("第一行；含分号, 逗号\n第二行"));
+    parent.due_date = Some(dt("2025-03-01T18:00:00+08:00"));
+    parent.tags = Some(vec![String::from("工作"), String::from("a,b")]);
+    parent.priority = Some(TodoPriority::High.as_str().into());
+    let mut child = todo(2, "child@example.com", &"很长的子任务标题".repeat(12));
+    child.parent_id = Some(1);
+    child.status = Some(TodoStatus::Completed.as_str().into());
+    child.completed_at = Some(dt("2025-02-01T08:00:00+08:00"));
+    let parent_uids = HashMap::from([(1, parent.ical_uid.clone())]);
+    let text = write_calendar("测试", &[parent.clone(), child.clone()], &parent_uids);
+
+    let parsed: Vec<_> = parse_vtodos(&text)
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].uid, parent.ical_uid);
+    assert_eq!(parsed[0].summary, parent.title);
+    assert_eq!(parsed[0].description, parent.description);
+    assert_eq!(parsed[0].due, parent.due_date);
+    assert_eq!(parsed[0].categories, ["工作", "a,b"]);
+    assert_eq!(parsed[0].priority, Some(TodoPriority::High));
+    assert_eq!(parsed[0].status, Some(TodoStatus::Pending));
+    assert_eq!(parsed[0].parent_uid, None);
+    assert_eq!(parsed[1].summary, child.title);
+    assert_eq!(parsed[1].status, Some(TodoStatus::Completed));
+    assert_eq!(parsed[1].completed, child.completed_at);
+    assert_eq!(parsed[1].parent_uid.as_deref(), Some("parent@example.com"));
+}
+
+#[test]
+fn related_to_only_follows_parent_reltype() {
+    let text = calendar(
+        "BEGIN:VTODO\r\nUID:a\r\nSUMMARY:a\r\nRELATED-TO;RELTYPE=CHILD:b\r\nRELATED-TO:c\r\nEND:VTODO\r\n\
+         BEGIN:VTODO\r\nUID:b\r\nSUMMARY:b\r\nRELATED-TO;RELTYPE=SIBLING:a\r\nEND:VTODO\r\n",
+    );
+    let parsed = parse_vtodos(&text).unwrap();
+    assert_eq!(parsed[0].as_ref().unwrap().parent_uid.as_deref(), Some("c"));
+    assert_eq!(parsed[1].as_ref().unwrap().parent_uid, None);
+}
+
+#[test]
+fn reports_invalid_items_with_their_uid() {
+    let long_uid = "u".repeat(256);
+    let text = calendar(&format!(
+        "BEGIN:VTODO\r\nUID:{long_uid}\r\nSUMMARY:too long\r\nEND:VTODO\r\n\
+         BEGIN:VTODO\r\nUID:no-summary\r\nEND:VTODO\r\n\
+         BEGIN:VTODO\r\nUID:{}\r\nSUMMARY:ok\r\nEND:VTODO\r\n",
+        "u".repeat(255)
+    ));
+    let parsed = parse_vtodos(&text).unwrap();
+    assert_eq!(parsed.len(), 3);
+    let (uid, message) = parsed[0].clone().unwrap_err();
+    assert_eq!(uid, Some(long_uid));
+    assert!(message.contains("UID"));
+    assert_eq!(
+        parsed[1].clone().unwrap_err(),
+        (
+            Some(String::from("no-summary")),
+            String::from("缺少 SUMMARY")
+        )
+    );
+    assert!(parsed[2].is_ok());
+}
+
+#[test]
+fn rejects_text_without_vcalendar() {
+    assert!(parse_vtodos("BEGIN:VTODO\r\nUID:a\r\nEND:VTODO\r\n").is_err());
+}
@@ -0,0 +1,32 @@
+use todo_list_v1::domain::mention::{MAX_MENTIONS, parse_mentions};
+
+#[test]
+fn parses_mentions_in_order_without_duplicates() {
+    assert_eq!(
+        parse_mentions("@alice 请看一下，@bob.wang 和 @alice 一起处理。"),
+        vec!["alice", "bob.wang"]
+    );
+    assert_eq!(parse_mentions("谢谢 @张三！"), vec!["张三"]);
+}
+
+#[test]
+fn ignores_emails_and_trailing_punctuation() {
+    assert_eq!(
+        parse_mentions("发邮件到 a@b.com 或者找 @carol."),
+        vec!["carol"]
+    );
+    assert!(parse_mentions("@ 空的 @@").is_empty());
+}
+
+#[test]
+fn skips_code_blocks_and_inline_code() {
+    let body =
+        "`@inline` @dave\n```\n@fenced\n```\n~~~rust\n@tilde\n~~~\n``@double`` and a stray ` @erin";
+    assert_eq!(parse_mentions(body), vec!["dave", "erin"]);
+}
+
+#[test]
+fn limits_the_number_of_mentions() {
+    let body: String = (0..MAX_MENTIONS + 5).map(|i| format!("@u{i} ")).collect();
+    assert_eq!(parse_mentions(&body).len(), MAX_MENTIONS);
+}
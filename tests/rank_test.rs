@@ -0,0 +1,43 @@
+use todo_list_v1::domain::rank::{RANK_GAP, rank_between};
+
+const GAP: i32 = RANK_GAP as i32;
+
+#[test]
+fn first_rank_in_empty_group() {
+    assert_eq!(rank_between(None, None), Some(GAP));
+}
+
+#[test]
+fn inserts_between_two_ranks() {
+    assert_eq!(rank_between(Some(1024), Some(2048)), Some(1536));
+    assert_eq!(rank_between(Some(1), Some(4)), Some(2));
+    assert_eq!(rank_between(Some(-10), Some(10)), Some(0));
+    // 间隔恰好为 2 时中点仍然存在
+    assert_eq!(rank_between(Some(7), Some(9)), Some(8));
+}
+
+#[test]
+fn inserts_at_either_end() {
+    assert_eq!(rank_between(Some(3072), None), Some(3072 + GAP));
+    assert_eq!(rank_between(None, Some(1024)), Some(0));
+    assert_eq!(rank_between(None, Some(0)), Some(-GAP));
+}
+
+#[test]
+fn exhausted_gap_needs_rebalance() {
+    assert_eq!(rank_between(Some(5), Some(6)), None);
+    assert_eq!(rank_between(Some(5), Some(5)), None);
+    // 上下界颠倒时同样没有可用的排序值
+    assert_eq!(rank_between(Some(6), Some(5)), None);
+}
+
+#[test]
+fn out_of_i32_range_needs_rebalance() {
+    assert_eq!(rank_between(Some(i32::MAX - 1), None), None);
+    assert_eq!(rank_between(None, Some(i32::MIN + 1)), None);
+    assert_eq!(
+        rank_between(Some(i32::MIN), Some(i32::MAX)),
+        Some(-1),
+        "中点在 i64 中计算，不会溢出"
+    );
+}
@@ -0,0 +1,99 @@
+//! 需要数据库的重复任务测试，运行方式见 `common` 模块
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{begin, create_user};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, IntoActiveModel, Set};
+use todo_list_v1::entities::{projects, todo_list, todo_recurrences};
+use todo_list_v1::handlers::todo::recurrence::spawn_next_occurrence;
+
+async fn create_project<C: ConnectionTrait>(db: &C, user_id: i32, archived: bool) -> i32 {
+    projects::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(String::from("工作")),
+        archived: Set(archived),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+    .id
+}
+
+/// 在 `project_id` 中创建一个每天重复的待办事项，返回设置好重复系列的实例
+async fn create_daily<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    project_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> todo_list::Model {
+    let due = Utc
+        .with_ymd_and_hms(2026, 1, 5, 9, 0, 0)
+        .unwrap()
+        .fixed_offset();
+    let todo = todo_list::ActiveModel {
+        user_id: Set(user_id),
+        title: Set(String::from("日报")),
+        due_date: Set(Some(due)),
+        project_id: Set(project_id),
+        parent_id: Set(parent_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let series = todo_recurrences::ActiveModel {
+        user_id: Set(user_id),
+        rrule: Set(String::from("FREQ=DAILY")),
+        dtstart: Set(due),
+        current_todo_id: Set(Some(todo.id)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let mut todo = todo.into_active_model();
+    todo.recurrence_id = Set(Some(series.id));
+    todo.update(db).await.unwrap()
+}
+
+#[tokio::test]
+async fn next_occurrence_stays_in_project() {
+    let Some(txn) = begin().await else {
+        return;
+    };
+    let user_id = create_user(&txn, "recurrence_project").await;
+    let project_id = create_project(&txn, user_id, false).await;
+    let todo = create_daily(&txn, user_id, Some(project_id), None).await;
+
+    let next = spawn_next_occurrence(&txn, &todo).await.unwrap().unwrap();
+    assert_eq!(next.project_id, Some(project_id));
+    assert_eq!(next.parent_id, None);
+    txn.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn next_occurrence_of_archived_project_goes_to_inbox() {
+    let Some(txn) = begin().await else {
+        return;
+    };
+    let user_id = create_user(&txn, "recurrence_archived").await;
+    let project_id = create_project(&txn, user_id, true).await;
+    let parent = todo_list::ActiveModel {
+        user_id: Set(user_id),
+        title: Set(String::from("例行事务")),
+        project_id: Set(Some(project_id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let todo = create_daily(&txn, user_id, Some(project_id), Some(parent.id)).await;
+
+    let next = spawn_next_occurrence(&txn, &todo).await.unwrap().unwrap();
+    assert_eq!(next.project_id, None);
+    // 父任务仍在已归档的项目中，新实例改为顶层任务
+    assert_eq!(next.parent_id, None);
+    txn.rollback().await.unwrap();
+}
@@ -0,0 +1,216 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
+use todo_list_v1::domain::recurrence::RecurrenceRule;
+
+#[test]
+fn rejects_non_ascii_by_day() {
+    for rule in [
+        "FREQ=WEEKLY;BYDAY=中",
+        "FREQ=MONTHLY;BYDAY=1中",
+        "FREQ=WEEKLY;BYDAY=MO,周一",
+    ] {
+        assert!(rule.parse::<RecurrenceRule>().is_err(), "{rule}");
+    }
+}
+
+fn dt(s: &str) -> DateTimeWithTimeZone {
+    DateTimeWithTimeZone::parse_from_rfc3339(s).unwrap()
+}
+
+fn rule(s: &str) -> RecurrenceRule {
+    s.parse().unwrap()
+}
+
+/// 展开全部重复时刻（规则必须带 COUNT 或 UNTIL），只比较日期
+fn dates(rule_str: &str, dtstart: &str) -> Vec<String> {
+    rule(rule_str)
+        .occurrences(dt(dtstart))
+        .take(100)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect()
+}
+
+#[test]
+fn display_round_trips_normalized_rules() {
+    for s in [
+        "FREQ=DAILY",
+        "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE,FR;WKST=SU",
+        "FREQ=MONTHLY;COUNT=5;BYDAY=2TU,-1FR",
+        "FREQ=MONTHLY;UNTIL=20251231;BYMONTHDAY=1,-1",
+        "FREQ=YEARLY;UNTIL=20301231T160000Z;BYMONTH=2,8",
+        "FREQ=YEARLY;UNTIL=20301231T235959;BYDAY=20MO",
+    ] {
+        assert_eq!(rule(s).to_string(), s);
+        assert_eq!(rule(&rule(s).to_string()), rule(s));
+    }
+    assert_eq!(
+        rule("rrule:freq=weekly;interval=1;byday=mo, we;wkst=mo").to_string(),
+        "FREQ=WEEKLY;BYDAY=MO,WE"
+    );
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    for s in [
+        "",
+        "INTERVAL=2",
+        "FREQ=HOURLY",
+        "FREQ=DAILY;INTERVAL=0",
+        "FREQ=DAILY;COUNT=0",
+        "FREQ=DAILY;COUNT=2;UNTIL=20250101",
+        "FREQ=DAILY;UNTIL=2025-01-01",
+        "FREQ=WEEKLY;BYDAY=1MO",
+        "FREQ=WEEKLY;BYMONTHDAY=1",
+        "FREQ=MONTHLY;BYDAY=0MO",
+        "FREQ=MONTHLY;BYDAY=XX",
+        "FREQ=MONTHLY;BYMONTHDAY=32",
+        "FREQ=YEARLY;BYMONTH=13",
+        "FREQ=DAILY;BYSETPOS=1",
+    ] {
+        assert!(s.parse::<RecurrenceRule>().is_err(), "{s}");
+    }
+}
+
+#[test]
+fn count_includes_dtstart() {
+    assert_eq!(
+        dates("FREQ=DAILY;INTERVAL=2;COUNT=3", "2025-01-01T09:00:00+08:00"),
+        ["2025-01-01", "2025-01-03", "2025-01-05"]
+    );
+}
+
+#[test]
+fn until_is_inclusive() {
+    assert_eq!(
+        dates("FREQ=DAILY;UNTIL=20250103", "2025-01-01T09:00:00+08:00"),
+        ["2025-01-01", "2025-01-02", "2025-01-03"]
+    );
+    // 2025-01-03T01:00:00Z 是东八区 09:00，恰好在边界上
+    assert_eq!(
+        dates(
+            "FREQ=DAILY;UNTIL=20250103T010000Z",
+            "2025-01-01T09:00:00+08:00"
+        ),
+        ["2025-01-01", "2025-01-02", "2025-01-03"]
+    );
+    assert_eq!(
+        dates(
+            "FREQ=DAILY;UNTIL=20250102T085959",
+            "2025-01-01T09:00:00+08:00"
+        ),
+        ["2025-01-01"]
+    );
+}
+
+#[test]
+fn weekly_expands_each_weekday() {
+    assert_eq!(
+        dates(
+            "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5",
+            "2025-01-06T09:00:00+08:00"
+        ),
+        [
+            "2025-01-06",
+            "2025-01-08",
+            "2025-01-13",
+            "2025-01-15",
+            "2025-01-20"
+        ]
+    );
+}
+
+#[test]
+fn monthly_by_day_with_ordinals() {
+    // 2025 年 1 月的第二个周二是 14 日，最后一个周五是 31 日
+    assert_eq!(
+        dates(
+            "FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=5",
+            "2025-01-01T09:00:00+08:00"
+        ),
+        [
+            "2025-01-01",
+            "2025-01-14",
+            "2025-01-31",
+            "2025-02-11",
+            "2025-02-28"
+        ]
+    );
+}
+
+#[test]
+fn yearly_by_day_ordinal_counts_within_year() {
+    // 每年的第一个周一
+    assert_eq!(
+        dates("FREQ=YEARLY;BYDAY=1MO;COUNT=3", "2025-01-06T09:00:00+08:00"),
+        ["2025-01-06", "2026-01-05", "2027-01-04"]
+    );
+}
+
+#[test]
+fn negative_by_month_day_counts_from_month_end() {
+    assert_eq!(
+        dates(
+            "FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=4",
+            "2024-01-31T09:00:00+08:00"
+        ),
+        ["2024-01-31", "2024-02-29", "2024-03-31", "2024-04-30"]
+    );
+}
+
+#[test]
+fn skips_months_without_the_day() {
+    assert_eq!(
+        dates("FREQ=MONTHLY;COUNT=4", "2025-01-31T09:00:00+08:00"),
+        ["2025-01-31", "2025-03-31", "2025-05-31", "2025-07-31"]
+    );
+}
+
+#[test]
+fn feb_29_only_repeats_in_leap_years() {
+    assert_eq!(
+        dates("FREQ=YEARLY;COUNT=3", "2024-02-29T09:00:00+08:00"),
+        ["2024-02-29", "2028-02-29", "2032-02-29"]
+    );
+}
+
+#[test]
+fn yearly_by_month_day_expands_every_month() {
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYMONTHDAY=15;COUNT=4",
+            "2025-01-15T09:00:00+08:00"
+        ),
+        ["2025-01-15", "2025-02-15", "2025-03-15", "2025-04-15"]
+    );
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYMONTH=3;BYMONTHDAY=15;COUNT=3",
+            "2025-01-15T09:00:00+08:00"
+        ),
+        ["2025-01-15", "2025-03-15", "2026-03-15"]
+    );
+}
+
+#[test]
+fn impossible_rules_end_without_hanging() {
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30",
+            "2025-01-01T09:00:00+08:00"
+        ),
+        ["2025-01-01"]
+    );
+}
+
+#[test]
+fn next_after_keeps_time_of_day() {
+    let rule = rule("FREQ=WEEKLY;BYDAY=TU,TH");
+    let start = dt("2025-01-07T18:30:00+08:00");
+    assert_eq!(
+        rule.next_after(start, dt("2025-01-07T18:30:00+08:00")),
+        Some(dt("2025-01-09T18:30:00+08:00"))
+    );
+    assert_eq!(
+        rule.next_after(start, dt("2025-02-01T00:00:00+08:00")),
+        Some(dt("2025-02-04T18:30:00+08:00"))
+    );
+}
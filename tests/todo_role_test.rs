@@ -0,0 +1,33 @@
+use todo_list_v1::domain::todo_role::TodoRole;
+use todo_list_v1::handlers::todo::access::ensure_role;
+
+#[test]
+fn roles_are_ordered_by_permission() {
+    assert!(TodoRole::Viewer < TodoRole::Editor);
+    assert!(TodoRole::Editor < TodoRole::Owner);
+    assert_eq!(
+        [Some(TodoRole::Editor), None, Some(TodoRole::Viewer)]
+            .into_iter()
+            .max()
+            .flatten(),
+        Some(TodoRole::Editor)
+    );
+}
+
+#[test]
+fn role_round_trips_through_str() {
+    for role in [TodoRole::Viewer, TodoRole::Editor, TodoRole::Owner] {
+        assert_eq!(role.as_str().parse::<TodoRole>().unwrap(), role);
+    }
+    assert!("admin".parse::<TodoRole>().is_err());
+}
+
+#[test]
+fn ensure_role_requires_at_least_the_given_role() {
+    assert_eq!(
+        ensure_role(Some(TodoRole::Owner), TodoRole::Editor).unwrap(),
+        TodoRole::Owner
+    );
+    assert!(ensure_role(Some(TodoRole::Viewer), TodoRole::Editor).is_err());
+    assert!(ensure_role(None, TodoRole::Viewer).is_err());
+}
@@ -0,0 +1,82 @@
+use todo_list_v1::domain::todo_status::{TodoStatus, completed_at_for};
+use todo_list_v1::response::errors::ApiError;
+
+use TodoStatus::{Cancelled, Completed, InProgress, Pending};
+
+const ALL: [TodoStatus; 4] = [Pending, InProgress, Completed, Cancelled];
+
+/// 期望的流转结果：(from, to, 不重新打开时是否允许, 重新打开时是否允许)
+const TRANSITIONS: [(TodoStatus, TodoStatus, bool, bool); 16] = [
+    (Pending, Pending, false, false),
+    (Pending, InProgress, true, true),
+    (Pending, Completed, true, true),
+    (Pending, Cancelled, true, true),
+    (InProgress, Pending, true, true),
+    (InProgress, InProgress, false, false),
+    (InProgress, Completed, true, true),
+    (InProgress, Cancelled, true, true),
+    (Completed, Pending, false, true),
+    (Completed, InProgress, false, true),
+    (Completed, Completed, false, false),
+    (Completed, Cancelled, false, false),
+    (Cancelled, Pending, false, true),
+    (Cancelled, InProgress, false, true),
+    (Cancelled, Completed, false, false),
+    (Cancelled, Cancelled, false, false),
+];
+
+#[test]
+fn transition_table_covers_every_pair() {
+    for from in ALL {
+        for to in ALL {
+            assert!(
+                TRANSITIONS.iter().any(|(f, t, ..)| *f == from && *t == to),
+                "缺少 {from} -> {to}"
+            );
+        }
+    }
+}
+
+#[test]
+fn checks_every_transition() {
+    for (from, to, plain, reopen) in TRANSITIONS {
+        assert_eq!(
+            from.check_transition(to, false).is_ok(),
+            plain,
+            "{from} -> {to}"
+        );
+        assert_eq!(
+            from.check_transition(to, true).is_ok(),
+            reopen,
+            "{from} -> {to} (reopen)"
+        );
+    }
+}
+
+#[test]
+fn illegal_transition_reports_both_states() {
+    match Completed.check_transition(Cancelled, true) {
+        Err(ApiError::IllegalStatusTransition { from, to }) => {
+            assert_eq!((from, to), ("completed", "cancelled"));
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn status_round_trips_through_str() {
+    for status in ALL {
+        assert_eq!(status.as_str().parse::<TodoStatus>().unwrap(), status);
+    }
+    assert!("done".parse::<TodoStatus>().is_err());
+    assert_eq!(TodoStatus::from_db(None), Pending);
+    assert_eq!(TodoStatus::from_db(Some("unknown")), Pending);
+}
+
+#[test]
+fn only_completed_has_completed_at() {
+    assert!(completed_at_for(Completed).is_some());
+    for status in [Pending, InProgress, Cancelled] {
+        assert!(completed_at_for(status).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::NaiveDate;
+use todo_list_v1::domain::todo_status::TodoStatus;
+use todo_list_v1::domain::todo_txt::{TodoTxtTask, parse_todo_txt, write_todo_txt};
+use todo_list_v1::handlers::todo::model::TodoPriority;
+
+fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(y, m, d)
+}
+
+fn task(title: &str) -> TodoTxtTask {
+    TodoTxtTask {
+        status: TodoStatus::Pending,
+        priority: None,
+        completion_date: None,
+        creation_date: None,
+        title: title.to_string(),
+        tags: vec![],
+        due: None,
+        uid: None,
+    }
+}
+
+#[test]
+fn parses_priority_dates_tags_and_due() {
+    let parsed =
+        TodoTxtTask::parse("(B) 2025-03-01 Call mom +Family @phone due:2025-03-05").unwrap();
+    assert_eq!(
+        parsed,
+        TodoTxtTask {
+            priority: Some(TodoPriority::High),
+            creation_date: date(2025, 3, 1),
+            tags: vec!["Family".into(), "@phone".into()],
+            due: date(2025, 3, 5),
+            ..task("Call mom")
+        }
+    );
+}
+
+#[test]
+fn maps_priority_letters() {
+    let priority = |line: &str| TodoTxtTask::parse(line).unwrap().priority;
+    assert_eq!(priority("(A) a"), Some(TodoPriority::Urgent));
+    assert_eq!(priority("(B) a"), Some(TodoPriority::High));
+    assert_eq!(priority("(C) a"), Some(TodoPriority::Medium));
+    assert_eq!(priority("(D) a"), Some(TodoPriority::Low));
+    assert_eq!(priority("(Z) a"), Some(TodoPriority::Low));
+    assert_eq!(priority("(a) a"), None);
+    assert_eq!(priority("a (A)"), None);
+}
+
+#[test]
+fn parses_completed_tasks() {
+    let parsed = TodoTxtTask::parse("x 2025-03-02 2025-03-01 Review PR +work pri:A").unwrap();
+    assert_eq!(parsed.status, TodoStatus::Completed);
+    assert_eq!(parsed.completion_date, date(2025, 3, 2));
+    assert_eq!(parsed.creation_date, date(2025, 3, 1));
+    assert_eq!(parsed.priority, Some(TodoPriority::Urgent));
+    assert_eq!(parsed.title, "Review PR");
+
+    // 只有一个日期时是完成日期
+    let parsed = TodoTxtTask::parse("x 2025-03-02 Review PR").unwrap();
+    assert_eq!(parsed.completion_date, date(2025, 3, 2));
+    assert_eq!(parsed.creation_date, None);
+
+    // 小写 x 后面必须有空格，否则是标题的一部分
+    let parsed = TodoTxtTask::parse("xylophone lessons").unwrap();
+    assert_eq!(parsed.status, TodoStatus::Pending);
+    assert_eq!(parsed.title, "xylophone lessons");
+}
+
+#[test]
+fn keeps_unknown_key_values_in_title() {
+    let parsed = TodoTxtTask::parse("Read https://example.com t:2025-01-01").unwrap();
+    assert_eq!(parsed.title, "Read https://example.com t:2025-01-01");
+}
+
+#[test]
+fn rejects_invalid_lines() {
+    assert!(TodoTxtTask::parse("Pay rent due:tomorrow").is_err());
+    assert!(TodoTxtTask::parse("(A) +project @context").is_err());
+    assert!(TodoTxtTask::parse("Pay rent status:unknown").is_err());
+}
+
+#[test]
+fn serializes_canonical_lines_unchanged() {
+    let lines = [
+        "(A) 2025-03-01 Call mom +Family @phone due:2025-03-05 uid:abc",
+        "(C) Plan trip",
+        "x 2025-03-02 2025-03-01 Review PR +work pri:B",
+        "x 2025-03-02 Old task status:cancelled",
+        "(D) Write report @office status:in_progress",
+        "x Done without dates",
+    ];
+    for line in lines {
+        let parsed = TodoTxtTask::parse(line).unwrap();
+        assert_eq!(parsed.to_string(), line);
+    }
+}
+
+#[test]
+fn round_trips_tasks() {
+    let tasks = vec![
+        TodoTxtTask {
+            priority: Some(TodoPriority::Urgent),
+            creation_date: date(2024, 12, 31),
+            tags: vec!["home".into(), "@errands".into()],
+            due: date(2025, 1, 15),
+            uid: Some("0b1c4a7e-uid".into()),
+            ..task("Buy milk")
+        },
+        TodoTxtTask {
+            status: TodoStatus::Completed,
+            priority: Some(TodoPriority::Low),
+            completion_date: date(2025, 2, 1),
+            creation_date: date(2025, 1, 1),
+            ..task("Finish taxes")
+        },
+        TodoTxtTask {
+            status: TodoStatus::Cancelled,
+            priority: Some(TodoPriority::Medium),
+            ..task("Cancelled idea")
+        },
+        TodoTxtTask {
+            status: TodoStatus::InProgress,
+            priority: Some(TodoPriority::High),
+            tags: vec!["项目".into()],
+            ..task("写周报")
+        },
+    ];
+    let text = write_todo_txt(&tasks);
+    let parsed: Vec<TodoTxtTask> = parse_todo_txt(&text)
+        .into_iter()
+        .map(|(_, task)| task.unwrap())
+        .collect();
+    assert_eq!(parsed, tasks);
+}
+
+#[test]
+fn reports_line_numbers_and_skips_blank_lines() {
+    let parsed = parse_todo_txt("(A) first\n\n   \nsecond due:bad\r\nthird\n");
+    let lines: Vec<u64> = parsed.iter().map(|(line, _)| *line).collect();
+    assert_eq!(lines, vec![1, 4, 5]);
+    assert!(parsed[0].1.is_ok());
+    assert!(parsed[1].1.is_err());
+    assert_eq!(parsed[2].1.as_ref().unwrap().title, "third");
+}
+
+#[test]
+fn replaces_whitespace_in_tags() {
+    let line = TodoTxtTask {
+        tags: vec!["side project".into()],
+        ..task("Ship it")
+    }
+    .to_string();
+    assert_eq!(line, "Ship it +side_project");
+}
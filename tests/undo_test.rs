@@ -0,0 +1,116 @@
+use chrono::Utc;
+use serde_json::{Value, json};
+use todo_list_v1::entities::todo_history;
+use todo_list_v1::handlers::todo::undo::UndoStep;
+
+fn row(id: i64, todo_id: i32, revision: i32, action: &str, changes: Value) -> todo_history::Model {
+    todo_history::Model {
+        id,
+        todo_id,
+        user_id: 1,
+        revision,
+        action: action.to_string(),
+        changes,
+        snapshot: json!({}),
+        actor_id: Some(1),
+        request_id: Some("req".to_string()),
+        created_at: Utc::now().fixed_offset(),
+    }
+}
+
+#[test]
+fn merges_changes_of_the_same_todo() {
+    let rows = vec![
+        row(
+            1,
+            7,
+            3,
+            "update",
+            json!({"title": {"old": "a", "new": "b"}}),
+        ),
+        row(
+            2,
+            8,
+            1,
+            "update",
+            json!({"tags": {"old": ["x"], "new": ["y"]}}),
+        ),
+        row(
+            3,
+            7,
+            4,
+            "update",
+            json!({"title": {"old": "b", "new": "c"}, "status": {"old": "pending", "new": "completed"}}),
+        ),
+    ];
+    let step = UndoStep::from_history("req".to_string(), &rows);
+    assert_eq!(step.changes.len(), 2);
+    let first = &step.changes[0];
+    assert_eq!(
+        (first.todo_id, first.revision, first.created),
+        (7, 4, false)
+    );
+    assert_eq!(first.before["title"], json!("a"));
+    assert_eq!(first.after["title"], json!("c"));
+    assert_eq!(first.before["status"], json!("pending"));
+    assert_eq!(step.changes[1].todo_id, 8);
+    assert_eq!(step.changes[1].before["tags"], json!(["x"]));
+}
+
+#[test]
+fn ignores_system_fields_and_reverted_changes() {
+    let rows = vec![
+        row(
+            1,
+            7,
+            2,
+            "update",
+            json!({"title": {"old": "a", "new": "b"}, "ical_uid": {"old": "u1", "new": "u2"}}),
+        ),
+        row(
+            2,
+            7,
+            3,
+            "update",
+            json!({"title": {"old": "b", "new": "a"}}),
+        ),
+        row(
+            3,
+            9,
+            5,
+            "update",
+            json!({"caldav_name": {"old": null, "new": "x.ics"}}),
+        ),
+    ];
+    let step = UndoStep::from_history("req".to_string(), &rows);
+    assert!(step.changes.is_empty());
+}
+
+#[test]
+fn keeps_created_todos() {
+    let rows = vec![row(
+        1,
+        7,
+        1,
+        "create",
+        json!({"title": {"old": null, "new": "new"}, "user_id": {"old": null, "new": 1}}),
+    )];
+    let step = UndoStep::from_history("req".to_string(), &rows);
+    assert_eq!(step.changes.len(), 1);
+    assert!(step.changes[0].created);
+    assert!(!step.changes[0].after.contains_key("user_id"));
+}
+
+#[test]
+fn step_round_trips_through_json() {
+    let rows = vec![row(
+        1,
+        7,
+        2,
+        "delete",
+        json!({"deleted_at": {"old": null, "new": "2025-12-01T02:00:00+00:00"}}),
+    )];
+    let step = UndoStep::from_history("req".to_string(), &rows);
+    let text = serde_json::to_string(&step).unwrap();
+    assert_eq!(serde_json::from_str::<UndoStep>(&text).unwrap(), step);
+}